@@ -1,12 +1,15 @@
+use clap::builder::NonEmptyStringValueParser;
 use clap::error::ErrorKind;
-use clap::{CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use log::debug;
+use std::fs;
+use std::path::Path;
 
 use crate::business::error::Error;
 use crate::business::handler::*;
 
 /// A simple-to-use converter prototype.
-/// It converts MSG files to DTP files and vice versa.
+/// It converts MSG, SRV, ACTION and IDL files to DTP files and vice versa.
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
@@ -16,7 +19,9 @@ struct Cli {
 
 #[derive(Subcommand, Debug)]
 enum Command {
-    /// Converts a MSG file to a DTP file
+    /// Converts a MSG file to a DTP file, a SRV file to a request and a response DTP file
+    /// or an ACTION file to a goal, a result and a feedback DTP file.
+    /// IDL files are split up according to the interface they contain
     ConvertToDtp {
         /// The file to convert
         #[arg(short = 'f', long = "file")]
@@ -24,11 +29,43 @@ enum Command {
         /// The directory where the conversion result will be written
         #[arg(short = 'd', long = "destination-directory")]
         path_to_destination_directory: String,
-        /// The name of the package the source file belongs to
-        #[arg(short = 'p', long = "package-name")]
-        package_name: String,
+        #[command(flatten)]
+        package: Package,
+        #[command(flatten)]
+        sources: Sources,
+        #[command(flatten)]
+        mapping: Mapping,
+    },
+    /// Converts every MSG, SRV and ACTION file in the "msg", "srv" and "action" folders
+    /// of a ROS 2 package into "<destination>/<package name>/<folder>/"
+    ConvertPackage {
+        /// The directory containing the package.xml
+        #[arg(short = 'f', long = "package-directory")]
+        path_to_package_directory: String,
+        /// The directory of the 4diac type library
+        #[arg(short = 'd', long = "destination-directory")]
+        path_to_destination_directory: String,
+        #[command(flatten)]
+        sources: Sources,
+        #[command(flatten)]
+        mapping: Mapping,
+    },
+    /// Converts every package of a colcon workspace like "convert-package" does.
+    /// Directories containing a COLCON_IGNORE or AMENT_IGNORE file are skipped
+    ConvertWorkspace {
+        /// The root directory of the workspace
+        #[arg(short = 'f', long = "workspace-directory")]
+        path_to_workspace_directory: String,
+        /// The directory of the 4diac type library
+        #[arg(short = 'd', long = "destination-directory")]
+        path_to_destination_directory: String,
+        #[command(flatten)]
+        sources: Sources,
+        #[command(flatten)]
+        mapping: Mapping,
     },
-    /// Converts a DTP file to a MSG file
+    /// Converts a DTP file to a MSG file, a request/response DTP file pair to a SRV file
+    /// or a goal/result/feedback DTP file triple to an ACTION file
     ConvertToMsg {
         /// The file to convert
         #[arg(short = 'f', long = "file")]
@@ -36,11 +73,68 @@ enum Command {
         /// The directory where the conversion result will be written
         #[arg(short = 'd', long = "destination-directory")]
         path_to_destination_directory: String,
-        /// The name of the package the destination file will belong to
+        #[command(flatten)]
+        package: Package,
+        /// How directly derived types and array types are converted
+        #[arg(long = "derived-types", value_enum, default_value_t = DerivedTypes::Wrap)]
+        derived_types: DerivedTypes,
+        #[command(flatten)]
+        mapping: Mapping,
+    },
+    /// Converts every DTP file below a 4diac type library folder into a ROS 2 interface
+    /// package "<destination>/<package name>/" including its package.xml and CMakeLists.txt
+    ConvertTypelib {
+        /// The type library folder, which is searched recursively
+        #[arg(short = 'f', long = "typelib-directory")]
+        path_to_typelib_directory: String,
+        /// The directory where the package will be created
+        #[arg(short = 'd', long = "destination-directory")]
+        path_to_destination_directory: String,
+        /// The name of the package to create
         #[arg(short = 'p', long = "package-name")]
         package_name: String,
+        /// How directly derived types and array types are converted
+        #[arg(long = "derived-types", value_enum, default_value_t = DerivedTypes::Wrap)]
+        derived_types: DerivedTypes,
+        #[command(flatten)]
+        mapping: Mapping,
     },
-    /// Print msg file data structure
+    /// Converts a DTP file, a request/response DTP file pair or a goal/result/feedback
+    /// DTP file triple to an IDL file
+    ConvertToIdl {
+        /// The file to convert
+        #[arg(short = 'f', long = "file")]
+        path_to_dtp_file: String,
+        /// The directory where the conversion result will be written
+        #[arg(short = 'd', long = "destination-directory")]
+        path_to_destination_directory: String,
+        #[command(flatten)]
+        package: Package,
+        /// How directly derived types and array types are converted
+        #[arg(long = "derived-types", value_enum, default_value_t = DerivedTypes::Wrap)]
+        derived_types: DerivedTypes,
+        #[command(flatten)]
+        mapping: Mapping,
+    },
+    /// Converts the definitions of std_msgs, geometry_msgs, sensor_msgs and builtin_interfaces
+    /// embedded into the converter into a 4diac type library "<destination>/<package name>/<folder>/"
+    ExportBundledInterfaces {
+        /// The directory of the 4diac type library
+        #[arg(short = 'd', long = "destination-directory")]
+        path_to_destination_directory: String,
+        #[command(flatten)]
+        mapping: Mapping,
+    },
+    /// Rewrites a DTP file in the layout the 4diac IDE saves it
+    FormatDtp {
+        /// The file to format
+        #[arg(short = 'f', long = "file")]
+        path_to_dtp_file: String,
+        /// The directory where the formatted file will be written
+        #[arg(short = 'd', long = "destination-directory")]
+        path_to_destination_directory: String,
+    },
+    /// Print msg, srv, action or idl file data structure
     PrintMsg {
         /// The file to read
         #[arg(short = 'f', long = "file")]
@@ -56,6 +150,154 @@ enum Command {
     Test,
 }
 
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum DerivedTypes {
+    /// Convert each derived type to a message with a single field "value"
+    Wrap,
+    /// Use the underlying type wherever a derived type is used
+    Inline,
+}
+
+// Every DTP file belongs to the package of its MSG, SRV or ACTION file
+#[derive(Args, Debug)]
+struct Package {
+    /// The name of the package the MSG, SRV or ACTION file belongs to. Defaults to the name
+    /// in the nearest package.xml above the source file when converting to DTP,
+    /// otherwise above the destination directory
+    #[arg(short = 'p', long = "package-name", value_name = "PACKAGE_NAME")]
+    name: Option<String>,
+}
+
+// Which MSG, SRV and ACTION files are converted and how
+#[derive(Args, Debug)]
+struct Sources {
+    /// Also generate the types rosidl implicitly derives from an action,
+    /// e.g. "<Name>_SendGoal_Request" or "<Name>_FeedbackMessage",
+    /// together with "GoalInfo" and "GoalStatus" of action_msgs
+    #[arg(long = "with-implicit-action-types")]
+    with_implicit_action_types: bool,
+    /// Embed the source of every MSG file into its data types, so that the converted
+    /// MSG file keeps the original layout, e.g. comments and blank lines
+    #[arg(long = "keep-msg-source")]
+    keep_msg_source: bool,
+    /// Also convert the messages the interfaces reference directly or indirectly
+    #[arg(long = "resolve-dependencies")]
+    resolve_dependencies: bool,
+    /// A directory, which is searched for the packages of referenced messages.
+    /// May be given several times
+    #[arg(short = 's', long = "search-path", requires = "resolve_dependencies")]
+    search_paths: Vec<String>,
+    /// An install prefix, whose ament index lists the packages of referenced messages.
+    /// May be given several times and is searched before the ones in AMENT_PREFIX_PATH
+    #[arg(long = "prefix", requires = "resolve_dependencies")]
+    prefixes: Vec<String>,
+    /// Do not fall back to the definitions of std_msgs, geometry_msgs, sensor_msgs
+    /// and builtin_interfaces embedded into the converter
+    #[arg(long = "without-bundled-interfaces", requires = "resolve_dependencies")]
+    without_bundled_interfaces: bool,
+}
+
+impl Sources {
+    fn search_paths(&self) -> Option<SearchPaths> {
+        self.resolve_dependencies.then(|| {
+            SearchPaths::new(
+                &self.search_paths,
+                &self.prefixes,
+                !self.without_bundled_interfaces,
+            )
+        })
+    }
+}
+
+// How the types of ROS 2 and 4diac are mapped onto each other, the conversion back has to use the same
+#[derive(Args, Debug)]
+struct Mapping {
+    /// The name of the generated data types made of the placeholders {package},
+    /// {pkg} (without underscores, spaces and dashes), {Pkg} (PascalCase), {kind} and {Type}.
+    /// A package of 4diac IDE 3 is separated by "::", e.g. "ros2::{package}::{Type}"
+    #[arg(
+        long = "type-name-template",
+        value_parser = parse_type_name_template,
+        default_value = DEFAULT_TEMPLATE
+    )]
+    type_name_template: TypeNameTemplate,
+    /// A TOML or YAML file mapping the primitive types and naming the annotations.
+    /// Defaults to the nearest ros2-4diac-converter.toml, .yaml or .yml above the source,
+    /// for exported interfaces above the destination
+    #[arg(long = "profile", value_parser = parse_mapping_profile)]
+    mapping_profile: Option<MappingProfile>,
+    #[command(flatten)]
+    dynamic_arrays: DynamicArrays,
+}
+
+// How sequences of ROS 2 are represented in 4diac, the conversion back has to use the same
+#[derive(Args, Debug)]
+struct DynamicArrays {
+    /// The capacity of the fixed arrays unbounded sequences become.
+    /// "@IEC61499_Capacity(<n>)" overrides it per field
+    #[arg(
+        long = "dynamic-array-capacity",
+        value_parser = clap::value_parser!(u64).range(1..),
+        default_value_t = DEFAULT_CAPACITY as u64
+    )]
+    capacity: u64,
+    /// Convert sequences to arrays of variable size (ArraySize="*") instead of fixed arrays.
+    /// "@IEC61499_Capacity(*)" does so per field
+    #[arg(long = "variable-size-arrays")]
+    variable_size: bool,
+    /// Do not add a variable counting the elements of a fixed array. "@IEC61499_ElementCounter"
+    /// and "@IEC61499_NoElementCounter" override it per field
+    #[arg(long = "without-element-counter")]
+    without_element_counter: bool,
+    /// The suffix of the name of the variable counting the elements.
+    /// "@IEC61499_ElementCounter(<name>)" names it per field
+    #[arg(
+        long = "element-counter-suffix",
+        value_parser = NonEmptyStringValueParser::new(),
+        default_value = DEFAULT_ELEMENT_COUNTER_SUFFIX
+    )]
+    element_counter_suffix: String,
+}
+
+fn parse_type_name_template(template: &str) -> Result<TypeNameTemplate, String> {
+    TypeNameTemplate::parse(template).map_err(|error| error.to_string())
+}
+
+fn parse_mapping_profile(path_to_file: &str) -> Result<MappingProfile, String> {
+    MappingProfile::load(path_to_file).map_err(|error| error.to_string())
+}
+
+impl From<DerivedTypes> for DerivedTypeStrategy {
+    fn from(derived_types: DerivedTypes) -> Self {
+        match derived_types {
+            DerivedTypes::Wrap => DerivedTypeStrategy::Wrap,
+            DerivedTypes::Inline => DerivedTypeStrategy::Inline,
+        }
+    }
+}
+
+impl From<Mapping> for ConversionOptions {
+    fn from(mapping: Mapping) -> Self {
+        ConversionOptions {
+            type_name_template: mapping.type_name_template,
+            dynamic_array_strategy: mapping.dynamic_arrays.into(),
+            mapping_profile: mapping.mapping_profile,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<DynamicArrays> for DynamicArrayStrategy {
+    fn from(dynamic_arrays: DynamicArrays) -> Self {
+        DynamicArrayStrategy {
+            capacity: dynamic_arrays.capacity as usize,
+            variable_size: dynamic_arrays.variable_size,
+            element_counter: !dynamic_arrays.without_element_counter,
+            element_counter_suffix: dynamic_arrays.element_counter_suffix,
+        }
+    }
+}
+
 pub fn run() {
     let cli = Cli::parse();
 
@@ -64,73 +306,122 @@ pub fn run() {
         Command::ConvertToDtp {
             path_to_msg_file,
             path_to_destination_directory,
-            package_name,
-        } => convert_to_dtp(
-            &path_to_msg_file,
+            package,
+            sources,
+            mapping,
+        } => {
+            let options = ConversionOptions {
+                keep_msg_source: sources.keep_msg_source,
+                ..mapping.into()
+            };
+            match sources.search_paths() {
+                Some(search_paths) => convert_to_dtp_with_dependencies(
+                    &path_to_msg_file,
+                    &path_to_destination_directory,
+                    package.name.as_deref(),
+                    sources.with_implicit_action_types,
+                    &search_paths,
+                    &options,
+                ),
+                None => convert_to_dtp(
+                    &path_to_msg_file,
+                    &path_to_destination_directory,
+                    package.name.as_deref(),
+                    sources.with_implicit_action_types,
+                    &options,
+                ),
+            }
+        }
+        Command::ConvertPackage {
+            path_to_package_directory,
+            path_to_destination_directory,
+            sources,
+            mapping,
+        } => convert_package_to_dtp(
+            &path_to_package_directory,
             &path_to_destination_directory,
-            &package_name,
-        ),
+            sources.with_implicit_action_types,
+            sources.search_paths().as_ref(),
+            &ConversionOptions {
+                keep_msg_source: sources.keep_msg_source,
+                ..mapping.into()
+            },
+        )
+        .and_then(print_summary),
+        Command::ConvertWorkspace {
+            path_to_workspace_directory,
+            path_to_destination_directory,
+            sources,
+            mapping,
+        } => convert_workspace_to_dtp(
+            &path_to_workspace_directory,
+            &path_to_destination_directory,
+            sources.with_implicit_action_types,
+            sources.search_paths().as_ref(),
+            &ConversionOptions {
+                keep_msg_source: sources.keep_msg_source,
+                ..mapping.into()
+            },
+        )
+        .and_then(print_summary),
         Command::ConvertToMsg {
             path_to_dtp_file,
             path_to_destination_directory,
-            package_name,
+            package,
+            derived_types,
+            mapping,
         } => convert_to_msg(
             &path_to_dtp_file,
             &path_to_destination_directory,
-            &package_name,
+            package.name.as_deref(),
+            &ConversionOptions {
+                derived_type_strategy: derived_types.into(),
+                ..mapping.into()
+            },
         ),
+        Command::ConvertToIdl {
+            path_to_dtp_file,
+            path_to_destination_directory,
+            package,
+            derived_types,
+            mapping,
+        } => convert_to_idl(
+            &path_to_dtp_file,
+            &path_to_destination_directory,
+            package.name.as_deref(),
+            &ConversionOptions {
+                derived_type_strategy: derived_types.into(),
+                ..mapping.into()
+            },
+        ),
+        Command::ConvertTypelib {
+            path_to_typelib_directory,
+            path_to_destination_directory,
+            package_name,
+            derived_types,
+            mapping,
+        } => convert_typelib_to_package(
+            &path_to_typelib_directory,
+            &path_to_destination_directory,
+            &package_name,
+            &ConversionOptions {
+                derived_type_strategy: derived_types.into(),
+                ..mapping.into()
+            },
+        )
+        .and_then(print_summary),
+        Command::ExportBundledInterfaces {
+            path_to_destination_directory,
+            mapping,
+        } => export_bundled_interfaces(&path_to_destination_directory, &mapping.into())
+            .and_then(print_summary),
+        Command::FormatDtp {
+            path_to_dtp_file,
+            path_to_destination_directory,
+        } => format_dtp(&path_to_dtp_file, &path_to_destination_directory),
         Command::PrintMsg { path_to_msg_file } => print_msg(&path_to_msg_file),
         Command::PrintDtp { path_to_dtp_file } => print_dtp(&path_to_dtp_file),
-        Command::Test => {
-            // hin
-            let _ = convert_to_msg("test/0-dtp/Iec61499Arrayspezifikationen1.dtp", "test/1-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/0-dtp/Iec61499Arrayspezifikationen2.dtp", "test/1-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/0-dtp/Iec61499PrimitiveDatentypen.dtp", "test/1-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/0-dtp/Iec61499Referenzen.dtp", "test/1-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/0-dtp/Iec61499Standardwertliterale1.dtp", "test/1-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/0-dtp/Iec61499Standardwertliterale2.dtp", "test/1-msg/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen1.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen2.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen3.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen4.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen5.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen6.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Konstanten.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2PrimitiveDatentypen.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Referenzen.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale1.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale2.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale3.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale4.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale5.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale6.msg", "test/1-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale7.msg", "test/1-dtp/", "conversion_tests");
-            
-            // zurück
-            let _ = convert_to_dtp("test/1-msg/Iec61499Arrayspezifikationen1.msg", "test/2-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/1-msg/Iec61499Arrayspezifikationen2.msg", "test/2-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/1-msg/Iec61499PrimitiveDatentypen.msg", "test/2-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/1-msg/Iec61499Referenzen.msg", "test/2-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/1-msg/Iec61499Standardwertliterale1.msg", "test/2-dtp/", "conversion_tests");
-            let _ = convert_to_dtp("test/1-msg/Iec61499Standardwertliterale2.msg", "test/2-dtp/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen1.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen2.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen3.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen4.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen5.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen6.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Konstanten.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2PrimitiveDatentypen.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Referenzen.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale1.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale2.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale3.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale4.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale5.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale6.dtp", "test/2-msg/", "conversion_tests");
-            let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale7.dtp", "test/2-msg/", "conversion_tests");
-            Ok(())
-        }
+        Command::Test => run_tests(),
     };
 
     if let Err(error) = result {
@@ -142,9 +433,198 @@ pub fn run() {
                     Error::DtpReader(_) => ErrorKind::Format,
                     Error::MsgReader(_) => ErrorKind::Format,
                     Error::DtpWriter(_) => ErrorKind::Io,
+                    Error::TomlReader(_) => ErrorKind::Format,
+                    Error::YamlReader(_) => ErrorKind::Format,
                 },
                 error,
             )
             .exit();
     }
 }
+
+// Converts the files in "test" forth and back, see README
+#[rustfmt::skip]
+fn run_tests() -> Result<(), Error> {
+    let options = ConversionOptions { keep_msg_source: true, ..Default::default() };
+    let inline_options = ConversionOptions { derived_type_strategy: DerivedTypeStrategy::Inline, ..Default::default() };
+    // hin
+    let _ = convert_to_msg("test/0-dtp/Iec61499Arrayspezifikationen1.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Arrayspezifikationen2.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499PrimitiveDatentypen.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Referenzen.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Typnamen.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Motor_Result.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Aufzaehlung.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Teilbereich1.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Teilbereich2.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Geschwindigkeit.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Messreihe.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Bezeichner.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499DynamischeArrays.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499AbgeleiteteTypen.dtp", "test/1-msg/", Some("conversion_tests"), &inline_options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Strukturinitialisierung.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Standardwertliterale1.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Standardwertliterale2.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/0-dtp/Iec61499Standardwertliterale3.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen1.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen2.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen3.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen4.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen5.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Arrayspezifikationen6.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Konstanten.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2PrimitiveDatentypen.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Referenzen.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Paketverweise.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Aufzaehlung.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Teilbereich1.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Teilbereich2.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Temperatur.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Gelenkpositionen.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale1.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale2.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale3.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale4.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale5.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale6.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Standardwertliterale7.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Zeichenketten.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Kommentare.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Layout.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2Bezeichner.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-msg/Ros2DynamischeArrays.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-srv/Ros2Dienst1.srv", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-srv/Ros2Dienst2.srv", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-action/Ros2Aktion1.action", "test/1-dtp/", None, true, &options);
+    let _ = convert_to_dtp("test/0-idl/Ros2Schnittstelle1.idl", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/0-idl/Ros2Schnittstelle2.idl", "test/1-dtp/", None, false, &options);
+    
+    // zurück
+    let _ = convert_to_dtp("test/1-msg/Iec61499Arrayspezifikationen1.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Arrayspezifikationen2.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499PrimitiveDatentypen.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Referenzen.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Typnamen.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Aufzaehlung.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Teilbereich1.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Teilbereich2.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Geschwindigkeit.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Messreihe.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Bezeichner.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499DynamischeArrays.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499AbgeleiteteTypen.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Strukturinitialisierung.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Standardwertliterale1.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Standardwertliterale2.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_dtp("test/1-msg/Iec61499Standardwertliterale3.msg", "test/2-dtp/", None, false, &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen1.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen2.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen3.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen4.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen5.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Arrayspezifikationen6.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Konstanten.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2PrimitiveDatentypen.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Referenzen.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Paketverweise.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Aufzaehlung.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Teilbereich1.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Teilbereich2.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Temperatur.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Gelenkpositionen.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale1.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale2.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale3.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale4.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale5.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale6.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Standardwertliterale7.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Zeichenketten.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Kommentare.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Layout.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Bezeichner.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2DynamischeArrays.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_srv_Ros2Dienst1_Request.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_srv_Ros2Dienst2_Request.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_action_Ros2Aktion1_Goal.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+    let _ = convert_to_idl("test/1-dtp/ROS2_conversiontests_msg_Ros2Schnittstelle1.dtp", "test/2-idl/", Some("conversion_tests"), &options);
+    let _ = convert_to_idl("test/1-dtp/ROS2_conversiontests_msg_Ros2Zeichenketten.dtp", "test/2-idl/", Some("conversion_tests"), &options);
+    let _ = convert_to_idl("test/1-dtp/ROS2_conversiontests_srv_Ros2Schnittstelle2_Request.dtp", "test/2-idl/", Some("conversion_tests"), &options);
+    // Files in the layout of the 4diac IDE have to be reproduced byte by byte
+    let formatted = [
+        "test/0-4diac/Iec61499Betriebsart.dtp",
+        "test/0-4diac/Iec61499Bezeichnung.dtp",
+        "test/0-4diac/Iec61499Matrix.dtp",
+        "test/0-4diac/Iec61499Metadaten.dtp",
+        "test/0-4diac/Iec61499Prozent.dtp",
+        "test/0-4diac/Iec61499Sonderzeichen.dtp",
+    ]
+    .into_iter()
+    .try_for_each(|path_to_dtp_file| format_and_compare_dtp(path_to_dtp_file, "test/2-4diac/"));
+    let _ = convert_workspace_to_dtp("test/0-workspace", "test/1-workspace", false, None, &options);
+    let _ = convert_workspace_to_dtp("test/0-workspace", "test/1-workspace-dependencies", false, Some(&SearchPaths { source_directories: Vec::new(), prefixes: vec!["test/0-prefix".to_string()], bundled_interfaces: true }), &options);
+    let _ = export_bundled_interfaces("test/1-bundled", &options);
+    let _ = convert_to_dtp_with_dependencies("test/0-workspace/src/foerderband_msgs/action/Ros2Transport.action", "test/1-dtp/", None, false, &SearchPaths { source_directories: vec!["test/0-workspace".to_string()], prefixes: vec!["test/0-prefix".to_string()], bundled_interfaces: false }, &options);
+    let _ = convert_typelib_to_package("test/0-typelib", "test/1-typelib", "foerderband_msgs", &inline_options);
+    let array_options = ConversionOptions { dynamic_array_strategy: DynamicArrayStrategy { capacity: 5, variable_size: false, element_counter: false, element_counter_suffix: "_count".to_string() }, keep_msg_source: true, ..Default::default() };
+    let _ = convert_to_dtp("test/0-msg/Ros2ArrayStrategie.msg", "test/1-dtp/", None, false, &array_options);
+    let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2ArrayStrategie.dtp", "test/2-msg/", Some("conversion_tests"), &array_options);
+    // The profile is found next to the source or given explicitly
+    let _ = convert_to_dtp("test/0-profil/Ros2Profil.msg", "test/1-dtp/", None, false, &options);
+    let _ = convert_to_msg("test/0-profil/Iec61499Profil.dtp", "test/1-msg/", Some("conversion_tests"), &options);
+    if let Ok(mapping_profile) = MappingProfile::load("test/0-profil/profil.yaml") {
+        let options = ConversionOptions { mapping_profile: Some(mapping_profile), ..Default::default() };
+        let _ = convert_to_msg("test/1-dtp/ROS2_conversiontests_msg_Ros2Profil.dtp", "test/2-msg/", Some("conversion_tests"), &options);
+        let _ = convert_to_dtp("test/1-msg/Iec61499Profil.msg", "test/2-dtp/", None, false, &options);
+    }
+    if let Ok(type_name_template) = TypeNameTemplate::parse("{Pkg}{Type}_T") {
+        let options = ConversionOptions { type_name_template, ..Default::default() };
+        let _ = convert_workspace_to_dtp("test/0-workspace", "test/1-workspace-vorlage", false, None, &options);
+        let _ = convert_typelib_to_package("test/0-typelib-vorlage", "test/1-typelib-vorlage", "foerderband_msgs", &options);
+    }
+    if let Ok(type_name_template) = TypeNameTemplate::parse("ros2::{package}::{Type}") {
+        let options = ConversionOptions { type_name_template, ..Default::default() };
+        let _ = convert_package_to_dtp("test/0-workspace/src/foerderband_msgs", "test/1-package-4diac3", false, None, &options);
+        let _ = convert_typelib_to_package("test/1-package-4diac3/foerderband_msgs", "test/2-package-4diac3", "foerderband_msgs", &options);
+    }
+    formatted
+}
+
+// Failures are listed, the conversion only fails as a whole if any file failed
+fn print_summary(summary: ConversionSummary) -> Result<(), Error> {
+    for (path_to_file, error) in summary.failed.iter() {
+        println!("FAILED {path_to_file}: {error}");
+    }
+    println!(
+        "{} converted, {} failed",
+        summary.converted.len(),
+        summary.failed.len()
+    );
+    if !summary.failed.is_empty() {
+        return Err(format!(
+            "{} of {} files could not be converted",
+            summary.failed.len(),
+            summary.converted.len() + summary.failed.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn format_and_compare_dtp(
+    path_to_source_file: &str,
+    path_to_destination_directory: &str,
+) -> Result<(), Error> {
+    format_dtp(path_to_source_file, path_to_destination_directory)?;
+    let file_name = Path::new(path_to_source_file)
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let path_to_destination_file = format!("{path_to_destination_directory}{file_name}");
+    if fs::read(path_to_source_file)? != fs::read(&path_to_destination_file)? {
+        return Err(
+            format!("{path_to_destination_file} differs from {path_to_source_file}").into(),
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use std::sync::OnceLock;
+
+use crate::business::dynamic_array::DynamicArrayStrategy;
+use crate::business::mapping_profile::MappingProfile;
+use crate::business::type_name::TypeNameTemplate;
+
+// The settings of a conversion, which have to be the same in both directions
+#[derive(Clone, Debug, Default)]
+pub struct ConversionOptions {
+    pub type_name_template: TypeNameTemplate,
+    // Only applies to the conversion of DTP files
+    pub derived_type_strategy: DerivedTypeStrategy,
+    pub dynamic_array_strategy: DynamicArrayStrategy,
+    // Without one, the handler looks for the nearest profile file above the source
+    pub mapping_profile: Option<MappingProfile>,
+    // Only applies to the conversion of MSG files: the whole source is embedded into every data type,
+    // so that its layout is reproduced on the way back
+    pub keep_msg_source: bool,
+}
+
+impl ConversionOptions {
+    pub fn mapping_profile(&self) -> &MappingProfile {
+        static DEFAULT_MAPPING_PROFILE: OnceLock<MappingProfile> = OnceLock::new();
+        self.mapping_profile
+            .as_ref()
+            .unwrap_or_else(|| DEFAULT_MAPPING_PROFILE.get_or_init(MappingProfile::default))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DerivedTypeStrategy {
+    // Directly derived types and array types become messages with a single field
+    #[default]
+    Wrap,
+    // Variables of those types get the underlying type, the types themselves are not converted
+    Inline,
+}
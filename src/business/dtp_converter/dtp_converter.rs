@@ -1,81 +1,1151 @@
+use std::collections::HashMap;
+
+use log::warn;
+
+use crate::business::conversion_options::ConversionOptions;
+use crate::business::dynamic_array::DynamicArrayStrategy;
 use crate::business::error::Result;
-use crate::core::{dtp, msg};
+use crate::business::identifier::{self, Identifier};
+use crate::business::mapping_profile::{self, FieldMapping, MappingProfile};
+use crate::business::msg_converter::{msg_converter, msg_reader};
+use crate::business::type_name;
+use crate::core::msg_cst::{LineContent, SyntaxTree};
+use crate::core::{action, dtp, msg, srv};
+
+const ANNOTATION_ATTRIBUTE_PREFIX: &str = "ROS2_Annotation_";
+const ENUMERATION_VALUE_FIELD_NAME: &str = "value";
+const SUBRANGE_VALUE_FIELD_NAME: &str = "value";
+const DERIVED_TYPE_VALUE_FIELD_NAME: &str = "value";
+
+// Variables of the types in "inlined_types" get the underlying type of those
+// directly derived types and array types instead of referencing them.
+// "message_names" maps the names of the other data types in the type library
+// to the names of the messages they are converted to.
+pub fn convert(
+    package_name: &str,
+    options: &ConversionOptions,
+    data_type: &dtp::DataType,
+    inlined_types: &[dtp::DataType],
+    message_names: &HashMap<String, String>,
+) -> Result<msg::StructuredType> {
+    convert_data_type(
+        package_name,
+        options,
+        msg::INTERFACE_KIND,
+        data_type,
+        inlined_types,
+        message_names,
+    )
+}
+
+pub fn convert_message_name(
+    package_name: &str,
+    options: &ConversionOptions,
+    data_type: &dtp::DataType,
+) -> Result<String> {
+    convert_data_type_name(package_name, options, msg::INTERFACE_KIND, data_type)
+}
+
+pub fn convert_service(
+    package_name: &str,
+    options: &ConversionOptions,
+    request_data_type: &dtp::DataType,
+    response_data_type: &dtp::DataType,
+    inlined_types: &[dtp::DataType],
+    message_names: &HashMap<String, String>,
+) -> Result<srv::Service> {
+    let request = convert_data_type(
+        package_name,
+        options,
+        srv::INTERFACE_KIND,
+        request_data_type,
+        inlined_types,
+        message_names,
+    )?;
+    let response = convert_data_type(
+        package_name,
+        options,
+        srv::INTERFACE_KIND,
+        response_data_type,
+        inlined_types,
+        message_names,
+    )?;
+    let name = request
+        .name()
+        .strip_suffix(srv::REQUEST_SUFFIX)
+        .ok_or(format!("No service request found in \"{}\"", request_data_type.name()))?;
+    if response.name().strip_suffix(srv::RESPONSE_SUFFIX) != Some(name) {
+        return Err(format!(
+            "\"{}\" is not the service response of \"{}\"",
+            response_data_type.name(),
+            request_data_type.name()
+        )
+        .into());
+    }
+    Ok(srv::Service::new(name, &request, &response))
+}
 
-pub fn convert(package_name: &str, data_type: &dtp::DataType) -> Result<msg::StructuredType> {
-    let name = convert_data_type_name(package_name, data_type)?;
-    let fields: Vec<msg::Field> = match data_type.data_type_kind() {
+pub fn convert_action(
+    package_name: &str,
+    options: &ConversionOptions,
+    goal_data_type: &dtp::DataType,
+    result_data_type: &dtp::DataType,
+    feedback_data_type: &dtp::DataType,
+    inlined_types: &[dtp::DataType],
+    message_names: &HashMap<String, String>,
+) -> Result<action::Action> {
+    let goal = convert_data_type(
+        package_name,
+        options,
+        action::INTERFACE_KIND,
+        goal_data_type,
+        inlined_types,
+        message_names,
+    )?;
+    let result = convert_data_type(
+        package_name,
+        options,
+        action::INTERFACE_KIND,
+        result_data_type,
+        inlined_types,
+        message_names,
+    )?;
+    let feedback = convert_data_type(
+        package_name,
+        options,
+        action::INTERFACE_KIND,
+        feedback_data_type,
+        inlined_types,
+        message_names,
+    )?;
+    let name = goal
+        .name()
+        .strip_suffix(action::GOAL_SUFFIX)
+        .ok_or(format!("No action goal found in \"{}\"", goal_data_type.name()))?;
+    if result.name().strip_suffix(action::RESULT_SUFFIX) != Some(name) {
+        return Err(format!(
+            "\"{}\" is not the action result of \"{}\"",
+            result_data_type.name(),
+            goal_data_type.name()
+        )
+        .into());
+    }
+    if feedback.name().strip_suffix(action::FEEDBACK_SUFFIX) != Some(name) {
+        return Err(format!(
+            "\"{}\" is not the action feedback of \"{}\"",
+            feedback_data_type.name(),
+            goal_data_type.name()
+        )
+        .into());
+    }
+    Ok(action::Action::new(name, &goal, &result, &feedback))
+}
+
+fn convert_data_type(
+    package_name: &str,
+    options: &ConversionOptions,
+    interface_kind: &str,
+    data_type: &dtp::DataType,
+    inlined_types: &[dtp::DataType],
+    message_names: &HashMap<String, String>,
+) -> Result<msg::StructuredType> {
+    let data_type = &inline_derived_types(data_type, inlined_types)?;
+    let data_type = &resolve_type_names(data_type, message_names);
+    let name = convert_data_type_name(package_name, options, interface_kind, data_type)?;
+    let (comment, fields) = match data_type.data_type_kind() {
+        dtp::DataTypeKind::DirectlyDerivedType(directly_derived_type) => (
+            data_type
+                .comment()
+                .clone()
+                .or_else(|| directly_derived_type.comment().clone()),
+            convert_directly_derived_type(
+                package_name,
+                options,
+                &options.mapping_profile().field_mapping(
+                    package_name,
+                    &name,
+                    &convert_to_wrapper_field_name(DERIVED_TYPE_VALUE_FIELD_NAME),
+                ),
+                directly_derived_type,
+            )?,
+        ),
+        dtp::DataTypeKind::ArrayType(array_type) => (
+            data_type
+                .comment()
+                .clone()
+                .or_else(|| array_type.comment().clone()),
+            convert_array_type(
+                package_name,
+                options,
+                &options.mapping_profile().field_mapping(
+                    package_name,
+                    &name,
+                    &convert_to_wrapper_field_name(DERIVED_TYPE_VALUE_FIELD_NAME),
+                ),
+                array_type,
+            )?,
+        ),
+        dtp::DataTypeKind::EnumeratedType(enumerated_type) => (
+            data_type
+                .comment()
+                .clone()
+                .or_else(|| enumerated_type.comment().clone()),
+            convert_enumerated_type(enumerated_type)?,
+        ),
+        dtp::DataTypeKind::SubrangeType(subrange_type) => (
+            data_type
+                .comment()
+                .clone()
+                .or_else(|| subrange_type.comment().clone()),
+            convert_subrange_type(
+                package_name,
+                options,
+                &options.mapping_profile().field_mapping(
+                    package_name,
+                    &name,
+                    &convert_to_wrapper_field_name(SUBRANGE_VALUE_FIELD_NAME),
+                ),
+                subrange_type,
+            )?,
+        ),
+        dtp::DataTypeKind::StructuredType(structured_type) => (
+            data_type
+                .comment()
+                .clone()
+                .or_else(|| structured_type.comment().clone()),
+            convert_structured_type(
+                package_name,
+                options,
+                &name,
+                structured_type,
+            )?,
+        ),
+    };
+    // The reverse conversion would not restore the name without the annotation
+    let qualified_name = type_name::qualified_name(data_type);
+    let comment = match options
+        .type_name_template
+        .render(package_name, interface_kind, &name)
+        == qualified_name
+    {
+        true => comment,
+        false => Some(prepend_annotation(
+            &type_name::encode_type_name_annotation(&qualified_name),
+            &comment,
+        )),
+    };
+    let syntax_tree = convert_to_syntax_tree(data_type);
+    let fields = keep_equivalent_comments(options, syntax_tree.as_ref(), fields);
+    let (comment, fields) = localize_annotations(options.mapping_profile(), &comment, &fields);
+    Ok(msg::StructuredType::new(&name, &comment, fields, &syntax_tree))
+}
+
+// A comment starting with annotations continues the list
+fn prepend_annotation(annotation: &str, comment: &Option<String>) -> String {
+    match comment.as_deref() {
+        None | Some("") => format!("{annotation}."),
+        Some(comment) if comment.starts_with('@') => format!("{annotation}, {comment}"),
+        Some(comment) => format!("{annotation}. {comment}"),
+    }
+}
+
+// The annotations get the own names of the mapping profile
+fn localize_annotations(
+    mapping_profile: &MappingProfile,
+    comment: &Option<String>,
+    fields: &[msg::Field],
+) -> (Option<String>, Vec<msg::Field>) {
+    let annotations = mapping_profile.annotations();
+    let localize =
+        |comment: Option<&String>| comment.map(|comment| annotations.localize(comment));
+    let fields = fields
+        .iter()
+        .map(|field| {
+            msg::Field::new(
+                field.base_type(),
+                &field.constraint().cloned(),
+                field.name(),
+                field.field_type(),
+                &localize(field.comment()),
+                field.annotations(),
+            )
+        })
+        .collect();
+    (localize(comment.as_ref()), fields)
+}
+
+fn inline_derived_types(
+    data_type: &dtp::DataType,
+    inlined_types: &[dtp::DataType],
+) -> Result<dtp::DataType> {
+    let dtp::DataTypeKind::StructuredType(structured_type) = data_type.data_type_kind() else {
+        return Ok(data_type.clone());
+    };
+    let children = structured_type
+        .children()
+        .iter()
+        .map(|child| match child {
+            dtp::StructuredTypeChild::VarDeclaration(var_declaration) => {
+                inline_var_declaration(var_declaration, inlined_types)
+                    .map(dtp::StructuredTypeChild::VarDeclaration)
+            }
+            dtp::StructuredTypeChild::SubrangeVarDeclaration(_) => Ok(child.clone()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(dtp::DataType::new(
+        data_type.name(),
+        data_type.comment(),
+        data_type.identification(),
+        data_type.version_info(),
+        data_type.compiler_info(),
+        &dtp::DataTypeKind::StructuredType(dtp::StructuredType::new(
+            structured_type.comment(),
+            &children,
+            structured_type.foreign_xml(),
+        )),
+        data_type.attributes(),
+        data_type.foreign_xml(),
+    ))
+}
+
+// Custom types found in the type library are referenced by the name of their message. Variables keep
+// their type, so that a renamed one is annotated, e.g. "@IEC61499_TypeName(Lager_Platz)".
+fn resolve_type_names(
+    data_type: &dtp::DataType,
+    message_names: &HashMap<String, String>,
+) -> dtp::DataType {
+    let resolve = |base_type: &dtp::BaseType| match base_type {
+        dtp::BaseType::Custom(type_name) => message_names
+            .get(type_name)
+            .map(|message_name| dtp::BaseType::Custom(message_name.clone()))
+            .unwrap_or_else(|| base_type.clone()),
+        _ => base_type.clone(),
+    };
+    let data_type_kind = match data_type.data_type_kind() {
+        dtp::DataTypeKind::DirectlyDerivedType(directly_derived_type) => {
+            dtp::DataTypeKind::DirectlyDerivedType(dtp::DirectlyDerivedType::new(
+                &resolve(directly_derived_type.base_type()),
+                directly_derived_type.initial_value(),
+                directly_derived_type.comment(),
+            ))
+        }
+        dtp::DataTypeKind::ArrayType(array_type) => {
+            dtp::DataTypeKind::ArrayType(dtp::ArrayType::new(
+                &resolve(array_type.base_type()),
+                array_type.subranges(),
+                array_type.initial_values(),
+                array_type.comment(),
+            ))
+        }
         dtp::DataTypeKind::StructuredType(structured_type) => {
-            convert_structured_type(package_name, structured_type)?
+            let children = structured_type
+                .children()
+                .iter()
+                .map(|child| match child {
+                    dtp::StructuredTypeChild::VarDeclaration(var_declaration) => {
+                        dtp::StructuredTypeChild::VarDeclaration(dtp::VarDeclaration::new(
+                            var_declaration.name(),
+                            var_declaration.base_type(),
+                            var_declaration.array_size(),
+                            var_declaration.initial_value(),
+                            var_declaration.comment(),
+                            &resolve_reference(var_declaration, message_names),
+                            var_declaration.foreign_xml(),
+                        ))
+                    }
+                    dtp::StructuredTypeChild::SubrangeVarDeclaration(_) => child.clone(),
+                })
+                .collect::<Vec<_>>();
+            dtp::DataTypeKind::StructuredType(dtp::StructuredType::new(
+                structured_type.comment(),
+                &children,
+                structured_type.foreign_xml(),
+            ))
+        }
+        dtp::DataTypeKind::EnumeratedType(_) | dtp::DataTypeKind::SubrangeType(_) => {
+            return data_type.clone();
         }
     };
-    Ok(msg::StructuredType::new(&name, fields))
+    dtp::DataType::new(
+        data_type.name(),
+        data_type.comment(),
+        data_type.identification(),
+        data_type.version_info(),
+        data_type.compiler_info(),
+        &data_type_kind,
+        data_type.attributes(),
+        data_type.foreign_xml(),
+    )
+}
+
+// Derived types may be based on further derived types, which are resolved one after another
+fn inline_var_declaration(
+    var_declaration: &dtp::VarDeclaration,
+    inlined_types: &[dtp::DataType],
+) -> Result<dtp::VarDeclaration> {
+    let mut result = var_declaration.clone();
+    for _ in 0..=inlined_types.len() {
+        let dtp::BaseType::Custom(type_name) = result.base_type() else {
+            return Ok(result);
+        };
+        let Some(inlined_type) = inlined_types
+            .iter()
+            .find(|inlined_type| type_name::qualified_name(inlined_type) == *type_name)
+        else {
+            return Ok(result);
+        };
+        result = match inlined_type.data_type_kind() {
+            dtp::DataTypeKind::DirectlyDerivedType(directly_derived_type) => {
+                dtp::VarDeclaration::new(
+                    result.name(),
+                    directly_derived_type.base_type(),
+                    result.array_size(),
+                    &result.initial_value().clone().or_else(|| {
+                        result
+                            .array_size()
+                            .is_none()
+                            .then(|| directly_derived_type.initial_value().clone())
+                            .flatten()
+                    }),
+                    &result
+                        .comment()
+                        .clone()
+                        .or_else(|| directly_derived_type.comment().clone()),
+                    result.attributes(),
+                    result.foreign_xml(),
+                )
+            }
+            dtp::DataTypeKind::ArrayType(array_type) => {
+                if result.array_size().is_some() {
+                    return Err(format!(
+                        "Array \"{}\" of array type \"{type_name}\" is not supported",
+                        result.name()
+                    )
+                    .into());
+                }
+                dtp::VarDeclaration::new(
+                    result.name(),
+                    array_type.base_type(),
+                    &Some(convert_to_array_size(array_type)?),
+                    &result
+                        .initial_value()
+                        .clone()
+                        .or_else(|| array_type.initial_values().clone()),
+                    &result
+                        .comment()
+                        .clone()
+                        .or_else(|| array_type.comment().clone()),
+                    result.attributes(),
+                    result.foreign_xml(),
+                )
+            }
+            _ => {
+                return Err(format!("\"{type_name}\" can not be inlined").into());
+            }
+        };
+    }
+    Err(format!("Cyclic derived type \"{}\" found", var_declaration.name()).into())
+}
+
+// ROS 2 only knows one-dimensional arrays
+fn convert_to_array_size(array_type: &dtp::ArrayType) -> Result<dtp::ArraySize> {
+    match array_type.subranges().as_slice() {
+        [subrange] if subrange.lower_limit == 0 => Ok(dtp::ArraySize::Static(
+            dtp::Capacity::InPlace((subrange.upper_limit + 1) as usize),
+        )),
+        [subrange] => Ok(dtp::ArraySize::Static(dtp::Capacity::Shifted(
+            subrange.lower_limit,
+            subrange.upper_limit,
+        ))),
+        _ => Err("Multidimensional array types are not supported".into()),
+    }
+}
+
+// The kind of interface a data type belongs to, as given by its attribute or its name
+pub fn find_interface_kind(
+    package_name: &str,
+    options: &ConversionOptions,
+    data_type: &dtp::DataType,
+) -> Result<Option<String>> {
+    if let Some(full_name) = find_string_attribute(data_type.attributes(), "ROS2_Type") {
+        let (_, kind, _) = type_name::decode_full_name(full_name)?;
+        return Ok(Some(kind.to_string()));
+    }
+    let qualified_name = type_name::qualified_name(data_type);
+    Ok(options
+        .type_name_template
+        .decode(&qualified_name, package_name)
+        .filter(|decoded| decoded.belongs_to(package_name))
+        .and_then(|decoded| decoded.interface_kind.map(str::to_string)))
+}
+
+fn resolve_reference(
+    var_declaration: &dtp::VarDeclaration,
+    message_names: &HashMap<String, String>,
+) -> Vec<dtp::Attribute> {
+    let mut attributes = var_declaration.attributes().clone();
+    let message_name = match var_declaration.base_type() {
+        dtp::BaseType::Custom(type_name) => message_names.get(type_name),
+        _ => None,
+    };
+    if let Some(message_name) = message_name {
+        attributes.push(dtp::Attribute {
+            name: "ROS2_RelativeReference".to_string(),
+            base_type: dtp::BaseType::STRING(None),
+            value: dtp::InitialValue::STRING(message_name.clone()),
+            comment: None,
+        });
+    }
+    attributes
+}
+
+// Data types converted from ROS 2 keep the name of their type in an attribute
+fn convert_data_type_name(
+    package_name: &str,
+    options: &ConversionOptions,
+    interface_kind: &str,
+    data_type: &dtp::DataType,
+) -> Result<String> {
+    if let Some(full_name) = find_string_attribute(data_type.attributes(), "ROS2_Type") {
+        let (_, kind, name) = type_name::decode_full_name(full_name)?;
+        if kind == interface_kind {
+            return Ok(name.to_string());
+        }
+    }
+    // Other data types keep their name as far as ROS 2 allows
+    let qualified_name = type_name::qualified_name(data_type);
+    Ok(
+        match options
+            .type_name_template
+            .decode(&qualified_name, package_name)
+        {
+            Some(decoded)
+                if decoded.belongs_to(package_name)
+                    && decoded
+                        .interface_kind
+                        .is_none_or(|kind| kind == interface_kind) =>
+            {
+                type_name::to_ros_type_name(decoded.name, interface_kind)
+            }
+            _ => type_name::to_ros_type_name(data_type.name(), interface_kind),
+        },
+    )
 }
 
-fn convert_data_type_name(package_name: &str, data_type: &dtp::DataType) -> Result<String> {
-    let package_name = package_name
-        .replace("_", "")
-        .replace(" ", "")
-        .replace("-", "");
-    let full_name = data_type.name();
-    Ok(full_name
-        .strip_prefix(&format!("ROS2_{package_name}_msg_"))
-        .unwrap_or(full_name)
-        .to_string())
+fn find_string_attribute<'a>(attributes: &'a [dtp::Attribute], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|attribute| attribute.name == name)
+        .and_then(|attribute| match &attribute.value {
+            dtp::InitialValue::STRING(value) => Some(value.as_str()),
+            _ => None,
+        })
 }
 
-fn convert_structured_type(module_name: &str, structured_type: &dtp::StructuredType) -> Result<Vec<msg::Field>> {
+fn convert_to_syntax_tree(data_type: &dtp::DataType) -> Option<SyntaxTree> {
+    let source = data_type
+        .attributes()
+        .iter()
+        .find(|attribute| attribute.name == "ROS2_MsgSource")
+        .and_then(|attribute| match &attribute.value {
+            dtp::InitialValue::STRING(value) => Some(value.clone()),
+            _ => None,
+        })?;
+    msg_reader::parse_syntax_tree(&source)
+        .inspect_err(|error| warn!("Ignoring invalid \"ROS2_MsgSource\": {error}"))
+        .ok()
+}
+
+// The conversion leaves out annotations, which the dynamic array strategy implies, e.g.
+// "@IEC61499_ElementCounter" when every sequence is counted anyway. A field of the MSG source
+// keeps its comment as long as it describes the same array, so its line is written unchanged.
+fn keep_equivalent_comments(
+    options: &ConversionOptions,
+    syntax_tree: Option<&SyntaxTree>,
+    fields: Vec<msg::Field>,
+) -> Vec<msg::Field> {
+    let Some(syntax_tree) = syntax_tree else {
+        return fields;
+    };
+    let annotations = options.mapping_profile().annotations();
+    fields
+        .into_iter()
+        .map(|field| {
+            let original_comment =
+                syntax_tree
+                    .lines()
+                    .iter()
+                    .find_map(|line| match &line.content {
+                        LineContent::Field {
+                            field: original, ..
+                        } if original.name() == field.name() => Some(
+                            original
+                                .comment()
+                                .map(|comment| annotations.canonicalize(comment)),
+                        ),
+                        _ => None,
+                    });
+            match original_comment {
+                Some(original_comment)
+                    if original_comment.as_ref() != field.comment()
+                        && is_equivalent_comment(options, &field, &original_comment) =>
+                {
+                    msg::Field::new(
+                        field.base_type(),
+                        &field.constraint().cloned(),
+                        field.name(),
+                        field.field_type(),
+                        &original_comment,
+                        field.annotations(),
+                    )
+                }
+                _ => field,
+            }
+        })
+        .collect()
+}
+
+fn is_equivalent_comment(
+    options: &ConversionOptions,
+    field: &msg::Field,
+    original_comment: &Option<String>,
+) -> bool {
+    let original = msg::Field::new(
+        field.base_type(),
+        &field.constraint().cloned(),
+        field.name(),
+        field.field_type(),
+        original_comment,
+        field.annotations(),
+    );
+    let strategy = &options.dynamic_array_strategy;
+    let dynamic_array = msg_converter::convert_to_dynamic_array(strategy, field, field.name());
+    let original_dynamic_array =
+        msg_converter::convert_to_dynamic_array(strategy, &original, field.name());
+    matches!((dynamic_array, original_dynamic_array), (Ok(a), Ok(b)) if a == b)
+        && without_dynamic_array_annotations(field.comment())
+            == without_dynamic_array_annotations(original.comment())
+}
+
+// "@IEC61499_Capacity(5), @IEC61499_Name(Flags). Flags" leaves "@IEC61499_Name(Flags). Flags"
+fn without_dynamic_array_annotations(comment: Option<&String>) -> (Vec<&str>, &str) {
+    let mut annotations = Vec::new();
+    let mut rest = comment.map_or("", |comment| comment.trim());
+    while rest.starts_with('@') {
+        let mut depth = 0;
+        let end = rest
+            .char_indices()
+            .find(|(_, char)| {
+                match char {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0 && (*char == ',' || *char == '.')
+            })
+            .map_or(rest.len(), |(position, _)| position);
+        annotations.push(&rest[..end]);
+        let is_last = !rest[end..].starts_with(',');
+        rest = rest[end..].get(1..).unwrap_or_default().trim_start();
+        if is_last {
+            break;
+        }
+    }
+    annotations.retain(|annotation| {
+        ![
+            "@IEC61499_Capacity",
+            "@IEC61499_ElementCounter",
+            "@IEC61499_NoElementCounter",
+        ]
+        .iter()
+        .any(|name| {
+            annotation
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('('))
+        })
+    });
+    (annotations, rest)
+}
+
+// An enumeration becomes a message of constants, one per value,
+// and a field holding the selected value
+// ROS 2 constants are UPPER_CASE, so renamed values keep their name in "@IEC61499_Name(<name>)"
+fn convert_enumerated_type(enumerated_type: &dtp::EnumeratedType) -> Result<Vec<msg::Field>> {
+    let values = enumerated_type.values();
+    let (base_type, create_initial_value): (_, fn(msg::IntLiteral) -> msg::InitialValue) =
+        if values.len() <= u8::MAX as usize + 1 {
+            (msg::BaseType::Uint8, msg::InitialValue::Uint8)
+        } else {
+            (msg::BaseType::Int32, msg::InitialValue::Int32)
+        };
+    let mut field_names = convert_to_enumeration_field_names(values);
+    let value_field_name = field_names.pop().unwrap_or_default();
+
+    let mut fields: Vec<msg::Field> = values
+        .iter()
+        .zip(field_names)
+        .enumerate()
+        .map(|(index, (value, field_name))| {
+            let comment = match field_name != value.name() {
+                true => Some(prepend_annotation(
+                    &format!("@IEC61499_Name({})", value.name()),
+                    value.comment(),
+                )),
+                false => value.comment().clone(),
+            };
+            msg::Field::new(
+                &base_type,
+                &None,
+                &field_name,
+                &msg::FieldType::Constant(create_initial_value(
+                    msg::IntLiteral::UnsignedDecimalInt(index as u64),
+                )),
+                &comment,
+                &[],
+            )
+        })
+        .collect();
+
+    let initial_value = enumerated_type
+        .initial_value()
+        .as_ref()
+        .map(|initial_value| {
+            values
+                .iter()
+                .position(|value| value.name() == initial_value)
+                .map(|index| {
+                    create_initial_value(msg::IntLiteral::UnsignedDecimalInt(index as u64))
+                })
+                .ok_or(format!("Unknown enumerated value \"{initial_value}\""))
+        })
+        .transpose()?;
+    fields.push(msg::Field::new(
+        &base_type,
+        &None,
+        &value_field_name,
+        &msg::FieldType::Variable(initial_value),
+        &Some("@IEC61499_EnumeratedType.".to_string()),
+        &[],
+    ));
+    Ok(fields)
+}
+
+// The names of the constants followed by the name of the field selecting one of them
+fn convert_to_enumeration_field_names(values: &[dtp::EnumeratedValue]) -> Vec<String> {
+    let identifiers = values
+        .iter()
+        .map(|value| Identifier {
+            name: value.name(),
+            is_constant: true,
+            is_original: false,
+        })
+        .chain(std::iter::once(Identifier {
+            name: ENUMERATION_VALUE_FIELD_NAME,
+            is_constant: false,
+            is_original: false,
+        }))
+        .collect::<Vec<_>>();
+    identifier::to_ros_names(&identifiers)
+}
+
+// The single field of a message wrapping a derived type is named like any other field
+fn convert_to_wrapper_field_name(name: &str) -> String {
+    identifier::to_ros_names(&[Identifier {
+        name,
+        is_constant: false,
+        is_original: false,
+    }])
+    .remove(0)
+}
+
+// Without inlining a directly derived type or an array type becomes a message,
+// which wraps the underlying type into a single field
+fn convert_directly_derived_type(
+    module_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    directly_derived_type: &dtp::DirectlyDerivedType,
+) -> Result<Vec<msg::Field>> {
+    let var_declaration = dtp::VarDeclaration::new(
+        DERIVED_TYPE_VALUE_FIELD_NAME,
+        directly_derived_type.base_type(),
+        &None,
+        directly_derived_type.initial_value(),
+        &None,
+        &[],
+        &dtp::ForeignXml::default(),
+    );
+    convert_var_declaration(
+        module_name,
+        options,
+        field_mapping,
+        &dtp::StructuredType::new(&None, &[], &dtp::ForeignXml::default()),
+        &var_declaration,
+        &convert_to_wrapper_field_name(var_declaration.name()),
+        None,
+    )
+    .map(|fields| annotate_fields(&fields, "@IEC61499_DirectlyDerivedType"))
+}
+
+fn convert_array_type(
+    module_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    array_type: &dtp::ArrayType,
+) -> Result<Vec<msg::Field>> {
+    let var_declaration = dtp::VarDeclaration::new(
+        DERIVED_TYPE_VALUE_FIELD_NAME,
+        array_type.base_type(),
+        &Some(convert_to_array_size(array_type)?),
+        array_type.initial_values(),
+        &None,
+        &[],
+        &dtp::ForeignXml::default(),
+    );
+    convert_var_declaration(
+        module_name,
+        options,
+        field_mapping,
+        &dtp::StructuredType::new(&None, &[], &dtp::ForeignXml::default()),
+        &var_declaration,
+        &convert_to_wrapper_field_name(var_declaration.name()),
+        None,
+    )
+    .map(|fields| annotate_fields(&fields, "@IEC61499_ArrayType"))
+}
+
+// Prepends the annotation to the ones already in the comment
+fn annotate_fields(fields: &[msg::Field], annotation: &str) -> Vec<msg::Field> {
+    fields
+        .iter()
+        .map(|field| {
+            let comment = match field.comment() {
+                Some(comment) if comment.starts_with('@') => format!("{annotation}, {comment}"),
+                Some(comment) => format!("{annotation}. {comment}"),
+                None => format!("{annotation}."),
+            };
+            msg::Field::new(
+                field.base_type(),
+                &field.constraint().cloned(),
+                field.name(),
+                field.field_type(),
+                &Some(comment),
+                field.annotations(),
+            )
+        })
+        .collect()
+}
+
+// A subrange becomes a message with a single field of the base type,
+// the limits are kept in the annotation of the field
+fn convert_subrange_type(
+    module_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    subrange_type: &dtp::SubrangeType,
+) -> Result<Vec<msg::Field>> {
+    let var_declaration = dtp::VarDeclaration::new(
+        SUBRANGE_VALUE_FIELD_NAME,
+        subrange_type.base_type(),
+        &None,
+        subrange_type.initial_value(),
+        &Some(format!(
+            "{}.",
+            convert_to_subrange_annotation("@IEC61499_SubrangeType", &Some(*subrange_type.subrange()))
+        )),
+        &[],
+        &dtp::ForeignXml::default(),
+    );
+    convert_var_declaration(
+        module_name,
+        options,
+        field_mapping,
+        &dtp::StructuredType::new(&None, &[], &dtp::ForeignXml::default()),
+        &var_declaration,
+        &convert_to_wrapper_field_name(var_declaration.name()),
+        None,
+    )
+}
+
+fn convert_structured_type(
+    module_name: &str,
+    options: &ConversionOptions,
+    type_name: &str,
+    structured_type: &dtp::StructuredType,
+) -> Result<Vec<msg::Field>> {
     let mut fields: Vec<msg::Field> = Vec::new();
+    let field_names = convert_to_field_names(structured_type);
+    let iec_names =
+        identifier::to_iec_names(&convert_to_identifiers(structured_type, &field_names));
 
-    for structured_type_child in structured_type.children() {
+    for ((structured_type_child, field_name), iec_name) in structured_type
+        .children()
+        .iter()
+        .zip(&field_names)
+        .zip(iec_names)
+    {
+        // The reverse conversion would not restore the name without the annotation
+        let var_name = get_var_name(structured_type_child);
+        let original_name = (iec_name != var_name).then_some(var_name);
+        let field_mapping =
+            options
+                .mapping_profile()
+                .field_mapping(module_name, type_name, field_name);
         match structured_type_child {
-            dtp::StructuredTypeChild::VarDeclaration(var_declaration) => fields.append(
-                &mut convert_var_declaration(module_name, structured_type, var_declaration)?,
-            ),
+            dtp::StructuredTypeChild::VarDeclaration(var_declaration) => {
+                fields.append(&mut convert_var_declaration(
+                    module_name,
+                    options,
+                    &field_mapping,
+                    structured_type,
+                    var_declaration,
+                    field_name,
+                    original_name,
+                )?)
+            }
+            dtp::StructuredTypeChild::SubrangeVarDeclaration(subrange_var_declaration) => fields
+                .append(&mut convert_subrange_var_declaration(
+                    module_name,
+                    options,
+                    &field_mapping,
+                    structured_type,
+                    subrange_var_declaration,
+                    field_name,
+                    original_name,
+                )?),
         }
     }
     Ok(fields)
 }
 
+// The names are sanitized together, as ROS 2 does not allow "Speed" next to "speed".
+// "ROS2_Name" restores the name of a field, which was renamed for IEC 61499.
+fn convert_to_field_names(structured_type: &dtp::StructuredType) -> Vec<String> {
+    let identifiers = structured_type
+        .children()
+        .iter()
+        .map(|structured_type_child| {
+            let attributes = get_attributes(structured_type_child);
+            let original_name = find_string_attribute(attributes, "ROS2_Name");
+            Identifier {
+                name: original_name.unwrap_or(get_var_name(structured_type_child)),
+                is_constant: attributes.iter().any(|attr| attr.name == "ROS2_CONSTANT"),
+                is_original: original_name.is_some(),
+            }
+        })
+        .collect::<Vec<_>>();
+    identifier::to_ros_names(&identifiers)
+}
+
+fn convert_to_identifiers<'a>(
+    structured_type: &dtp::StructuredType,
+    field_names: &'a [String],
+) -> Vec<Identifier<'a>> {
+    structured_type
+        .children()
+        .iter()
+        .zip(field_names)
+        .map(|(structured_type_child, field_name)| Identifier {
+            name: field_name,
+            is_constant: get_attributes(structured_type_child)
+                .iter()
+                .any(|attr| attr.name == "ROS2_CONSTANT"),
+            is_original: false,
+        })
+        .collect()
+}
+
+fn get_var_name(structured_type_child: &dtp::StructuredTypeChild) -> &str {
+    match structured_type_child {
+        dtp::StructuredTypeChild::VarDeclaration(var_declaration) => var_declaration.name(),
+        dtp::StructuredTypeChild::SubrangeVarDeclaration(subrange_var_declaration) => {
+            subrange_var_declaration.name()
+        }
+    }
+}
+
+fn get_attributes(structured_type_child: &dtp::StructuredTypeChild) -> &[dtp::Attribute] {
+    match structured_type_child {
+        dtp::StructuredTypeChild::VarDeclaration(var_declaration) => var_declaration.attributes(),
+        dtp::StructuredTypeChild::SubrangeVarDeclaration(subrange_var_declaration) => {
+            subrange_var_declaration.attributes()
+        }
+    }
+}
+
+fn convert_subrange_var_declaration(
+    module_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    structured_type: &dtp::StructuredType,
+    subrange_var_declaration: &dtp::SubrangeVarDeclaration,
+    field_name: &str,
+    original_name: Option<&str>,
+) -> Result<Vec<msg::Field>> {
+    let annotation =
+        convert_to_subrange_annotation("@IEC61499_Subrange", subrange_var_declaration.subrange());
+    let comment = match subrange_var_declaration.comment() {
+        Some(comment) => format!("{annotation}. {comment}"),
+        None => format!("{annotation}."),
+    };
+    let var_declaration = dtp::VarDeclaration::new(
+        subrange_var_declaration.name(),
+        subrange_var_declaration.base_type(),
+        &None,
+        subrange_var_declaration.initial_value(),
+        &Some(comment),
+        subrange_var_declaration.attributes(),
+        &dtp::ForeignXml::default(),
+    );
+    convert_var_declaration(
+        module_name,
+        options,
+        field_mapping,
+        structured_type,
+        &var_declaration,
+        field_name,
+        original_name,
+    )
+}
+
+// "." separates the annotations from the comment, so the limits are separated by ","
+fn convert_to_subrange_annotation(name: &str, subrange: &Option<dtp::Subrange>) -> String {
+    match subrange {
+        Some(subrange) => format!("{name}({}, {})", subrange.lower_limit, subrange.upper_limit),
+        None => name.to_string(),
+    }
+}
+
 fn convert_var_declaration(
     module_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
     structured_type: &dtp::StructuredType,
     var_declaration: &dtp::VarDeclaration,
+    field_name: &str,
+    original_name: Option<&str>,
 ) -> Result<Vec<msg::Field>> {
     let is_helper = var_declaration
         .attributes()
-        .into_iter()
+        .iter()
         .find(|attr| attr.name == "ROS2_ElementCounter");
     if is_helper.is_some() {
         return Ok(Vec::new());
     }
 
+    let base_type = convert_to_msg_base_type(module_name, options, field_mapping, var_declaration)?;
     Ok(vec![msg::Field::new(
-        &convert_to_msg_base_type(module_name, var_declaration)?,
+        &base_type,
         &convert_to_msg_constraint(var_declaration)?,
-        &convert_to_field_name(var_declaration),
-        &convert_to_msg_initial_value(structured_type, var_declaration)?,
-        &convert_to_msg_comment(var_declaration),
+        field_name,
+        &convert_to_msg_initial_value(structured_type, var_declaration, &base_type)?,
+        &convert_to_msg_comment(
+            var_declaration,
+            convert_to_type_annotation(
+                module_name,
+                options,
+                field_mapping,
+                var_declaration,
+                &base_type,
+            )?,
+            original_name,
+            &convert_to_dynamic_array_annotations(
+                &options.dynamic_array_strategy,
+                structured_type,
+                var_declaration,
+            ),
+        ),
+        &convert_to_msg_annotations(var_declaration),
     )])
 }
 
-fn convert_to_msg_comment(var_declaration: &dtp::VarDeclaration) -> Option<String> {
+// Annotates, where a sequence deviates from the strategy, so that it is restored the same way
+fn convert_to_dynamic_array_annotations(
+    dynamic_array_strategy: &DynamicArrayStrategy,
+    structured_type: &dtp::StructuredType,
+    var_declaration: &dtp::VarDeclaration,
+) -> Vec<String> {
+    let is_bounded = is_bound_dynamic_array(var_declaration);
+    let capacity = match var_declaration.array_size() {
+        Some(dtp::ArraySize::Dynamic) => None,
+        Some(dtp::ArraySize::Static(dtp::Capacity::InPlace(capacity)))
+            if is_bounded || is_dynamic_array(var_declaration) =>
+        {
+            Some(*capacity)
+        }
+        _ => return Vec::new(),
+    };
+    let mut annotations = Vec::new();
+    match capacity {
+        None if !dynamic_array_strategy.variable_size => {
+            annotations.push("@IEC61499_Capacity(*)".to_string())
+        }
+        Some(capacity)
+            if dynamic_array_strategy.variable_size
+                || !is_bounded && capacity != dynamic_array_strategy.capacity =>
+        {
+            annotations.push(format!("@IEC61499_Capacity({capacity})"))
+        }
+        _ => {}
+    }
+    let is_counted_by_default = dynamic_array_strategy.element_counter && capacity.is_some();
+    let default_name = dynamic_array_strategy.element_counter_name(var_declaration.name());
+    match find_element_counter(structured_type, var_declaration) {
+        Some(helper) if helper.name() != default_name => {
+            annotations.push(format!("@IEC61499_ElementCounter({})", helper.name()))
+        }
+        Some(_) if !is_counted_by_default => {
+            annotations.push("@IEC61499_ElementCounter".to_string())
+        }
+        None if is_counted_by_default => annotations.push("@IEC61499_NoElementCounter".to_string()),
+        _ => {}
+    }
+    annotations
+}
+
+fn convert_to_msg_annotations(var_declaration: &dtp::VarDeclaration) -> Vec<msg::Annotation> {
+    var_declaration
+        .attributes()
+        .iter()
+        .filter_map(|attribute| {
+            let name = attribute.name.strip_prefix(ANNOTATION_ATTRIBUTE_PREFIX)?;
+            let parameters = match &attribute.value {
+                dtp::InitialValue::STRING(value) if !value.is_empty() => Some(value.clone()),
+                _ => None,
+            };
+            Some(msg::Annotation {
+                name: name.to_string(),
+                parameters,
+            })
+        })
+        .collect()
+}
+
+fn convert_to_msg_comment(
+    var_declaration: &dtp::VarDeclaration,
+    type_annotation: Option<String>,
+    original_name: Option<&str>,
+    dynamic_array_annotations: &[String],
+) -> Option<String> {
     let mut annotations: Vec<String> = Vec::new();
-    annotations.append(&mut match *var_declaration.base_type() {
-        dtp::BaseType::WORD => vec!["@IEC61499_WORD".to_string()],
-        dtp::BaseType::DWORD => vec!["@IEC61499_DWORD".to_string()],
-        dtp::BaseType::LWORD => vec!["@IEC61499_LWORD".to_string()],
-        _ => vec![],
-    });
+    annotations.extend(type_annotation);
     if let Some(dtp::ArraySize::Static(dtp::Capacity::Shifted(start, _))) =
         var_declaration.array_size()
     {
         annotations.push(format!("@IEC61499_StartIndex({start})"));
     }
+    annotations.extend_from_slice(dynamic_array_annotations);
+    if let Some(original_name) = original_name {
+        annotations.push(format!("@IEC61499_Name({original_name})"));
+    }
 
-    let msg_comment = match annotations.is_empty() {
-        false => format!("{}. ", &annotations.join(", ")),
-        true => String::new(),
-    } + var_declaration.comment().as_ref().unwrap_or(&String::new());
+    // A comment starting with annotations continues the list
+    let comment = var_declaration.comment().clone().unwrap_or_default();
+    let msg_comment = match (annotations.is_empty(), comment.starts_with('@')) {
+        (true, _) => comment,
+        (false, true) => format!("{}, {comment}", annotations.join(", ")),
+        (false, false) => format!("{}. {comment}", annotations.join(", ")),
+    };
 
     if !msg_comment.is_empty() {
         Some(msg_comment.trim().to_string())
@@ -84,38 +1154,74 @@ fn convert_to_msg_comment(var_declaration: &dtp::VarDeclaration) -> Option<Strin
     }
 }
 
-fn convert_to_msg_base_type(module_name: &str, var_declaration: &dtp::VarDeclaration) -> Result<msg::BaseType> {
-    let result = match var_declaration.base_type() {
-        dtp::BaseType::BOOL => msg::BaseType::Bool,
-        dtp::BaseType::SINT => msg::BaseType::Int8,
-        dtp::BaseType::INT => msg::BaseType::Int16,
-        dtp::BaseType::DINT => msg::BaseType::Int32,
-        dtp::BaseType::LINT => msg::BaseType::Int64,
-        dtp::BaseType::USINT => msg::BaseType::Uint8,
-        dtp::BaseType::UINT => msg::BaseType::Uint16,
-        dtp::BaseType::UDINT => msg::BaseType::Uint32,
-        dtp::BaseType::ULINT => msg::BaseType::Uint64,
-        dtp::BaseType::BYTE => msg::BaseType::Byte,
-        dtp::BaseType::WORD => msg::BaseType::Uint16,
-        dtp::BaseType::DWORD => msg::BaseType::Uint32,
-        dtp::BaseType::LWORD => msg::BaseType::Uint64,
-        dtp::BaseType::REAL => msg::BaseType::Float32,
-        dtp::BaseType::LREAL => msg::BaseType::Float64,
-        dtp::BaseType::CHAR => msg::BaseType::Char,
-        dtp::BaseType::STRING(opt_bound) => msg::BaseType::String(opt_bound.clone()),
-        dtp::BaseType::WSTRING(opt_bount) => msg::BaseType::Wstring(opt_bount.clone()),
-        dtp::BaseType::Custom(value) => {
-            msg::BaseType::Custom(convert_reference(module_name, var_declaration, value)?)
-        }
+// "ROS2_BaseType" takes precedence over the mapping profile
+fn convert_to_msg_base_type(
+    module_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    var_declaration: &dtp::VarDeclaration,
+) -> Result<msg::BaseType> {
+    let attributes = var_declaration.attributes();
+    match var_declaration.base_type() {
+        dtp::BaseType::Custom(value) => Ok(msg::BaseType::Custom(convert_reference(
+            module_name,
+            options,
+            var_declaration,
+            value,
+        )?)),
+        base_type => match find_string_attribute(attributes, "ROS2_BaseType") {
+            Some(recorded_base_type) => {
+                mapping_profile::recorded_ros_type(recorded_base_type, base_type)
+                    .map_err(|err| format!("Variable \"{}\": {err}", var_declaration.name()).into())
+            }
+            None => field_mapping.to_ros_type(base_type),
+        },
+    }
+}
+
+// The reverse conversion would not restore the IEC 61131-3 type without the annotation,
+// e.g. "@IEC61499_WORD" for a WORD becoming an uint16 or "@IEC61499_TypeName(Lager_Platz)"
+// for a reference to "LagerPlatz"
+fn convert_to_type_annotation(
+    module_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    var_declaration: &dtp::VarDeclaration,
+    base_type: &msg::BaseType,
+) -> Result<Option<String>> {
+    if let (dtp::BaseType::Custom(iec_type_name), msg::BaseType::Custom(reference)) =
+        (var_declaration.base_type(), base_type)
+    {
+        let (package_name, file) = match reference {
+            msg::Reference::Relative { file } => (module_name, file),
+            msg::Reference::Absolute { package, file } => (package.as_str(), file),
+        };
+        let rendered_name =
+            options
+                .type_name_template
+                .render(package_name, msg::INTERFACE_KIND, file);
+        return Ok((rendered_name != *iec_type_name)
+            .then(|| type_name::encode_type_name_annotation(iec_type_name)));
+    }
+    let Some(type_name) = mapping_profile::iec_type_name(var_declaration.base_type()) else {
+        return Ok(None);
     };
-    Ok(result)
+    let restored_base_type = field_mapping.to_iec_type(base_type)?;
+    Ok((restored_base_type != *var_declaration.base_type())
+        .then(|| format!("@IEC61499_{type_name}")))
 }
 
 fn convert_to_msg_constraint(
     var_declaration: &dtp::VarDeclaration,
 ) -> Result<Option<msg::Constraint>> {
     match var_declaration.array_size() {
-        Some(dtp::ArraySize::Dynamic) => Err("Dynamic arrays are not supported".into()),
+        // Without the attributes an array of variable size is an unbounded sequence
+        Some(dtp::ArraySize::Dynamic) if is_bound_dynamic_array(var_declaration) => {
+            let array_bound = convert_array_bound(var_declaration)
+                .ok_or("Unsigned integer required for \"ROS2_BoundDynamicArray\"")?;
+            Ok(Some(msg::Constraint::BoundedDynamicArray(array_bound)))
+        }
+        Some(dtp::ArraySize::Dynamic) => Ok(Some(msg::Constraint::UnboundedDynamicArray)),
         Some(dtp::ArraySize::Static(dtp::Capacity::Shifted(start, end))) => Ok(Some(
             msg::Constraint::StaticArray((end - start + 1) as usize),
         )),
@@ -138,64 +1244,149 @@ fn convert_to_msg_constraint(
     }
 }
 
-fn convert_to_field_name(var_declaration: &dtp::VarDeclaration) -> String {
-    var_declaration.name().to_string()
-}
-
 fn convert_to_msg_initial_value(
     structured_type: &dtp::StructuredType,
     var_declaration: &dtp::VarDeclaration,
+    base_type: &msg::BaseType,
 ) -> Result<msg::FieldType> {
     let optional_initial_value = var_declaration
         .initial_value()
         .as_ref()
+        .filter(|initial_value| {
+            let is_nested = contains_nested_value(initial_value);
+            if is_nested {
+                warn!(
+                    "Ignoring initial value of \"{}\", ROS 2 has no default values for nested messages",
+                    var_declaration.name()
+                );
+            }
+            !is_nested
+        })
         .map(|initial_value| {
-            convert_initial_value_directly2(structured_type, var_declaration, initial_value)
+            convert_initial_value_directly2(structured_type, var_declaration, base_type, initial_value)
         })
         .transpose()?;
     convert_field_type(var_declaration, optional_initial_value)
 }
 
+// The value gets the ROS 2 type of its field, e.g. a WORD mapped onto uint16 an uint16 value
 fn convert_initial_value_directly2(
     structured_type: &dtp::StructuredType,
     var_declaration: &dtp::VarDeclaration,
+    base_type: &msg::BaseType,
     initial_value: &dtp::InitialValue,
 ) -> Result<msg::InitialValue> {
-    let result = match initial_value {
-        dtp::InitialValue::BOOL(v) => msg::InitialValue::Bool(convert_bool_literal(v)),
-        dtp::InitialValue::SINT(v) => msg::InitialValue::Int8(convert_int_literal(v)),
-        dtp::InitialValue::INT(v) => msg::InitialValue::Int16(convert_int_literal(v)),
-        dtp::InitialValue::DINT(v) => msg::InitialValue::Int32(convert_int_literal(v)),
-        dtp::InitialValue::LINT(v) => msg::InitialValue::Int64(convert_int_literal(v)),
-        dtp::InitialValue::USINT(v) => msg::InitialValue::Uint8(convert_int_literal(v)),
-        dtp::InitialValue::UINT(v) => msg::InitialValue::Uint16(convert_int_literal(v)),
-        dtp::InitialValue::UDINT(v) => msg::InitialValue::Uint32(convert_int_literal(v)),
-        dtp::InitialValue::ULINT(v) => msg::InitialValue::Uint64(convert_int_literal(v)),
-        dtp::InitialValue::BYTE(v) => msg::InitialValue::Byte(convert_int_literal(v)),
-        dtp::InitialValue::WORD(v) => msg::InitialValue::Uint16(convert_int_literal(v)),
-        dtp::InitialValue::DWORD(v) => msg::InitialValue::Uint32(convert_int_literal(v)),
-        dtp::InitialValue::LWORD(v) => msg::InitialValue::Uint64(convert_int_literal(v)),
-        dtp::InitialValue::REAL(v) => msg::InitialValue::Float32(*v),
-        dtp::InitialValue::LREAL(v) => msg::InitialValue::Float64(*v),
-        dtp::InitialValue::CHAR(v) => msg::InitialValue::Char(convert_char_literal(v)),
-        dtp::InitialValue::STRING(v) => msg::InitialValue::String(v.clone()),
-        dtp::InitialValue::WSTRING(v) => msg::InitialValue::Wstring(v.clone()),
-        dtp::InitialValue::Array(v) => {
-            let slice = if is_dynamic_array(var_declaration) {
-                &v[..convert_default_dynamic_array_count(structured_type, var_declaration)?]
-            } else {
-                &v
+    let mismatch = || {
+        format!(
+            "The value of \"{}\" does not fit its type",
+            var_declaration.name()
+        )
+    };
+    let result = match (initial_value, base_type) {
+        (dtp::InitialValue::BOOL(v), msg::BaseType::Bool) => {
+            msg::InitialValue::Bool(convert_bool_literal(v))
+        }
+        (dtp::InitialValue::REAL(v), msg::BaseType::Float32) => msg::InitialValue::Float32(*v),
+        // Widened by its decimal representation, so that 0.1 stays 0.1
+        (dtp::InitialValue::REAL(v), msg::BaseType::Float64) => {
+            msg::InitialValue::Float64(v.to_string().parse().unwrap_or(f64::from(*v)))
+        }
+        (dtp::InitialValue::LREAL(v), msg::BaseType::Float32) => {
+            msg::InitialValue::Float32(*v as f32)
+        }
+        (dtp::InitialValue::LREAL(v), msg::BaseType::Float64) => msg::InitialValue::Float64(*v),
+        (
+            dtp::InitialValue::STRING(v) | dtp::InitialValue::WSTRING(v),
+            msg::BaseType::String(_),
+        ) => msg::InitialValue::String(v.clone()),
+        (
+            dtp::InitialValue::STRING(v) | dtp::InitialValue::WSTRING(v),
+            msg::BaseType::Wstring(_),
+        ) => msg::InitialValue::Wstring(v.clone()),
+        (dtp::InitialValue::CHAR(v), base_type) => {
+            create_int_initial_value(base_type, convert_char_literal(v)).ok_or_else(mismatch)?
+        }
+        (dtp::InitialValue::Array(v), base_type) => {
+            // Only the elements counted by the element counter are set
+            let slice = match convert_default_dynamic_array_count(structured_type, var_declaration)?
+            {
+                Some(count) => v.get(..count).ok_or("Invalid element counter found")?,
+                None => v,
             };
             slice
-                .into_iter()
-                .map(|v| convert_initial_value_directly2(structured_type, var_declaration, v))
+                .iter()
+                .map(|v| {
+                    convert_initial_value_directly2(structured_type, var_declaration, base_type, v)
+                })
                 .collect::<Result<Vec<_>>>()
                 .map(msg::InitialValue::Array)?
         }
+        (dtp::InitialValue::Struct(_), _) => {
+            return Err(format!(
+                "Structure initialization of \"{}\" is not supported",
+                var_declaration.name()
+            )
+            .into())
+        }
+        (dtp::InitialValue::Enumerated(value), _) => {
+            return Err(format!(
+                "Enumerated value \"{value}\" of \"{}\" is not supported",
+                var_declaration.name()
+            )
+            .into())
+        }
+        (initial_value, base_type) => get_int_literal(initial_value)
+            .and_then(|v| create_int_initial_value(base_type, convert_int_literal(v)))
+            .ok_or_else(mismatch)?,
     };
     Ok(result)
 }
 
+fn get_int_literal(initial_value: &dtp::InitialValue) -> Option<&dtp::IntLiteral> {
+    match initial_value {
+        dtp::InitialValue::SINT(literal)
+        | dtp::InitialValue::INT(literal)
+        | dtp::InitialValue::DINT(literal)
+        | dtp::InitialValue::LINT(literal)
+        | dtp::InitialValue::USINT(literal)
+        | dtp::InitialValue::UINT(literal)
+        | dtp::InitialValue::UDINT(literal)
+        | dtp::InitialValue::ULINT(literal)
+        | dtp::InitialValue::BYTE(literal)
+        | dtp::InitialValue::WORD(literal)
+        | dtp::InitialValue::DWORD(literal)
+        | dtp::InitialValue::LWORD(literal) => Some(literal),
+        _ => None,
+    }
+}
+
+fn create_int_initial_value(
+    base_type: &msg::BaseType,
+    int_literal: msg::IntLiteral,
+) -> Option<msg::InitialValue> {
+    match base_type {
+        msg::BaseType::Byte => Some(msg::InitialValue::Byte(int_literal)),
+        msg::BaseType::Char => Some(msg::InitialValue::Char(int_literal)),
+        msg::BaseType::Int8 => Some(msg::InitialValue::Int8(int_literal)),
+        msg::BaseType::Uint8 => Some(msg::InitialValue::Uint8(int_literal)),
+        msg::BaseType::Int16 => Some(msg::InitialValue::Int16(int_literal)),
+        msg::BaseType::Uint16 => Some(msg::InitialValue::Uint16(int_literal)),
+        msg::BaseType::Int32 => Some(msg::InitialValue::Int32(int_literal)),
+        msg::BaseType::Uint32 => Some(msg::InitialValue::Uint32(int_literal)),
+        msg::BaseType::Int64 => Some(msg::InitialValue::Int64(int_literal)),
+        msg::BaseType::Uint64 => Some(msg::InitialValue::Uint64(int_literal)),
+        _ => None,
+    }
+}
+
+fn contains_nested_value(initial_value: &dtp::InitialValue) -> bool {
+    match initial_value {
+        dtp::InitialValue::Struct(_) | dtp::InitialValue::Enumerated(_) => true,
+        dtp::InitialValue::Array(values) => values.iter().any(contains_nested_value),
+        _ => false,
+    }
+}
+
 fn convert_char_literal(dtp_char_literal: &dtp::CharLiteral) -> msg::IntLiteral {
     match dtp_char_literal {
         dtp::CharLiteral::Value(char) | dtp::CharLiteral::Hex(char) => {
@@ -234,34 +1425,71 @@ fn convert_field_type(
     }
 }
 
+// The reference attributes keep the reference as written in the MSG file. Older DTP files
+// only flag the kind of reference with a BOOL, then it is recovered from the type name.
 fn convert_reference(
     module_name: &str,
+    options: &ConversionOptions,
     var_declaration: &dtp::VarDeclaration,
     dtp_reference_string: &str,
 ) -> Result<msg::Reference> {
-    let reference_parts: Vec<&str> = dtp_reference_string.split("_").collect();
-    if is_absolute_reference(var_declaration) && reference_parts.len() == 4 {
-        Ok(msg::Reference::Absolute {
-            package: module_name.to_string(),
-            file: reference_parts[3].to_string(),
-        })
-    } else if is_relative_reference(var_declaration) && reference_parts.len() == 4 {
-        Ok(msg::Reference::Relative {
-            file: reference_parts[3].to_string(),
-        })
-    } else if reference_parts.len() == 1 {
-        Ok(msg::Reference::Relative {
-            file: dtp_reference_string.to_string(),
-        })
-    } else {
-        Err("Invalid reference found".into())
+    let attributes = var_declaration.attributes();
+    if let Some(reference) = find_string_attribute(attributes, "ROS2_AbsoluteReference")
+        .or_else(|| find_string_attribute(attributes, "ROS2_RelativeReference"))
+    {
+        return type_name::decode_reference(reference);
     }
+    let decoded_type_name = options
+        .type_name_template
+        .decode(dtp_reference_string, module_name);
+    let file = type_name::to_ros_type_name(
+        match &decoded_type_name {
+            Some(decoded) => decoded.name,
+            None => type_name::split_qualified_name(dtp_reference_string).1,
+        },
+        msg::INTERFACE_KIND,
+    );
+    if !is_absolute_reference(var_declaration) {
+        return Ok(msg::Reference::Relative { file });
+    }
+    let package = match decoded_type_name.as_ref().and_then(|decoded| {
+        decoded
+            .package_name()
+            .filter(|_| !decoded.belongs_to(module_name))
+            .map(|package_name| (package_name, decoded.is_package_name_complete()))
+    }) {
+        Some((package_name, is_complete)) => {
+            if !is_complete {
+                warn!(
+                    "The package of \"{dtp_reference_string}\" is only known as \"{package_name}\" from the type name"
+                );
+            }
+            package_name.to_string()
+        }
+        None => module_name.to_string(),
+    };
+    Ok(msg::Reference::Absolute { package, file })
 }
 
 fn convert_default_dynamic_array_count(
     structured_type: &dtp::StructuredType,
     var_declaration: &dtp::VarDeclaration,
-) -> Result<usize> {
+) -> Result<Option<usize>> {
+    find_element_counter(structured_type, var_declaration)
+        .map(|helper| {
+            helper
+                .initial_value()
+                .as_ref()
+                .and_then(extract_usize_from_intial_value)
+                .ok_or("Invalid element counter found".into())
+        })
+        .transpose()
+}
+
+fn find_element_counter<'a>(
+    structured_type: &'a dtp::StructuredType,
+    var_declaration: &dtp::VarDeclaration,
+) -> Option<&'a dtp::VarDeclaration> {
     structured_type
         .children()
         .iter()
@@ -273,22 +1501,12 @@ fn convert_default_dynamic_array_count(
             }
             _ => None,
         })
-        .map(|helper| {
-            helper
-                .initial_value()
-                .as_ref()
-                .map(extract_usize_from_intial_value)
-                .flatten()
-                .ok_or("Invalid element counter found")
-        })
-        .transpose()?
-        .ok_or("No element counter found".into())
 }
 
 fn convert_array_bound(var_declaration: &dtp::VarDeclaration) -> Option<usize> {
     var_declaration
         .attributes()
-        .into_iter()
+        .iter()
         .find(|attribute| attribute.name == "ROS2_BoundDynamicArray")
         .map(|initial_value| &initial_value.value)
         .and_then(extract_usize_from_intial_value)
@@ -305,7 +1523,7 @@ fn extract_usize_from_intial_value(initial_value: &dtp::InitialValue) -> Option<
 }
 
 fn is_element_counter_of(main: &dtp::VarDeclaration, helper: &dtp::VarDeclaration) -> bool {
-    helper.attributes().into_iter().any(|attribute| {
+    helper.attributes().iter().any(|attribute| {
         let has_annotation = attribute.name == "ROS2_ElementCounter";
         let annotation_references_main = matches!(
             &attribute.value,
@@ -318,34 +1536,206 @@ fn is_element_counter_of(main: &dtp::VarDeclaration, helper: &dtp::VarDeclaratio
 fn is_dynamic_array(var_declaration: &dtp::VarDeclaration) -> bool {
     var_declaration
         .attributes()
-        .into_iter()
+        .iter()
         .any(|attr| attr.name == "ROS2_DynamicArray")
 }
 
 fn is_bound_dynamic_array(var_declaration: &dtp::VarDeclaration) -> bool {
     var_declaration
         .attributes()
-        .into_iter()
+        .iter()
         .any(|attr| attr.name == "ROS2_BoundDynamicArray")
 }
 
-fn is_relative_reference(var_declaration: &dtp::VarDeclaration) -> bool {
-    var_declaration
-        .attributes()
-        .into_iter()
-        .any(|attr| attr.name == "ROS2_RelativeReference")
-}
-
 fn is_absolute_reference(var_declaration: &dtp::VarDeclaration) -> bool {
     var_declaration
         .attributes()
-        .into_iter()
+        .iter()
         .any(|attr| attr.name == "ROS2_AbsoluteReference")
 }
 
 fn is_constant(var_declaration: &dtp::VarDeclaration) -> bool {
     var_declaration
         .attributes()
-        .into_iter()
+        .iter()
         .any(|attr| attr.name == "ROS2_CONSTANT")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_data_type(name: &str, data_type_kind: dtp::DataTypeKind) -> dtp::DataType {
+        dtp::DataType::new(
+            name,
+            &None,
+            &None,
+            &[],
+            &None,
+            &data_type_kind,
+            &[],
+            &dtp::ForeignXml::default(),
+        )
+    }
+
+    #[test]
+    fn mixed_case_enumerated_values_round_trip() {
+        let options = ConversionOptions::default();
+        let data_type = create_data_type(
+            "Betriebsart",
+            dtp::DataTypeKind::EnumeratedType(dtp::EnumeratedType::new(
+                &None,
+                &Some("Running".to_string()),
+                &[
+                    dtp::EnumeratedValue::new("Idle", &Some("Wartet".to_string())),
+                    dtp::EnumeratedValue::new("Running", &None),
+                    dtp::EnumeratedValue::new("value", &None),
+                    dtp::EnumeratedValue::new("FAULT", &None),
+                ],
+            )));
+        let structured_type = convert("tests", &options, &data_type, &[], &HashMap::new()).unwrap();
+        let fields = structured_type
+            .fields()
+            .iter()
+            .map(|field| (field.name(), field.comment().map(String::as_str)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            [
+                ("IDLE", Some("@IEC61499_Name(Idle). Wartet")),
+                ("RUNNING", Some("@IEC61499_Name(Running).")),
+                ("VALUE", Some("@IEC61499_Name(value).")),
+                ("FAULT", None),
+                ("value", Some("@IEC61499_EnumeratedType.")),
+            ]
+        );
+
+        let restored = msg_converter::convert("tests", &options, &None, &structured_type).unwrap();
+        let dtp::DataTypeKind::EnumeratedType(enumerated_type) = restored.data_type_kind() else {
+            panic!("{restored:?} is no enumeration");
+        };
+        let values = enumerated_type
+            .values()
+            .iter()
+            .map(|value| (value.name(), value.comment().as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [
+                ("Idle", Some("Wartet")),
+                ("Running", None),
+                ("value", None),
+                ("FAULT", None),
+            ]
+        );
+        assert_eq!(enumerated_type.initial_value().as_deref(), Some("Running"));
+    }
+
+    #[test]
+    fn type_names_differing_from_the_template_round_trip() {
+        let options = ConversionOptions::default();
+        let create_motor = |name: &str, reference: &str| {
+            create_data_type(
+                name,
+                dtp::DataTypeKind::StructuredType(dtp::StructuredType::new(
+                    &None,
+                    &[dtp::StructuredTypeChild::VarDeclaration(
+                        dtp::VarDeclaration::new(
+                            "drive",
+                            &dtp::BaseType::Custom(reference.to_string()),
+                            &None,
+                            &None,
+                            &None,
+                            &[],
+                            &dtp::ForeignXml::default(),
+                        ),
+                    )],
+                    &dtp::ForeignXml::default(),
+                )),
+            )
+        };
+
+        let structured_type = convert(
+            "tests",
+            &options,
+            &create_motor("Motor", "Antrieb"),
+            &[],
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            structured_type.comment().map(String::as_str),
+            Some("@IEC61499_TypeName(Motor).")
+        );
+        assert_eq!(
+            structured_type.fields()[0].comment().map(String::as_str),
+            Some("@IEC61499_TypeName(Antrieb).")
+        );
+        let restored = msg_converter::convert("tests", &options, &None, &structured_type).unwrap();
+        assert_eq!(restored.name(), "Motor");
+        let dtp::DataTypeKind::StructuredType(restored_structured_type) = restored.data_type_kind()
+        else {
+            panic!("{restored:?} is no structure");
+        };
+        let [dtp::StructuredTypeChild::VarDeclaration(var_declaration)] =
+            &restored_structured_type.children()[..]
+        else {
+            panic!("{restored_structured_type:?} has not one variable");
+        };
+        assert_eq!(
+            var_declaration.base_type(),
+            &dtp::BaseType::Custom("Antrieb".to_string())
+        );
+
+        // Names given by the template need no annotation
+        let structured_type = convert(
+            "tests",
+            &options,
+            &create_motor("ROS2_tests_msg_Motor", "ROS2_tests_msg_Antrieb"),
+            &[],
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(structured_type.comment(), None);
+        assert_eq!(structured_type.fields()[0].comment(), None);
+    }
+
+    #[test]
+    fn wrapper_fields_are_valid_ros_names() {
+        let options = ConversionOptions::default();
+        let subrange = dtp::Subrange {
+            lower_limit: 0,
+            upper_limit: 100,
+        };
+        for data_type_kind in [
+            dtp::DataTypeKind::DirectlyDerivedType(dtp::DirectlyDerivedType::new(
+                &dtp::BaseType::INT,
+                &None,
+                &None,
+            )),
+            dtp::DataTypeKind::ArrayType(dtp::ArrayType::new(
+                &dtp::BaseType::INT,
+                &[subrange],
+                &None,
+                &None,
+            )),
+            dtp::DataTypeKind::SubrangeType(dtp::SubrangeType::new(
+                &dtp::BaseType::INT,
+                &None,
+                &None,
+                &subrange,
+            )),
+        ] {
+            let data_type = create_data_type("Prozent", data_type_kind);
+            let structured_type =
+                convert("tests", &options, &data_type, &[], &HashMap::new()).unwrap();
+            let names = structured_type
+                .fields()
+                .iter()
+                .map(|field| field.name())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["value"], "{data_type:?}");
+        }
+        assert_eq!(convert_to_wrapper_field_name("IstWert"), "ist_wert");
+    }
+}
@@ -1,15 +1,14 @@
 use log::info;
 use nom::branch::alt;
-use nom::bytes::complete::{is_a, tag, take_till1};
-use nom::character::complete::{digit1, hex_digit1, oct_digit1};
-use nom::combinator::{map, map_res, opt, recognize};
-use nom::multi::{many0, many1};
-use nom::sequence::{delimited, preceded, tuple};
+use nom::bytes::complete::{tag, take_till1};
+use nom::character::complete::digit1;
+use nom::combinator::{cut, map, map_res, opt};
+use nom::sequence::{delimited, preceded};
 use nom::{Finish, IResult};
-use std::num::ParseIntError;
 use xmltree::{Element, XMLNode};
 
 use crate::business::error::{Error, Result};
+use crate::business::dtp_converter::literal_parser::parse_initial_value;
 use crate::core::dtp::*;
 
 pub fn read(path_to_file: &str) -> Result<DataType> {
@@ -31,8 +30,107 @@ pub fn parse_data_type(file: std::fs::File) -> Result<DataType> {
         .attributes
         .get_key_value(XML_ATTRIBUTE_COMMENT)
         .map(|key_value| key_value.1.clone());
+    let identification = get_filtered_children(&data_type_element, |child| {
+        child.name == XML_TAG_IDENTIFICATION
+    })
+    .into_iter()
+    .next()
+    .map(parse_identification);
+    let version_info = get_filtered_children(&data_type_element, |child| {
+        child.name == XML_TAG_VERSION_INFO
+    })
+    .into_iter()
+    .map(parse_version_info)
+    .collect::<Result<Vec<_>>>()?;
+    let compiler_info = get_filtered_children(&data_type_element, |child| {
+        child.name == XML_TAG_COMPILER_INFO
+    })
+    .into_iter()
+    .next()
+    .map(parse_compiler_info)
+    .transpose()?;
     let data_type_kind = parse_data_type_kind(&data_type_element)?;
-    Ok(DataType::new(&name, &comment, &data_type_kind))
+    let attributes = parse_attributes(&data_type_element)?;
+    let foreign_xml = parse_foreign_xml(
+        &data_type_element,
+        &[XML_ATTRIBUTE_NAME, XML_ATTRIBUTE_COMMENT],
+        |child| {
+            DataTypeKind::matches_any(&child.name)
+                || [
+                    XML_TAG_IDENTIFICATION,
+                    XML_TAG_VERSION_INFO,
+                    XML_TAG_COMPILER_INFO,
+                    XML_TAG_ATTRIBUTE,
+                ]
+                .contains(&child.name.as_str())
+        },
+    );
+    Ok(DataType::new(
+        &name,
+        &comment,
+        &identification,
+        &version_info,
+        &compiler_info,
+        &data_type_kind,
+        &attributes,
+        &foreign_xml,
+    ))
+}
+
+fn parse_identification(element: &Element) -> Identification {
+    let get = |attribute_name: &str| element.attributes.get(attribute_name).cloned();
+    Identification {
+        standard: get(XML_ATTRIBUTE_STANDARD),
+        classification: get(XML_ATTRIBUTE_CLASSIFICATION),
+        application_domain: get(XML_ATTRIBUTE_APPLICATION_DOMAIN),
+        function: get(XML_ATTRIBUTE_FUNCTION),
+        identification_type: get(XML_ATTRIBUTE_TYPE),
+        description: get(XML_ATTRIBUTE_DESCRIPTION),
+    }
+}
+
+fn parse_version_info(element: &Element) -> Result<VersionInfo> {
+    Ok(VersionInfo {
+        organization: element.attributes.get(XML_ATTRIBUTE_ORGANIZATION).cloned(),
+        version: get_required_attribute(element, XML_ATTRIBUTE_VERSION)?,
+        author: get_required_attribute(element, XML_ATTRIBUTE_AUTHOR)?,
+        date: get_required_attribute(element, XML_ATTRIBUTE_DATE)?,
+        remarks: element.attributes.get(XML_ATTRIBUTE_REMARKS).cloned(),
+    })
+}
+
+fn parse_compiler_info(element: &Element) -> Result<CompilerInfo> {
+    let compilers = get_filtered_children(element, |child| child.name == XML_TAG_COMPILER)
+        .into_iter()
+        .map(|compiler| {
+            Ok(Compiler {
+                language: get_required_attribute(compiler, XML_ATTRIBUTE_LANGUAGE)?,
+                vendor: get_required_attribute(compiler, XML_ATTRIBUTE_VENDOR)?,
+                product: get_required_attribute(compiler, XML_ATTRIBUTE_PRODUCT)?,
+                version: get_required_attribute(compiler, XML_ATTRIBUTE_VERSION)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(CompilerInfo {
+        package_name: element.attributes.get(XML_ATTRIBUTE_PACKAGE_NAME).cloned(),
+        header: element.attributes.get(XML_ATTRIBUTE_HEADER).cloned(),
+        classdef: element.attributes.get(XML_ATTRIBUTE_CLASSDEF).cloned(),
+        compilers,
+    })
+}
+
+fn get_required_attribute(element: &Element, attribute_name: &str) -> Result<String> {
+    element
+        .attributes
+        .get(attribute_name)
+        .cloned()
+        .ok_or_else(|| {
+            format!(
+                "No \"{attribute_name}\" attribute defined for \"{}\" element",
+                element.name
+            )
+            .into()
+        })
 }
 
 fn parse_data_type_kind(element: &Element) -> Result<DataTypeKind> {
@@ -43,18 +141,18 @@ fn parse_data_type_kind(element: &Element) -> Result<DataTypeKind> {
             .ok_or("No data type kind element found in \"DataType\" element")?;
 
     match data_type_kind_element.name.as_ref() {
-        // "DirectlyDerivedType" => Ok(DataTypeKind::DirectlyDerivedType(parse_directly_derived_type(
-        //     &data_type_kind_element,
-        // )?)),
-        // "EnumeratedType" => Ok(DataTypeKind::EnumeratedType(parse_enumerated_type(
-        //     &data_type_kind_element,
-        // )?)),
-        // "SubrangeType" => Ok(DataTypeKind::SubrangeType(parse_subrange_type(
-        //     &data_type_kind_element,
-        // )?)),
-        // "ArrayType" => Ok(DataTypeKind::ArrayType(parse_array_type(
-        //     &data_type_kind_element,
-        // )?)),
+        XML_TAG_DIRECTLY_DERIVED_TYPE => Ok(DataTypeKind::DirectlyDerivedType(
+            parse_directly_derived_type(data_type_kind_element)?,
+        )),
+        XML_TAG_ENUMERATED_TYPE => Ok(DataTypeKind::EnumeratedType(parse_enumerated_type(
+            data_type_kind_element,
+        )?)),
+        XML_TAG_SUBRANGE_TYPE => Ok(DataTypeKind::SubrangeType(parse_subrange_type(
+            data_type_kind_element,
+        )?)),
+        XML_TAG_ARRAY_TYPE => Ok(DataTypeKind::ArrayType(parse_array_type(
+            data_type_kind_element,
+        )?)),
         XML_TAG_STRUCTURED_TYPE => Ok(DataTypeKind::StructuredType(parse_structured_type(
             data_type_kind_element,
         )?)),
@@ -66,13 +164,159 @@ fn parse_data_type_kind(element: &Element) -> Result<DataTypeKind> {
     }
 }
 
+fn parse_directly_derived_type(element: &Element) -> Result<DirectlyDerivedType> {
+    let base_type = parse_derived_base_type(element)?;
+    let initial_value = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_INITIAL_VALUE)
+        .map(|key_value| key_value.1.clone())
+        .map(|value| parse_initial_value(&base_type, &None, &value))
+        .transpose()?;
+    let comment = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_COMMENT)
+        .map(|key_value| key_value.1.clone());
+    Ok(DirectlyDerivedType::new(&base_type, &initial_value, &comment))
+}
+
+fn parse_array_type(element: &Element) -> Result<ArrayType> {
+    let base_type = parse_derived_base_type(element)?;
+    let subranges = get_filtered_children(element, |child| child.name == XML_TAG_SUBRANGE)
+        .into_iter()
+        .map(parse_subrange)
+        .collect::<Result<Vec<_>>>()?;
+    if subranges.is_empty() {
+        return Err("No \"Subrange\" element found in \"ArrayType\" element".into());
+    }
+    // Die Initialwerte werden wie bei einem Array mit fester Größe gelesen
+    let capacity = subranges
+        .iter()
+        .map(|subrange| (subrange.upper_limit - subrange.lower_limit + 1) as usize)
+        .product();
+    let initial_values = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_INITIAL_VALUES)
+        .map(|key_value| key_value.1.clone())
+        .map(|value| {
+            parse_initial_value(
+                &base_type,
+                &Some(ArraySize::Static(Capacity::InPlace(capacity))),
+                &value,
+            )
+        })
+        .transpose()?;
+    let comment = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_COMMENT)
+        .map(|key_value| key_value.1.clone());
+    Ok(ArrayType::new(&base_type, &subranges, &initial_values, &comment))
+}
+
+fn parse_derived_base_type(element: &Element) -> Result<BaseType> {
+    element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_BASE_TYPE)
+        .map(|key_value| key_value.1.clone())
+        .map(|value| parse_base_type(value.as_str()))
+        .ok_or(format!(
+            "No \"BaseType\" attribute defined for \"{}\" element",
+            element.name
+        ))?
+}
+
+fn parse_enumerated_type(element: &Element) -> Result<EnumeratedType> {
+    let comment = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_COMMENT)
+        .map(|comment| comment.1.clone());
+    let initial_value = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_INITIAL_VALUE)
+        .map(|key_value| key_value.1.clone());
+    let values = get_filtered_children(element, |child| child.name == XML_TAG_ENUMERATED_VALUE)
+        .into_iter()
+        .map(parse_enumerated_value)
+        .collect::<Result<Vec<_>>>()?;
+    if values.is_empty() {
+        return Err("No \"EnumeratedValue\" element found in \"EnumeratedType\" element".into());
+    }
+    Ok(EnumeratedType::new(&comment, &initial_value, &values))
+}
+
+fn parse_enumerated_value(element: &Element) -> Result<EnumeratedValue> {
+    let name = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_NAME)
+        .map(|key_value| key_value.1.clone())
+        .ok_or("No \"Name\" attribute found on \"EnumeratedValue\" element")?;
+    let comment = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_COMMENT)
+        .map(|key_value| key_value.1.clone());
+    Ok(EnumeratedValue::new(&name, &comment))
+}
+
+fn parse_subrange_type(element: &Element) -> Result<SubrangeType> {
+    let base_type = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_BASE_TYPE)
+        .map(|key_value| key_value.1.clone())
+        .map(|value| parse_base_type(value.as_str()))
+        .ok_or("No \"BaseType\" attribute defined for \"SubrangeType\" element")??;
+    let initial_value = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_INITIAL_VALUE)
+        .map(|key_value| key_value.1.clone())
+        .map(|value| parse_initial_value(&base_type, &None, &value))
+        .transpose()?;
+    let comment = element
+        .attributes
+        .get_key_value(XML_ATTRIBUTE_COMMENT)
+        .map(|key_value| key_value.1.clone());
+    let subrange = parse_optional_subrange(element)?
+        .ok_or("No \"Subrange\" element found in \"SubrangeType\" element")?;
+    Ok(SubrangeType::new(&base_type, &initial_value, &comment, &subrange))
+}
+
+fn parse_optional_subrange(element: &Element) -> Result<Option<Subrange>> {
+    get_filtered_children(element, |child| child.name == XML_TAG_SUBRANGE)
+        .into_iter()
+        .next()
+        .map(parse_subrange)
+        .transpose()
+}
+
+fn parse_subrange(element: &Element) -> Result<Subrange> {
+    let parse_limit = |attribute_name: &str| -> Result<i64> {
+        element
+            .attributes
+            .get_key_value(attribute_name)
+            .map(|key_value| key_value.1.trim().parse::<i64>().map_err(Error::custom))
+            .ok_or(format!(
+                "No \"{attribute_name}\" attribute defined for \"Subrange\" element"
+            ))?
+    };
+    let lower_limit = parse_limit(XML_ATTRIBUTE_LOWER_LIMIT)?;
+    let upper_limit = parse_limit(XML_ATTRIBUTE_UPPER_LIMIT)?;
+    if lower_limit > upper_limit {
+        return Err(format!("Invalid subrange {lower_limit}..{upper_limit}").into());
+    }
+    Ok(Subrange {
+        lower_limit,
+        upper_limit,
+    })
+}
+
 fn parse_structured_type(element: &Element) -> Result<StructuredType> {
     let comment = element
         .attributes
         .get_key_value(XML_ATTRIBUTE_COMMENT)
         .map(|comment| comment.1.clone());
     let children = parse_structured_type_children(element)?;
-    Ok(StructuredType::new(&comment, &children))
+    let foreign_xml = parse_foreign_xml(element, &[XML_ATTRIBUTE_COMMENT], |child| {
+        StructuredTypeChild::matches_any(&child.name)
+    });
+    Ok(StructuredType::new(&comment, &children, &foreign_xml))
 }
 
 fn parse_structured_type_children(element: &Element) -> Result<Vec<StructuredTypeChild>> {
@@ -86,9 +330,11 @@ fn parse_structured_type_children(element: &Element) -> Result<Vec<StructuredTyp
             XML_TAG_VAR_DECLARATION => result.push(StructuredTypeChild::VarDeclaration(
                 parse_var_declaration(structured_type_child_element)?,
             )),
-            // "SubrangeVarDeclaration" => result.push(StructuredTypeChild::SubrangeVarDeclaration(
-            //     parse_subrange_var_declaration(element),
-            // )),
+            XML_TAG_SUBRANGE_VAR_DECLARATION => {
+                result.push(StructuredTypeChild::SubrangeVarDeclaration(
+                    parse_subrange_var_declaration(structured_type_child_element)?,
+                ))
+            }
             _ => {
                 return Err(format!(
                     "Unsupported StructuredType child element : {}",
@@ -129,13 +375,24 @@ fn parse_var_declaration(element: &Element) -> Result<VarDeclaration> {
         .attributes
         .get_key_value(XML_ATTRIBUTE_INITIAL_VALUE)
         .map(|key_value| key_value.1.clone())
-        .map(|value| parse_initial_value(&base_type, &array_size)(value.as_str()))
+        .map(|value| parse_initial_value(&base_type, &array_size, &value))
         .transpose()?;
     let comment = element
         .attributes
         .get_key_value(XML_ATTRIBUTE_COMMENT)
         .map(|key_value| key_value.1.clone());
     let attributes = parse_attributes(element)?;
+    let foreign_xml = parse_foreign_xml(
+        element,
+        &[
+            XML_ATTRIBUTE_NAME,
+            XML_ATTRIBUTE_TYPE,
+            XML_ATTRIBUTE_ARRAY_SIZE,
+            XML_ATTRIBUTE_INITIAL_VALUE,
+            XML_ATTRIBUTE_COMMENT,
+        ],
+        |child| child.name == XML_TAG_ATTRIBUTE,
+    );
 
     Ok(VarDeclaration::new(
         &name,
@@ -144,6 +401,29 @@ fn parse_var_declaration(element: &Element) -> Result<VarDeclaration> {
         &initial_value,
         &comment,
         &attributes,
+        &foreign_xml,
+    ))
+}
+
+// A SubrangeVarDeclaration is a VarDeclaration without array size,
+// whose values are limited by a "Subrange" child element
+fn parse_subrange_var_declaration(element: &Element) -> Result<SubrangeVarDeclaration> {
+    if element.attributes.contains_key(XML_ATTRIBUTE_ARRAY_SIZE) {
+        return Err(format!(
+            "\"{XML_ATTRIBUTE_ARRAY_SIZE}\" is not allowed on \"{}\" element",
+            element.name
+        )
+        .into());
+    }
+    let var_declaration = parse_var_declaration(element)?;
+    let subrange = parse_optional_subrange(element)?;
+    Ok(SubrangeVarDeclaration::new(
+        var_declaration.name(),
+        var_declaration.base_type(),
+        &subrange,
+        var_declaration.initial_value(),
+        var_declaration.comment(),
+        var_declaration.attributes(),
     ))
 }
 
@@ -170,7 +450,7 @@ fn parse_attribute(element: &Element) -> Result<Attribute> {
         .attributes
         .get_key_value(XML_ATTRIBUTE_VALUE)
         .map(|key_value| key_value.1.clone())
-        .map(|value| parse_initial_value(&base_type, &None)(value.as_str()))
+        .map(|value| parse_initial_value(&base_type, &None, &value))
         .transpose()?
         .ok_or("No \"Value\" attribute defined for \"Attribute\" element")?;
     let comment = element
@@ -205,16 +485,12 @@ fn parse_base_type(string: &str) -> Result<BaseType> {
         map(tag("LREAL"), |_| BaseType::LREAL),
         map(tag("CHAR"), |_| BaseType::CHAR),
         map(
-            tuple((tag("STRING"), opt(delimited(tag("["), digit1, tag("]"))))),
-            |(_, optional_bound): (&str, Option<&str>)| {
-                BaseType::STRING(optional_bound.map(|digits| digits.parse().unwrap()))
-            },
+            preceded(tag("STRING"), opt(parse_string_bound)),
+            BaseType::STRING,
         ),
         map(
-            tuple((tag("WSTRING"), opt(delimited(tag("["), digit1, tag("]"))))),
-            |(_, optional_bound): (&str, Option<&str>)| {
-                BaseType::WSTRING(optional_bound.map(|digits| digits.parse().unwrap()))
-            },
+            preceded(tag("WSTRING"), opt(parse_string_bound)),
+            BaseType::WSTRING,
         ),
         map(take_till1(|c| c == '"'), |custom_type: &str| {
             BaseType::Custom(custom_type.to_string())
@@ -225,6 +501,11 @@ fn parse_base_type(string: &str) -> Result<BaseType> {
     .1)
 }
 
+// A bound too large for usize must not leave the type to be read as a custom type
+fn parse_string_bound(input: &str) -> IResult<&str, usize> {
+    delimited(tag("["), cut(map_res(digit1, str::parse)), tag("]"))(input)
+}
+
 fn parse_array_size(input: &str) -> Result<ArraySize> {
     if input == "*" {
         Ok(ArraySize::Dynamic)
@@ -244,59 +525,48 @@ fn parse_array_size(input: &str) -> Result<ArraySize> {
     }
 }
 
-macro_rules! parse_primitive_initial_value {
-    ($iec61131_primitive:ident) => {
-        Box::new(|str| {
-            str.parse()
-                .map(InitialValue::$iec61131_primitive)
-                .map_err(Error::custom)
-        })
-    };
+// Attributes and child elements, which are not modeled, are kept with their position
+fn parse_foreign_xml(
+    element: &Element,
+    known_attributes: &[&str],
+    is_known_child: fn(&Element) -> bool,
+) -> ForeignXml {
+    ForeignXml {
+        attributes: element
+            .attributes
+            .iter()
+            .enumerate()
+            .filter(|(_, (name, _))| !known_attributes.contains(&name.as_str()))
+            .map(|(position, (name, value))| (position, name.clone(), value.clone()))
+            .collect(),
+        elements: get_children(element)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, child)| !is_known_child(child))
+            .map(|(position, child)| (position, parse_foreign_element(child)))
+            .collect(),
+    }
 }
 
-fn parse_initial_value<'a>(
-    base_type: &'a BaseType,
-    array_size: &'a Option<ArraySize>,
-) -> Box<dyn FnMut(&str) -> Result<InitialValue> + 'a> {
-    if let Some(array_size) = array_size {
-        Box::new(move |str| {
-            let trimmmed = str.trim();
-            if !(trimmmed.starts_with('[') && trimmmed.ends_with(']')) {
-                return match array_size {
-                    ArraySize::Dynamic => Ok(InitialValue::Array(Vec::new())),
-                    ArraySize::Static(_) => Err("Static arrays must use '[]'".into()),
-                };
-            }
-            let values = trimmmed[1..trimmmed.len() - 1]
-                .split(',')
-                .map(|value| parse_initial_value(base_type, &None)(value.trim()))
-                .collect::<Result<Vec<_>>>()?;
-            Ok(InitialValue::Array(values))
-        })
-    } else {
-        match base_type {
-            BaseType::BOOL => Box::new(|str| parse_bool_literal(str).map(InitialValue::BOOL)),
-            BaseType::SINT => Box::new(|str| parse_int_literal(str).map(InitialValue::SINT)),
-            BaseType::INT => Box::new(|str| parse_int_literal(str).map(InitialValue::INT)),
-            BaseType::DINT => Box::new(|str| parse_int_literal(str).map(InitialValue::DINT)),
-            BaseType::LINT => Box::new(|str| parse_int_literal(str).map(InitialValue::LINT)),
-            BaseType::USINT => Box::new(|str| parse_int_literal(str).map(InitialValue::USINT)),
-            BaseType::UINT => Box::new(|str| parse_int_literal(str).map(InitialValue::UINT)),
-            BaseType::UDINT => Box::new(|str| parse_int_literal(str).map(InitialValue::UDINT)),
-            BaseType::ULINT => Box::new(|str| parse_int_literal(str).map(InitialValue::ULINT)),
-            BaseType::BYTE => Box::new(|str| parse_int_literal(str).map(InitialValue::BYTE)),
-            BaseType::WORD => Box::new(|str| parse_int_literal(str).map(InitialValue::WORD)),
-            BaseType::DWORD => Box::new(|str| parse_int_literal(str).map(InitialValue::DWORD)),
-            BaseType::LWORD => Box::new(|str| parse_int_literal(str).map(InitialValue::LWORD)),
-            BaseType::REAL => parse_primitive_initial_value!(REAL),
-            BaseType::LREAL => parse_primitive_initial_value!(LREAL),
-            BaseType::CHAR => Box::new(|str| parse_char_literal(str).map(InitialValue::CHAR)),
-            BaseType::STRING(_) => Box::new(|str| parse_string_literal(str).map(InitialValue::STRING)),
-            BaseType::WSTRING(_) => {
-                Box::new(|str| parse_wstring_literal(str).map(InitialValue::WSTRING))
-            }
-            BaseType::Custom(_) => unreachable!(),
-        }
+fn parse_foreign_element(element: &Element) -> ForeignElement {
+    ForeignElement {
+        name: element.name.clone(),
+        attributes: element
+            .attributes
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect(),
+        children: element
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                XMLNode::Element(child) => Some(ForeignNode::Element(parse_foreign_element(child))),
+                XMLNode::Text(text) | XMLNode::CData(text) if !text.trim().is_empty() => {
+                    Some(ForeignNode::Text(text.clone()))
+                }
+                _ => None,
+            })
+            .collect(),
     }
 }
 
@@ -317,124 +587,17 @@ fn get_children(parent: &Element) -> Vec<&Element> {
         .collect()
 }
 
-fn parse_bool_literal(input: &str) -> Result<BoolLiteral> {
-    if input == "TRUE" || input == "FALSE" {
-        Ok(BoolLiteral::String(input == "TRUE"))
-    } else if input == "1" || input == "0" {
-        Ok(BoolLiteral::Int(input == "1"))
-    } else {
-        Err("Invalid boolean literal".into())
-    }
-}
-
-fn parse_char_literal(input: &str) -> Result<CharLiteral> {
-    if !(input.starts_with("'") && input.ends_with("'")) {
-        return Err("Input must be delimited with 'value'".into());
-    }
-    let actual_value = &input[1..input.len() - 1];
-
-    // Annahme: CHAR's werden nur im Format '$<h0><h1>' eingegeben
-    if !actual_value.starts_with("$") {
-        return Err("Hex char literals expected".into());
-    }
-    // Strip the '$' prefix
-    let hex_part = &actual_value[1..];
-    // Parse the remaining part as a hexadecimal number
-    let number = u8::from_str_radix(hex_part, 16).map_err(|_| "Invalid hexadecimal number")?;
-    match char::from_u32(number as u32) {
-        Some(c) => Ok(CharLiteral::Hex(c)),
-        None => Err("Invalid char given. UTF-8 hex expected.".into()),
-    }
-}
-
-fn parse_string_literal(input: &str) -> Result<String> {
-    if !(input.starts_with("'") && input.ends_with("'")) {
-        return Err("InitialValue of STRING must be delimited with ''".into());
-    }
-    Ok(input[1..input.len() - 1].to_string())
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn parse_wstring_literal(input: &str) -> Result<String> {
-    if !(input.starts_with("\"") && input.ends_with("\"")) {
-        return Err("InitialValue of WSTRING must be delimited with &quot;&quot;".into());
+    #[test]
+    fn string_bounds_exceeding_usize_are_rejected() {
+        assert!(matches!(
+            parse_base_type("STRING[80]"),
+            Ok(BaseType::STRING(Some(80)))
+        ));
+        assert!(parse_base_type("STRING[99999999999999999999]").is_err());
+        assert!(parse_base_type("WSTRING[99999999999999999999]").is_err());
     }
-    Ok(input[1..input.len() - 1].to_string())
-}
-
-fn parse_int_literal(input: &str) -> Result<IntLiteral> {
-    Ok(alt((
-        hex_int_parser,
-        octal_int_parser,
-        bin_int_parser,
-        dec_int_parser,
-    ))(input)
-    .map_err(|e| e.to_owned())
-    .finish()?
-    .1)
-}
-
-fn dec_int_parser(input: &str) -> IResult<&str, IntLiteral> {
-    map_res(
-        tuple((
-            opt(alt((tag("-"), tag("+")))),
-            recognize(tuple((digit1, many0(preceded(opt(tag("_")), digit1))))),
-        )),
-        |(sign, str): (Option<&str>, &str)| {
-            let cleaned_str = str.replace("_", "");
-            Ok::<IntLiteral, ParseIntError>(if let Some(sign) = sign {
-                let to_parse = format!("{sign}{cleaned_str}");
-                let i64 = i64::from_str_radix(&to_parse, 10)?;
-                IntLiteral::SignedDecimalInt(i64)
-            } else {
-                let u64 = u64::from_str_radix(&cleaned_str, 10)?;
-                IntLiteral::UnsignedDecimalInt(u64)
-            })
-        },
-    )(input)
-}
-
-fn hex_int_parser(input: &str) -> IResult<&str, IntLiteral> {
-    preceded(
-        tag("16#"),
-        map_res(
-            recognize(many1(preceded(opt(tag("_")), hex_digit1))),
-            |str: &str| {
-                let cleaned_str = str.replace("_", "");
-                let u64 = u64::from_str_radix(&cleaned_str, 16)?;
-                Ok::<IntLiteral, ParseIntError>(IntLiteral::HexalInt(u64))
-            },
-        ),
-    )(input)
-}
-
-fn octal_int_parser(input: &str) -> IResult<&str, IntLiteral> {
-    preceded(
-        tag("8#"),
-        map_res(
-            recognize(many1(preceded(opt(tag("_")), oct_digit1))),
-            |str: &str| {
-                let cleaned_str = str.replace("_", "");
-                let u64 = u64::from_str_radix(&cleaned_str, 8)?;
-                Ok::<IntLiteral, ParseIntError>(IntLiteral::OctalInt(u64))
-            },
-        ),
-    )(input)
-}
-
-fn bin_int_parser(input: &str) -> IResult<&str, IntLiteral> {
-    preceded(
-        tag("2#"),
-        map_res(
-            recognize(many1(preceded(opt(tag("_")), bin_digit))),
-            |str: &str| {
-                let cleaned_str = str.replace("_", "");
-                let u64 = u64::from_str_radix(&cleaned_str, 2)?;
-                Ok::<IntLiteral, ParseIntError>(IntLiteral::BinaryInt(u64))
-            },
-        ),
-    )(input)
-}
-
-fn bin_digit(input: &str) -> IResult<&str, &str> {
-    is_a("01")(input)
 }
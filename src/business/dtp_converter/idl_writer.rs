@@ -0,0 +1,303 @@
+use std::fs;
+
+use crate::business::error::Result;
+use crate::business::string_codec::encode_ros_string;
+use crate::core::idl::*;
+use crate::core::msg::{
+    Annotation, BaseType, BoolLiteral, Constraint, Field, FieldType, InitialValue, IntLiteral,
+    Reference, StructuredType,
+};
+use crate::core::{action, msg, srv};
+
+const INDENT: &str = "  ";
+
+pub fn write(package_name: &str, interface: &Interface, to_directory: &str) -> Result<()> {
+    let file_name = interface.name();
+    let path_to_file = format!("{to_directory}{file_name}.{FILE_EXTENSION}");
+    let file_content: String = interface_as_string(package_name, interface);
+    fs::write(path_to_file, file_content)?;
+    Ok(())
+}
+
+// Follows the layout of the IDL files rosidl generates from MSG, SRV and ACTION files
+fn interface_as_string(package_name: &str, interface: &Interface) -> String {
+    let (interface_kind, structured_types) = match interface {
+        Interface::Message(structured_type) => (msg::INTERFACE_KIND, vec![structured_type]),
+        Interface::Service(service) => (
+            srv::INTERFACE_KIND,
+            vec![service.request(), service.response()],
+        ),
+        Interface::Action(action) => (
+            action::INTERFACE_KIND,
+            vec![action.goal(), action.result(), action.feedback()],
+        ),
+    };
+    let fields: Vec<&Field> = structured_types
+        .iter()
+        .flat_map(|structured_type| structured_type.fields().iter())
+        .collect();
+
+    let mut result = String::new();
+    let includes = includes_as_string(package_name, &fields);
+    if !includes.is_empty() {
+        result.push_str(&includes);
+        result.push('\n');
+    }
+    result.push_str(&format!("module {package_name} {{\n"));
+    result.push_str(&format!("{INDENT}module {interface_kind} {{\n"));
+    let indent = INDENT.repeat(2);
+    result.push_str(&typedefs_as_string(package_name, &fields, &indent));
+    for structured_type in structured_types.iter() {
+        result.push_str(&constants_module_as_string(package_name, structured_type, &indent));
+        result.push_str(&struct_as_string(package_name, structured_type, &indent));
+    }
+    result.push_str(&format!("{INDENT}}};\n"));
+    result.push_str("};\n");
+    result
+}
+
+fn includes_as_string(package_name: &str, fields: &[&Field]) -> String {
+    let mut includes: Vec<String> = Vec::new();
+    for field in fields.iter() {
+        if let BaseType::Custom(reference) = field.base_type() {
+            let include = format!(
+                "#include \"{}.{FILE_EXTENSION}\"\n",
+                scoped_name(package_name, reference).replace("::", "/")
+            );
+            if !includes.contains(&include) {
+                includes.push(include);
+            }
+        }
+    }
+    includes.concat()
+}
+
+// Like rosidl, static arrays are declared as typedef, e.g. "typedef int8 int8__3[3];"
+fn typedefs_as_string(package_name: &str, fields: &[&Field], indent: &str) -> String {
+    let mut typedefs: Vec<String> = Vec::new();
+    for field in fields.iter() {
+        if let Some(Constraint::StaticArray(size)) = field.constraint() {
+            let element_type = base_type_as_string(package_name, field.base_type());
+            let typedef = format!(
+                "{indent}typedef {element_type} {}[{size}];\n",
+                typedef_name(&element_type, *size)
+            );
+            if !typedefs.contains(&typedef) {
+                typedefs.push(typedef);
+            }
+        }
+    }
+    typedefs.concat()
+}
+
+fn typedef_name(element_type: &str, size: usize) -> String {
+    let element_type = element_type
+        .replace("::", "__")
+        .replace(['<', ' '], "__")
+        .replace('>', "");
+    format!("{element_type}__{size}")
+}
+
+fn constants_module_as_string(
+    package_name: &str,
+    structured_type: &StructuredType,
+    indent: &str,
+) -> String {
+    let constants: Vec<(&Field, &InitialValue)> = structured_type
+        .fields()
+        .iter()
+        .filter_map(|field| match field.field_type() {
+            FieldType::Constant(initial_value) => Some((field, initial_value)),
+            FieldType::Variable(_) => None,
+        })
+        .collect();
+    if constants.is_empty() {
+        return String::new();
+    }
+    let inner_indent = format!("{indent}{INDENT}");
+    let mut result = format!(
+        "{indent}module {}{CONSTANTS_MODULE_SUFFIX} {{\n",
+        structured_type.name()
+    );
+    for (field, initial_value) in constants.iter() {
+        result.push_str(&annotations_as_string(field.comment(), field.annotations(), &inner_indent));
+        result.push_str(&format!(
+            "{inner_indent}const {} {} = {};\n",
+            base_type_as_string(package_name, field.base_type()),
+            field.name(),
+            literal_as_string(initial_value)
+        ));
+    }
+    result.push_str(&format!("{indent}}};\n"));
+    result
+}
+
+fn struct_as_string(package_name: &str, structured_type: &StructuredType, indent: &str) -> String {
+    let inner_indent = format!("{indent}{INDENT}");
+    let mut result = annotations_as_string(structured_type.comment(), &[], indent);
+    result.push_str(&format!("{indent}struct {} {{\n", structured_type.name()));
+    let members: Vec<&Field> = structured_type
+        .fields()
+        .iter()
+        .filter(|field| matches!(field.field_type(), FieldType::Variable(_)))
+        .collect();
+    for field in members.iter() {
+        result.push_str(&member_as_string(package_name, field, &inner_indent));
+    }
+    if members.is_empty() {
+        result.push_str(&format!("{inner_indent}uint8 {EMPTY_STRUCTURE_MEMBER_NAME};\n"));
+    }
+    result.push_str(&format!("{indent}}};\n"));
+    result
+}
+
+fn member_as_string(package_name: &str, field: &Field, indent: &str) -> String {
+    let mut result = annotations_as_string(field.comment(), field.annotations(), indent);
+    if let FieldType::Variable(Some(initial_value)) = field.field_type() {
+        result.push_str(&format!(
+            "{indent}@default (value={})\n",
+            default_value_as_string(initial_value)
+        ));
+    }
+    let base_type = base_type_as_string(package_name, field.base_type());
+    let type_spec = match field.constraint() {
+        Some(Constraint::StaticArray(size)) => typedef_name(&base_type, *size),
+        Some(Constraint::UnboundedDynamicArray) => format!("sequence<{base_type}>"),
+        Some(Constraint::BoundedDynamicArray(bound)) => format!("sequence<{base_type}, {bound}>"),
+        None => base_type,
+    };
+    result.push_str(&format!("{indent}{type_spec} {};\n", field.name()));
+    result
+}
+
+fn annotations_as_string(
+    comment: Option<&String>,
+    annotations: &[Annotation],
+    indent: &str,
+) -> String {
+    let mut result = String::new();
+    if let Some(comment) = comment {
+        let text = comment
+            .split('\n')
+            .map(string_literal_as_string)
+            .collect::<Vec<String>>()
+            .join(&format!(" \"\\n\"\n{indent}{INDENT}"));
+        result.push_str(&format!(
+            "{indent}@verbatim (language=\"comment\", text=\n{indent}{INDENT}{text})\n"
+        ));
+    }
+    for annotation in annotations.iter() {
+        match &annotation.parameters {
+            Some(parameters) => {
+                result.push_str(&format!("{indent}@{} ({parameters})\n", annotation.name))
+            }
+            None => result.push_str(&format!("{indent}@{}\n", annotation.name)),
+        }
+    }
+    result
+}
+
+fn base_type_as_string(package_name: &str, base_type: &BaseType) -> String {
+    match base_type {
+        BaseType::Bool => "boolean".to_string(),
+        BaseType::Byte => "octet".to_string(),
+        BaseType::Float32 => "float".to_string(),
+        BaseType::Float64 => "double".to_string(),
+        BaseType::Int8 => "int8".to_string(),
+        BaseType::Uint8 => "uint8".to_string(),
+        BaseType::Int16 => "int16".to_string(),
+        BaseType::Uint16 => "uint16".to_string(),
+        BaseType::Int32 => "int32".to_string(),
+        BaseType::Uint32 => "uint32".to_string(),
+        BaseType::Int64 => "int64".to_string(),
+        BaseType::Uint64 => "uint64".to_string(),
+        BaseType::Char => "char".to_string(),
+        BaseType::String(bound) => bound
+            .map(|bound| format!("string<{bound}>"))
+            .unwrap_or_else(|| "string".to_string()),
+        BaseType::Wstring(bound) => bound
+            .map(|bound| format!("wstring<{bound}>"))
+            .unwrap_or_else(|| "wstring".to_string()),
+        BaseType::Custom(reference) => scoped_name(package_name, reference),
+    }
+}
+
+fn scoped_name(package_name: &str, reference: &Reference) -> String {
+    match reference {
+        Reference::Relative { file } => format!("{package_name}::{}::{file}", msg::INTERFACE_KIND),
+        Reference::Absolute { package, file } => {
+            format!("{package}::{}::{file}", msg::INTERFACE_KIND)
+        }
+    }
+}
+
+// rosidl stores array defaults as string of a Python tuple, e.g. "(1, 2)"
+fn default_value_as_string(initial_value: &InitialValue) -> String {
+    match initial_value {
+        InitialValue::Array(values) => string_literal_as_string(&format!(
+            "({})",
+            values
+                .iter()
+                .map(tuple_element_as_string)
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+        _ => literal_as_string(initial_value),
+    }
+}
+
+fn tuple_element_as_string(initial_value: &InitialValue) -> String {
+    match initial_value {
+        InitialValue::Bool(BoolLiteral::String(true) | BoolLiteral::Int(true)) => {
+            "True".to_string()
+        }
+        InitialValue::Bool(BoolLiteral::String(false) | BoolLiteral::Int(false)) => {
+            "False".to_string()
+        }
+        InitialValue::String(value) | InitialValue::Wstring(value) => {
+            format!("'{}'", encode_ros_string(value, '\''))
+        }
+        _ => literal_as_string(initial_value),
+    }
+}
+
+fn literal_as_string(initial_value: &InitialValue) -> String {
+    match initial_value {
+        InitialValue::Bool(BoolLiteral::String(true) | BoolLiteral::Int(true)) => {
+            "TRUE".to_string()
+        }
+        InitialValue::Bool(BoolLiteral::String(false) | BoolLiteral::Int(false)) => {
+            "FALSE".to_string()
+        }
+        InitialValue::Byte(value)
+        | InitialValue::Int8(value)
+        | InitialValue::Uint8(value)
+        | InitialValue::Int16(value)
+        | InitialValue::Uint16(value)
+        | InitialValue::Int32(value)
+        | InitialValue::Uint32(value)
+        | InitialValue::Int64(value)
+        | InitialValue::Uint64(value)
+        | InitialValue::Char(value) => int_literal_as_string(value),
+        InitialValue::Float32(value) => value.to_string(),
+        InitialValue::Float64(value) => value.to_string(),
+        InitialValue::String(value) | InitialValue::Wstring(value) => {
+            string_literal_as_string(value)
+        }
+        InitialValue::Array(_) => default_value_as_string(initial_value),
+    }
+}
+
+// IDL has no binary literals, those are written as decimals
+fn int_literal_as_string(int_literal: &IntLiteral) -> String {
+    match int_literal {
+        IntLiteral::SignedDecimalInt(i64) => format!("{i64}"),
+        IntLiteral::UnsignedDecimalInt(u64) | IntLiteral::BinaryInt(u64) => format!("{u64}"),
+        IntLiteral::OctalInt(u64) => format!("0{u64:o}"),
+        IntLiteral::HexalInt(u64) => format!("0x{u64:X}"),
+    }
+}
+
+fn string_literal_as_string(value: &str) -> String {
+    format!("\"{}\"", encode_ros_string(value, '"'))
+}
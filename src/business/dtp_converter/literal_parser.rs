@@ -0,0 +1,584 @@
+use nom::branch::alt;
+use nom::bytes::complete::{is_a, tag, take_while, take_while1};
+use nom::character::complete::{anychar, char, digit1, hex_digit1, multispace0, none_of, oct_digit1, one_of};
+use nom::combinator::{all_consuming, cut, map, map_opt, map_res, opt, recognize, verify};
+use nom::multi::{many0, separated_list0};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated, tuple};
+use nom::{Finish, IResult};
+use std::num::ParseIntError;
+
+use crate::business::error::Result;
+use crate::business::string_codec::decode_iec_string;
+use crate::core::dtp::*;
+
+// Literals according to IEC 61131-3 as the 4diac IDE saves them,
+// e.g. "INT#5", "16#FF_FF", "REAL#1.5E3", "'it$'s'", "[3(0), 1]" or "(x:=1.0, y:=2.0)"
+pub fn parse_initial_value(
+    base_type: &BaseType,
+    array_size: &Option<ArraySize>,
+    input: &str,
+) -> Result<InitialValue> {
+    let trimmed = input.trim();
+    let result = match array_size {
+        // 4diac leaves dynamic arrays without initial value empty
+        Some(ArraySize::Dynamic) if !trimmed.starts_with('[') => {
+            return Ok(InitialValue::Array(Vec::new()))
+        }
+        Some(_) if !trimmed.starts_with('[') => {
+            return Err(format!("Static arrays must use '[]', found \"{trimmed}\"").into())
+        }
+        Some(array_size) => {
+            let capacity = match array_size {
+                ArraySize::Dynamic => None,
+                ArraySize::Static(Capacity::InPlace(capacity)) => Some(*capacity),
+                ArraySize::Static(Capacity::Shifted(start, end)) => {
+                    Some(usize::try_from(end - start + 1).unwrap_or_default())
+                }
+            };
+            let result =
+                all_consuming(|input| array_literal(Some(base_type), capacity, input))(trimmed)
+                    .finish();
+            if let (Ok((_, InitialValue::Array(values))), Some(capacity)) = (&result, capacity) {
+                if values.len() > capacity {
+                    return Err(format!(
+                        "Initial value \"{trimmed}\" has {} elements, but the array only {capacity}",
+                        values.len()
+                    )
+                    .into());
+                }
+            }
+            result
+        }
+        None => all_consuming(|input| value_literal(Some(base_type), input))(trimmed).finish(),
+    };
+    result
+        .map(|(_, initial_value)| initial_value)
+        .map_err(|error: nom::error::Error<&str>| {
+            format!(
+                "Invalid initial value \"{trimmed}\" for type {base_type:?} at \"{}\"",
+                error.input
+            )
+            .into()
+        })
+}
+
+// Without base type the type is derived from the literal itself,
+// which is the case for the members of a structure initialization
+fn value_literal<'a>(base_type: Option<&BaseType>, input: &'a str) -> IResult<&'a str, InitialValue> {
+    match base_type {
+        Some(BaseType::Custom(_)) | None => untyped_literal(input),
+        Some(base_type) => typed_literal(base_type, input),
+    }
+}
+
+// A type prefix has to name the type of the variable, e.g. "BOOL#1" is no INT
+fn typed_literal<'a>(base_type: &BaseType, input: &'a str) -> IResult<&'a str, InitialValue> {
+    let (input, _) = verify(opt(type_prefix), |type_name: &Option<&str>| {
+        type_name.is_none_or(|type_name| {
+            elementary_base_type(type_name).is_some_and(|prefixed_type| {
+                std::mem::discriminant(&prefixed_type) == std::mem::discriminant(base_type)
+            })
+        })
+    })(input)?;
+    match base_type {
+        BaseType::BOOL => map(bool_literal, InitialValue::BOOL)(input),
+        BaseType::SINT => map(int_literal, InitialValue::SINT)(input),
+        BaseType::INT => map(int_literal, InitialValue::INT)(input),
+        BaseType::DINT => map(int_literal, InitialValue::DINT)(input),
+        BaseType::LINT => map(int_literal, InitialValue::LINT)(input),
+        BaseType::USINT => map(int_literal, InitialValue::USINT)(input),
+        BaseType::UINT => map(int_literal, InitialValue::UINT)(input),
+        BaseType::UDINT => map(int_literal, InitialValue::UDINT)(input),
+        BaseType::ULINT => map(int_literal, InitialValue::ULINT)(input),
+        BaseType::BYTE => map(int_literal, InitialValue::BYTE)(input),
+        BaseType::WORD => map(int_literal, InitialValue::WORD)(input),
+        BaseType::DWORD => map(int_literal, InitialValue::DWORD)(input),
+        BaseType::LWORD => map(int_literal, InitialValue::LWORD)(input),
+        BaseType::REAL => map_res(real_literal, |str| str.parse().map(InitialValue::REAL))(input),
+        BaseType::LREAL => map_res(real_literal, |str| str.parse().map(InitialValue::LREAL))(input),
+        BaseType::CHAR => map(char_literal, InitialValue::CHAR)(input),
+        BaseType::STRING(_) => map(string_literal, InitialValue::STRING)(input),
+        BaseType::WSTRING(_) => map(wstring_literal, InitialValue::WSTRING)(input),
+        BaseType::Custom(_) => untyped_literal(input),
+    }
+}
+
+fn untyped_literal(input: &str) -> IResult<&str, InitialValue> {
+    alt((
+        struct_literal,
+        |input| array_literal(None, None, input),
+        prefixed_literal,
+        map(string_literal, InitialValue::STRING),
+        map(wstring_literal, InitialValue::WSTRING),
+        map(bool_literal_keyword, InitialValue::BOOL),
+        map_res(
+            verify(real_literal, |str: &str| str.contains(['.', 'e', 'E'])),
+            |str| str.parse().map(InitialValue::LREAL),
+        ),
+        map(int_literal, InitialValue::LINT),
+        map(identifier, |name| InitialValue::Enumerated(name.to_string())),
+    ))(input)
+}
+
+// "INT#5" determines the type, "Color#RED" names the value of an enumeration
+fn prefixed_literal(input: &str) -> IResult<&str, InitialValue> {
+    let (_, type_name) = type_prefix(input)?;
+    match elementary_base_type(type_name) {
+        Some(base_type) => typed_literal(&base_type, input),
+        None => map(recognize(pair(type_prefix, identifier)), |name: &str| {
+            InitialValue::Enumerated(name.to_string())
+        })(input),
+    }
+}
+
+fn elementary_base_type(type_name: &str) -> Option<BaseType> {
+    Some(match type_name.to_ascii_uppercase().as_str() {
+        "BOOL" => BaseType::BOOL,
+        "SINT" => BaseType::SINT,
+        "INT" => BaseType::INT,
+        "DINT" => BaseType::DINT,
+        "LINT" => BaseType::LINT,
+        "USINT" => BaseType::USINT,
+        "UINT" => BaseType::UINT,
+        "UDINT" => BaseType::UDINT,
+        "ULINT" => BaseType::ULINT,
+        "REAL" => BaseType::REAL,
+        "LREAL" => BaseType::LREAL,
+        "BYTE" => BaseType::BYTE,
+        "WORD" => BaseType::WORD,
+        "DWORD" => BaseType::DWORD,
+        "LWORD" => BaseType::LWORD,
+        "CHAR" => BaseType::CHAR,
+        "STRING" => BaseType::STRING(None),
+        "WSTRING" => BaseType::WSTRING(None),
+        _ => return None,
+    })
+}
+
+// Type names start with a letter, which distinguishes them from the bases "2#", "8#" and "16#"
+fn type_prefix(input: &str) -> IResult<&str, &str> {
+    terminated(identifier, char('#'))(input)
+}
+
+fn identifier(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        take_while1(|c: char| c.is_ascii_alphabetic() || c == '_'),
+        take_while(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+    ))(input)
+}
+
+// Elements are separated by ",", "n(value)" repeats the value n times,
+// but not more often than the capacity of the array allows
+fn array_literal<'a>(
+    base_type: Option<&BaseType>,
+    capacity: Option<usize>,
+    input: &'a str,
+) -> IResult<&'a str, InitialValue> {
+    map(
+        delimited(
+            pair(char('['), multispace0),
+            separated_list0(delimited(multispace0, char(','), multispace0), |input| {
+                array_element(base_type, capacity, input)
+            }),
+            pair(multispace0, char(']')),
+        ),
+        |elements| InitialValue::Array(elements.into_iter().flatten().collect()),
+    )(input)
+}
+
+fn array_element<'a>(
+    base_type: Option<&BaseType>,
+    capacity: Option<usize>,
+    input: &'a str,
+) -> IResult<&'a str, Vec<InitialValue>> {
+    alt((
+        map_opt(
+            pair(
+                terminated(digit1, multispace0),
+                delimited(
+                    pair(char('('), multispace0),
+                    opt(|input| array_element_value(base_type, capacity, input)),
+                    pair(multispace0, char(')')),
+                ),
+            ),
+            |(count, value): (&str, Option<InitialValue>)| {
+                let count: usize = count.parse().ok()?;
+                // Leere Wiederholungen erhalten den Standardwert des Typs
+                let values =
+                    value.map_or_else(|| vec![default_initial_value(base_type)], flatten_array);
+                let length = count.checked_mul(values.len())?;
+                if capacity.is_some_and(|capacity| length > capacity) {
+                    return None;
+                }
+                Some((0..count).flat_map(|_| values.iter().cloned()).collect())
+            },
+        ),
+        map(
+            |input| array_element_value(base_type, capacity, input),
+            flatten_array,
+        ),
+    ))(input)
+}
+
+// Nested arrays initialize multidimensional arrays, their values are stored row by row
+fn flatten_array(value: InitialValue) -> Vec<InitialValue> {
+    match value {
+        InitialValue::Array(values) => values,
+        value => vec![value],
+    }
+}
+
+fn array_element_value<'a>(
+    base_type: Option<&BaseType>,
+    capacity: Option<usize>,
+    input: &'a str,
+) -> IResult<&'a str, InitialValue> {
+    alt((
+        |input| array_literal(base_type, capacity, input),
+        |input| value_literal(base_type, input),
+    ))(input)
+}
+
+fn default_initial_value(base_type: Option<&BaseType>) -> InitialValue {
+    let zero = IntLiteral::UnsignedDecimalInt(0);
+    match base_type {
+        Some(BaseType::BOOL) => InitialValue::BOOL(BoolLiteral::String(false)),
+        Some(BaseType::SINT) => InitialValue::SINT(zero),
+        Some(BaseType::INT) => InitialValue::INT(zero),
+        Some(BaseType::DINT) => InitialValue::DINT(zero),
+        Some(BaseType::LINT) => InitialValue::LINT(zero),
+        Some(BaseType::USINT) => InitialValue::USINT(zero),
+        Some(BaseType::UINT) => InitialValue::UINT(zero),
+        Some(BaseType::UDINT) => InitialValue::UDINT(zero),
+        Some(BaseType::ULINT) => InitialValue::ULINT(zero),
+        Some(BaseType::BYTE) => InitialValue::BYTE(zero),
+        Some(BaseType::WORD) => InitialValue::WORD(zero),
+        Some(BaseType::DWORD) => InitialValue::DWORD(zero),
+        Some(BaseType::LWORD) => InitialValue::LWORD(zero),
+        Some(BaseType::REAL) => InitialValue::REAL(0f32),
+        Some(BaseType::LREAL) => InitialValue::LREAL(0f64),
+        Some(BaseType::CHAR) => InitialValue::CHAR(CharLiteral::Hex('\0')),
+        Some(BaseType::STRING(_)) => InitialValue::STRING(String::new()),
+        Some(BaseType::WSTRING(_)) => InitialValue::WSTRING(String::new()),
+        Some(BaseType::Custom(_)) | None => InitialValue::Struct(Vec::new()),
+    }
+}
+
+fn struct_literal(input: &str) -> IResult<&str, InitialValue> {
+    map(
+        delimited(
+            pair(char('('), multispace0),
+            separated_list0(
+                delimited(multispace0, char(','), multispace0),
+                map(
+                    separated_pair(
+                        identifier,
+                        delimited(multispace0, tag(":="), multispace0),
+                        cut(|input| array_element_value(None, None, input)),
+                    ),
+                    |(name, value)| StructElement {
+                        name: name.to_string(),
+                        value,
+                    },
+                ),
+            ),
+            pair(multispace0, char(')')),
+        ),
+        InitialValue::Struct,
+    )(input)
+}
+
+fn bool_literal(input: &str) -> IResult<&str, BoolLiteral> {
+    alt((
+        bool_literal_keyword,
+        map(char('1'), |_| BoolLiteral::Int(true)),
+        map(char('0'), |_| BoolLiteral::Int(false)),
+    ))(input)
+}
+
+fn bool_literal_keyword(input: &str) -> IResult<&str, BoolLiteral> {
+    map_opt(identifier, |keyword| match keyword.to_ascii_uppercase().as_str() {
+        "TRUE" => Some(BoolLiteral::String(true)),
+        "FALSE" => Some(BoolLiteral::String(false)),
+        _ => None,
+    })(input)
+}
+
+// Integers are accepted as well, e.g. "0" for a REAL
+fn real_literal(input: &str) -> IResult<&str, String> {
+    map(
+        recognize(tuple((
+            opt(one_of("+-")),
+            digits_with_underscores(digit1),
+            opt(pair(char('.'), digits_with_underscores(digit1))),
+            opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
+        ))),
+        |str: &str| str.replace('_', ""),
+    )(input)
+}
+
+fn int_literal(input: &str) -> IResult<&str, IntLiteral> {
+    alt((
+        based_int_literal("16#", hex_digit1, 16, IntLiteral::HexalInt),
+        based_int_literal("8#", oct_digit1, 8, IntLiteral::OctalInt),
+        based_int_literal("2#", bin_digit1, 2, IntLiteral::BinaryInt),
+        decimal_int_literal,
+    ))(input)
+}
+
+fn decimal_int_literal(input: &str) -> IResult<&str, IntLiteral> {
+    map_res(
+        pair(opt(one_of("+-")), digits_with_underscores(digit1)),
+        |(sign, str): (Option<char>, &str)| {
+            let cleaned_str = str.replace('_', "");
+            Ok::<IntLiteral, ParseIntError>(match sign {
+                Some(sign) => IntLiteral::SignedDecimalInt(format!("{sign}{cleaned_str}").parse()?),
+                None => IntLiteral::UnsignedDecimalInt(cleaned_str.parse()?),
+            })
+        },
+    )(input)
+}
+
+fn based_int_literal<'a>(
+    base_prefix: &'static str,
+    digits: impl FnMut(&'a str) -> IResult<&'a str, &'a str> + Copy,
+    radix: u32,
+    create_literal: fn(u64) -> IntLiteral,
+) -> impl FnMut(&'a str) -> IResult<&'a str, IntLiteral> {
+    preceded(
+        tag(base_prefix),
+        map_res(digits_with_underscores(digits), move |str: &str| {
+            u64::from_str_radix(&str.replace('_', ""), radix).map(create_literal)
+        }),
+    )
+}
+
+fn bin_digit1(input: &str) -> IResult<&str, &str> {
+    is_a("01")(input)
+}
+
+// "1_000" is a valid integer, but "_1", "1_" and "1__0" are not
+fn digits_with_underscores<'a>(
+    digits: impl FnMut(&'a str) -> IResult<&'a str, &'a str> + Copy,
+) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    recognize(pair(digits, many0(preceded(opt(char('_')), digits))))
+}
+
+fn char_literal(input: &str) -> IResult<&str, CharLiteral> {
+    delimited(
+        char('\''),
+        alt((
+            map_opt(preceded(char('$'), hex_byte), |byte| {
+                char::from_u32(byte as u32).map(CharLiteral::Hex)
+            }),
+            map_opt(preceded(char('$'), anychar), |escaped| {
+                escaped_char(escaped).map(CharLiteral::Hex)
+            }),
+            map(none_of("'$"), CharLiteral::Value),
+        )),
+        char('\''),
+    )(input)
+}
+
+fn hex_byte(input: &str) -> IResult<&str, u8> {
+    map_res(
+        recognize(pair(
+            one_of("0123456789abcdefABCDEF"),
+            one_of("0123456789abcdefABCDEF"),
+        )),
+        |str| u8::from_str_radix(str, 16),
+    )(input)
+}
+
+fn escaped_char(escaped: char) -> Option<char> {
+    match escaped {
+        '$' | '\'' | '"' => Some(escaped),
+        'L' | 'l' | 'N' | 'n' => Some('\n'),
+        'R' | 'r' => Some('\r'),
+        'T' | 't' => Some('\t'),
+        'P' | 'p' => Some('\u{c}'),
+        _ => None,
+    }
+}
+
+fn string_literal(input: &str) -> IResult<&str, String> {
+    map_res(quoted_literal('\''), |value| decode_iec_string(&value, false))(input)
+}
+
+fn wstring_literal(input: &str) -> IResult<&str, String> {
+    map_res(quoted_literal('"'), |value| decode_iec_string(&value, true))(input)
+}
+
+fn quoted_literal(quote: char) -> impl FnMut(&str) -> IResult<&str, String> {
+    move |input: &str| {
+        map(
+            delimited(
+                char(quote),
+                recognize(many0(alt((
+                    recognize(pair(char('$'), anychar)),
+                    recognize(verify(none_of("$"), |c: &char| *c != quote)),
+                )))),
+                char(quote),
+            ),
+            str::to_string,
+        )(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(base_type: &BaseType, input: &str) -> InitialValue {
+        parse_initial_value(base_type, &None, input).unwrap()
+    }
+
+    fn parse_array(base_type: &BaseType, capacity: usize, input: &str) -> Result<InitialValue> {
+        parse_initial_value(
+            base_type,
+            &Some(ArraySize::Static(Capacity::InPlace(capacity))),
+            input,
+        )
+    }
+
+    fn ints(values: &[u64]) -> InitialValue {
+        InitialValue::Array(
+            values
+                .iter()
+                .map(|value| InitialValue::INT(IntLiteral::UnsignedDecimalInt(*value)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn typed_literals() {
+        assert_eq!(
+            parse(&BaseType::INT, "INT#5"),
+            InitialValue::INT(IntLiteral::UnsignedDecimalInt(5))
+        );
+        assert_eq!(
+            parse(&BaseType::REAL, "REAL#1.5E3"),
+            InitialValue::REAL(1500.0)
+        );
+        assert_eq!(
+            parse(&BaseType::DINT, " -1_000 "),
+            InitialValue::DINT(IntLiteral::SignedDecimalInt(-1000))
+        );
+    }
+
+    #[test]
+    fn based_literals_with_underscores() {
+        assert_eq!(
+            parse(&BaseType::WORD, "16#FF_FF"),
+            InitialValue::WORD(IntLiteral::HexalInt(0xFFFF))
+        );
+        assert_eq!(
+            parse(&BaseType::BYTE, "BYTE#2#1010_0101"),
+            InitialValue::BYTE(IntLiteral::BinaryInt(0b1010_0101))
+        );
+        assert_eq!(
+            parse(&BaseType::USINT, "8#17"),
+            InitialValue::USINT(IntLiteral::OctalInt(0o17))
+        );
+    }
+
+    #[test]
+    fn repeated_array_elements() {
+        assert_eq!(
+            parse_array(&BaseType::INT, 4, "[3(7),1]").unwrap(),
+            ints(&[7, 7, 7, 1])
+        );
+        assert_eq!(
+            parse_array(&BaseType::INT, 3, "[2(), 1]").unwrap(),
+            ints(&[0, 0, 1])
+        );
+    }
+
+    #[test]
+    fn nested_arrays_are_stored_row_by_row() {
+        assert_eq!(
+            parse_array(&BaseType::INT, 4, "[[1, 2], [3, 4]]").unwrap(),
+            ints(&[1, 2, 3, 4])
+        );
+        assert_eq!(
+            parse_array(&BaseType::INT, 4, "[2([1, 2])]").unwrap(),
+            ints(&[1, 2, 1, 2])
+        );
+    }
+
+    #[test]
+    fn strings_containing_commas() {
+        assert_eq!(
+            parse_array(&BaseType::STRING(None), 2, "['a, b', '$T']").unwrap(),
+            InitialValue::Array(vec![
+                InitialValue::STRING("a, b".to_string()),
+                InitialValue::STRING("\t".to_string()),
+            ])
+        );
+        assert_eq!(
+            parse(
+                &BaseType::Custom("Punkt".to_string()),
+                "(name:='x, y', x:=1.0)"
+            ),
+            InitialValue::Struct(vec![
+                StructElement {
+                    name: "name".to_string(),
+                    value: InitialValue::STRING("x, y".to_string()),
+                },
+                StructElement {
+                    name: "x".to_string(),
+                    value: InitialValue::LREAL(1.0),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn malformed_literals_are_rejected() {
+        for (base_type, input) in [
+            (BaseType::INT, "INT#"),
+            (BaseType::INT, "5 6"),
+            (BaseType::WORD, "16#FF__FF"),
+            (BaseType::WORD, "16#_FF"),
+            (BaseType::REAL, "1.5E"),
+            (BaseType::BOOL, "TRUEX"),
+            (BaseType::STRING(None), "'unterminated"),
+        ] {
+            assert!(
+                parse_initial_value(&base_type, &None, input).is_err(),
+                "{input}"
+            );
+        }
+        assert!(parse_array(&BaseType::INT, 2, "[1, 2").is_err());
+        assert!(parse_array(&BaseType::INT, 2, "[3(7]").is_err());
+        assert!(parse_array(&BaseType::INT, 2, "1, 2").is_err());
+    }
+
+    #[test]
+    fn type_prefixes_have_to_name_the_type() {
+        assert!(parse_initial_value(&BaseType::INT, &None, "BOOL#5").is_err());
+        assert!(parse_initial_value(&BaseType::WORD, &None, "REAL#3").is_err());
+        assert!(parse_initial_value(&BaseType::INT, &None, "INT#INT#5").is_err());
+        assert_eq!(
+            parse(&BaseType::STRING(Some(8)), "STRING#'abc'"),
+            InitialValue::STRING("abc".to_string())
+        );
+    }
+
+    #[test]
+    fn arrays_are_limited_by_their_capacity() {
+        assert!(parse_array(&BaseType::INT, 3, "[1, 2, 3, 4]").is_err());
+        assert!(parse_array(&BaseType::INT, 3, "[4(7)]").is_err());
+        assert!(parse_array(&BaseType::INT, 3, "[99999999999999999999(7)]").is_err());
+        assert!(parse_array(&BaseType::INT, 3, "[2([1, 2])]").is_err());
+        assert_eq!(
+            parse_initial_value(
+                &BaseType::INT,
+                &Some(ArraySize::Static(Capacity::Shifted(1, 3))),
+                "[3(7)]"
+            )
+            .unwrap(),
+            ints(&[7, 7, 7])
+        );
+    }
+}
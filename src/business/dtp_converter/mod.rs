@@ -1,3 +1,7 @@
 pub mod dtp_reader;
+pub mod literal_parser;
 pub mod msg_writer;
+pub mod idl_writer;
+pub mod package_writer;
+#[allow(clippy::module_inception)]
 pub mod dtp_converter;
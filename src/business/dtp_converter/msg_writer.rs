@@ -1,10 +1,17 @@
 use std::fs;
 
 use crate::business::error::Result;
+use crate::business::string_codec::encode_ros_string;
 use crate::core::msg::{
     BaseType, BoolLiteral, Constraint, Field, FieldType, InitialValue, IntLiteral, Reference,
     StructuredType,
 };
+use crate::core::action::Action;
+use crate::core::msg_cst::{LineContent, SyntaxTree};
+use crate::core::srv::Service;
+
+const SECTION_SEPARATOR: &str = "---";
+const DEFAULT_LINE_ENDING: &str = "\r\n";
 
 pub fn write(msg_dto: &StructuredType, to_directory: &str) -> Result<()> {
     let file_name = msg_dto.name();
@@ -14,23 +21,164 @@ pub fn write(msg_dto: &StructuredType, to_directory: &str) -> Result<()> {
     Ok(())
 }
 
+pub fn write_service(srv_dto: &Service, to_directory: &str) -> Result<()> {
+    let file_name = srv_dto.name();
+    let path_to_file = format!("{to_directory}{file_name}.srv");
+    let file_content: String = srv_dto_as_string(srv_dto);
+    fs::write(path_to_file, file_content)?;
+    Ok(())
+}
+
+fn srv_dto_as_string(srv_dto: &Service) -> String {
+    sections_as_string(&[srv_dto.request(), srv_dto.response()])
+}
+
+pub fn write_action(action_dto: &Action, to_directory: &str) -> Result<()> {
+    let file_name = action_dto.name();
+    let path_to_file = format!("{to_directory}{file_name}.action");
+    let file_content: String = action_dto_as_string(action_dto);
+    fs::write(path_to_file, file_content)?;
+    Ok(())
+}
+
+fn action_dto_as_string(action_dto: &Action) -> String {
+    sections_as_string(&[action_dto.goal(), action_dto.result(), action_dto.feedback()])
+}
+
+fn sections_as_string(sections: &[&StructuredType]) -> String {
+    let mut result: String = String::new();
+    for (index, section) in sections.iter().enumerate() {
+        let line_ending = line_ending_of(section);
+        if index > 0 {
+            if !result.is_empty() && !result.ends_with('\n') {
+                result.push_str(line_ending);
+            }
+            result.push_str(SECTION_SEPARATOR);
+            result.push_str(line_ending);
+        }
+        result.push_str(&msg_dto_as_string(section));
+    }
+    result
+}
+
 fn msg_dto_as_string(msg_dto: &StructuredType) -> String {
+    match msg_dto.syntax_tree() {
+        Some(syntax_tree) => msg_dto_as_string_with_layout(msg_dto, syntax_tree),
+        None => {
+            let mut result: String = comment_lines_as_string(msg_dto.comment(), DEFAULT_LINE_ENDING);
+            if msg_dto.comment().is_some() {
+                result.push_str(DEFAULT_LINE_ENDING);
+            }
+            for field in msg_dto.fields().iter() {
+                result.push_str(&field_as_string(field));
+                result.push_str(DEFAULT_LINE_ENDING);
+            }
+            result
+        }
+    }
+}
+
+// Reuses the original lines wherever the model did not change semantically. The lines are matched
+// to the fields by name: changed fields are regenerated in place, deleted ones are dropped and added
+// or moved ones are written in the order of the model before the next original field.
+fn msg_dto_as_string_with_layout(msg_dto: &StructuredType, syntax_tree: &SyntaxTree) -> String {
+    let line_ending = line_ending_of(msg_dto);
+    let file_level_comment_lines = syntax_tree.file_level_comment_lines();
+
     let mut result: String = String::new();
-    for field in msg_dto.fields().iter() {
+    if msg_dto.comment().cloned() == syntax_tree.file_level_comment() {
+        for line in file_level_comment_lines.iter() {
+            result.push_str(&line.text());
+        }
+    } else {
+        result.push_str(&comment_lines_as_string(msg_dto.comment(), line_ending));
+        if file_level_comment_lines.is_empty() && msg_dto.comment().is_some() {
+            result.push_str(line_ending);
+        }
+    }
+
+    let fields = msg_dto.fields();
+    // The index of the next field of the model to be written
+    let mut next = 0;
+    for line in syntax_tree.lines()[file_level_comment_lines.len()..].iter() {
+        let original = match &line.content {
+            LineContent::Empty => {
+                result.push_str(&line.text());
+                continue;
+            }
+            LineContent::Field { field, .. } => field,
+        };
+        let Some(position) = fields[next..]
+            .iter()
+            .position(|field| field.name() == original.name())
+        else {
+            continue;
+        };
+        for field in &fields[next..next + position] {
+            result.push_str(&line.leading_whitespace);
+            result.push_str(&field_as_string(field));
+            result.push_str(line_ending);
+        }
+        let field = &fields[next + position];
+        next += position + 1;
+        if field == original.as_ref() {
+            result.push_str(&line.text());
+            continue;
+        }
+        result.push_str(&line.leading_whitespace);
+        match &line.comment {
+            Some(comment) if field.comment() == original.comment() => {
+                result.push_str(&field_declaration_as_string(field));
+                result.push_str(&line.trailing_whitespace);
+                result.push_str(comment);
+            }
+            _ => result.push_str(&field_as_string(field)),
+        }
+        result.push_str(&line.line_ending);
+    }
+    for field in &fields[next..] {
+        if !result.is_empty() && !result.ends_with('\n') {
+            result.push_str(line_ending);
+        }
         result.push_str(&field_as_string(field));
-        result.push_str("\r\n");
+        result.push_str(line_ending);
+    }
+    result
+}
+
+fn line_ending_of(msg_dto: &StructuredType) -> &str {
+    msg_dto
+        .syntax_tree()
+        .and_then(|syntax_tree| {
+            syntax_tree
+                .lines()
+                .iter()
+                .map(|line| line.line_ending.as_str())
+                .find(|line_ending| !line_ending.is_empty())
+        })
+        .unwrap_or(DEFAULT_LINE_ENDING)
+}
+
+fn comment_lines_as_string(comment: Option<&String>, line_ending: &str) -> String {
+    let mut result: String = String::new();
+    for line in comment.map(|comment| comment.lines()).into_iter().flatten() {
+        result.push_str(format!("# {line}").trim_end());
+        result.push_str(line_ending);
     }
     result
 }
 
 fn field_as_string(field: &Field) -> String {
+    field_declaration_as_string(field) + &comment_as_string(field.comment())
+}
+
+fn field_declaration_as_string(field: &Field) -> String {
     let mut result: String = String::new();
     result.push_str(&base_type_as_string(field.base_type()));
     result.push_str(&constraints_as_string(field.constraint()));
-    result.push_str(" ");
+    result.push(' ');
     result.push_str(field.name());
     result.push_str(&field_type_as_string(field.field_type()));
-    result.push_str(&comment_as_string(field.comment()));
     result
 }
 
@@ -66,10 +214,10 @@ fn base_type_as_string(base_type: &BaseType) -> String {
         BaseType::Uint64 => "uint64".to_string(),
         BaseType::Char => "char".to_string(),
         BaseType::String(constraint) => constraint
-            .map(|c| format!("string<={}", c.to_string()))
+            .map(|c| format!("string<={c}"))
             .unwrap_or_else(|| "string".to_string()),
         BaseType::Wstring(constraint) => constraint
-            .map(|c| format!("wstring<={}", c.to_string()))
+            .map(|c| format!("wstring<={c}"))
             .unwrap_or_else(|| "wstring".to_string()),
         BaseType::Custom(reference) => match reference {
             Reference::Relative { file } => file.clone(),
@@ -107,8 +255,9 @@ fn initial_value_as_string(initial_value: &InitialValue) -> String {
         InitialValue::Float32(value) => value.to_string(),
         InitialValue::Float64(value) => value.to_string(),
         InitialValue::Char(value) => int_literal_as_string(value),
-        InitialValue::String(value) => format!("\"{}\"", value.replace("\"", "\\\"")),
-        InitialValue::Wstring(value) => format!("\"{}\"", value.replace("\"", "\\\"")),
+        InitialValue::String(value) | InitialValue::Wstring(value) => {
+            format!("\"{}\"", encode_ros_string(value, '"'))
+        }
         InitialValue::Array(values) => array_of_initial_values_as_string(values),
     }
 }
@@ -142,3 +291,109 @@ fn int_literal_as_string(int_literal: &IntLiteral) -> String {
         IntLiteral::HexalInt(u64) => format!("0x{u64:X}"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::business::msg_converter::msg_reader::parse_syntax_tree;
+    use crate::core::msg_cst::Line;
+
+    const SOURCE: &str =
+        "# Antrieb\r\n\r\nint32 a 5  #  Fuenf\r\n# Zwischen a und b\r\nint32 b # B\r\nint32 c\r\n";
+
+    fn parse_fields(input: &str) -> Vec<Field> {
+        let syntax_tree = parse_syntax_tree(input).unwrap();
+        syntax_tree
+            .lines()
+            .iter()
+            .filter_map(Line::field)
+            .cloned()
+            .collect()
+    }
+
+    fn write_changed(change: impl FnOnce(&mut Vec<Field>)) -> String {
+        let syntax_tree = parse_syntax_tree(SOURCE).unwrap();
+        let mut fields = parse_fields(SOURCE);
+        change(&mut fields);
+        msg_dto_as_string(&StructuredType::new(
+            "Antrieb",
+            &syntax_tree.file_level_comment(),
+            fields,
+            &Some(syntax_tree),
+        ))
+    }
+
+    #[test]
+    fn unchanged_fields_keep_their_lines() {
+        assert_eq!(write_changed(|_| {}), SOURCE);
+    }
+
+    #[test]
+    fn deleted_field_is_dropped() {
+        assert_eq!(
+            write_changed(|fields| {
+                fields.remove(1);
+            }),
+            "# Antrieb\r\n\r\nint32 a 5  #  Fuenf\r\n# Zwischen a und b\r\nint32 c\r\n"
+        );
+        assert_eq!(
+            write_changed(|fields| {
+                fields.remove(0);
+            }),
+            "# Antrieb\r\n\r\n# Zwischen a und b\r\nint32 b # B\r\nint32 c\r\n"
+        );
+    }
+
+    #[test]
+    fn renamed_field_stays_in_place() {
+        assert_eq!(
+            write_changed(|fields| fields[1] = parse_fields("int32 x # B").remove(0)),
+            "# Antrieb\r\n\r\nint32 a 5  #  Fuenf\r\n# Zwischen a und b\r\nint32 x # B\r\nint32 c\r\n"
+        );
+    }
+
+    #[test]
+    fn changed_default_keeps_comment() {
+        assert_eq!(
+            write_changed(|fields| fields[0] = parse_fields("int32 a 6 # Fuenf").remove(0)),
+            "# Antrieb\r\n\r\nint32 a 6  #  Fuenf\r\n# Zwischen a und b\r\nint32 b # B\r\nint32 c\r\n"
+        );
+        assert_eq!(
+            write_changed(|fields| fields[0] = parse_fields("int32 a 6 # Sechs").remove(0)),
+            "# Antrieb\r\n\r\nint32 a 6 # Sechs\r\n# Zwischen a und b\r\nint32 b # B\r\nint32 c\r\n"
+        );
+    }
+
+    #[test]
+    fn moved_and_added_fields_follow_the_model() {
+        assert_eq!(
+            write_changed(|fields| {
+                fields.swap(1, 2);
+                fields.push(parse_fields("bool d").remove(0));
+            }),
+            "# Antrieb\r\n\r\nint32 a 5  #  Fuenf\r\n# Zwischen a und b\r\nint32 c\r\nint32 b # B\r\nbool d\r\n"
+        );
+    }
+
+    #[test]
+    fn annotations_implied_by_the_strategy_keep_their_lines() {
+        use crate::business::conversion_options::ConversionOptions;
+        use crate::business::dtp_converter::dtp_converter;
+        use crate::business::msg_converter::msg_converter;
+        use std::collections::HashMap;
+
+        let source = "int32[] samples [1,2]\nbool[] flags [true] # @IEC61499_ElementCounter.\n\
+            uint8[<=4] levels # @IEC61499_Capacity(4), @IEC61499_ElementCounter. Levels\n";
+        let options = ConversionOptions {
+            keep_msg_source: true,
+            ..Default::default()
+        };
+        let syntax_tree = parse_syntax_tree(source).unwrap();
+        let msg_dto =
+            StructuredType::new("Strategie", &None, parse_fields(source), &Some(syntax_tree));
+        let data_type = msg_converter::convert("tests", &options, &None, &msg_dto).unwrap();
+        let restored =
+            dtp_converter::convert("tests", &options, &data_type, &[], &HashMap::new()).unwrap();
+        assert_eq!(msg_dto_as_string(&restored), source);
+    }
+}
@@ -0,0 +1,93 @@
+use std::fs;
+
+use crate::business::error::Result;
+use crate::core::package::*;
+
+const CMAKE_FILE_NAME: &str = "CMakeLists.txt";
+
+// Writes the package.xml and the CMakeLists.txt of a package,
+// which generates the interfaces in "paths_to_interface_files" with rosidl
+pub fn write(package: &Package, paths_to_interface_files: &[String], to_directory: &str) -> Result<()> {
+    fs::write(format!("{to_directory}{FILE_NAME}"), package_as_string(package))?;
+    fs::write(
+        format!("{to_directory}{CMAKE_FILE_NAME}"),
+        cmake_lists_as_string(package, paths_to_interface_files),
+    )?;
+    Ok(())
+}
+
+fn package_as_string(package: &Package) -> String {
+    let mut result = String::new();
+    result.push_str("<?xml version=\"1.0\"?>\n");
+    result.push_str("<?xml-model href=\"http://download.ros.org/schema/package_format3.xsd\" schematypens=\"http://www.w3.org/2001/XMLSchema\"?>\n");
+    result.push_str("<package format=\"3\">\n");
+    push_element(&mut result, XML_TAG_NAME, package.name());
+    push_element(&mut result, XML_TAG_VERSION, package.version());
+    push_element(&mut result, XML_TAG_DESCRIPTION, package.description());
+    for maintainer in package.maintainers() {
+        match &maintainer.email {
+            Some(email) => result.push_str(&format!(
+                "  <{XML_TAG_MAINTAINER} {XML_ATTRIBUTE_EMAIL}=\"{}\">{}</{XML_TAG_MAINTAINER}>\n",
+                escape_xml(email),
+                escape_xml(&maintainer.name)
+            )),
+            None => push_element(&mut result, XML_TAG_MAINTAINER, &maintainer.name),
+        }
+    }
+    for license in package.licenses() {
+        push_element(&mut result, XML_TAG_LICENSE, license);
+    }
+    result.push('\n');
+    push_element(&mut result, "buildtool_depend", "ament_cmake");
+    push_element(&mut result, "buildtool_depend", "rosidl_default_generators");
+    for dependency in package.dependencies() {
+        push_element(&mut result, XML_TAG_DEPEND, dependency);
+    }
+    push_element(&mut result, "exec_depend", "rosidl_default_runtime");
+    push_element(&mut result, "member_of_group", "rosidl_interface_packages");
+    result.push('\n');
+    result.push_str("  <export>\n");
+    result.push_str("    <build_type>ament_cmake</build_type>\n");
+    result.push_str("  </export>\n");
+    result.push_str("</package>\n");
+    result
+}
+
+fn push_element(result: &mut String, name: &str, text: &str) {
+    result.push_str(&format!("  <{name}>{}</{name}>\n", escape_xml(text)));
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn cmake_lists_as_string(package: &Package, paths_to_interface_files: &[String]) -> String {
+    let mut result = String::new();
+    result.push_str("cmake_minimum_required(VERSION 3.8)\n");
+    result.push_str(&format!("project({})\n", package.name()));
+    result.push('\n');
+    result.push_str("find_package(ament_cmake REQUIRED)\n");
+    result.push_str("find_package(rosidl_default_generators REQUIRED)\n");
+    for dependency in package.dependencies() {
+        result.push_str(&format!("find_package({dependency} REQUIRED)\n"));
+    }
+    result.push('\n');
+    result.push_str("rosidl_generate_interfaces(${PROJECT_NAME}\n");
+    for path_to_interface_file in paths_to_interface_files {
+        result.push_str(&format!("  \"{path_to_interface_file}\"\n"));
+    }
+    if !package.dependencies().is_empty() {
+        result.push_str(&format!(
+            "  DEPENDENCIES {}\n",
+            package.dependencies().join(" ")
+        ));
+    }
+    result.push_str(")\n");
+    result.push('\n');
+    result.push_str("ament_export_dependencies(rosidl_default_runtime)\n");
+    result.push_str("ament_package()\n");
+    result
+}
@@ -0,0 +1,35 @@
+// Sequences of ROS 2 become fixed arrays together with a variable counting their elements or
+// arrays of variable size (ArraySize="*"). The strategy applies to every sequence, annotations
+// override it per field: "@IEC61499_Capacity(10)" or "@IEC61499_Capacity(*)", "@IEC61499_ElementCounter",
+// "@IEC61499_ElementCounter(<name>)" and "@IEC61499_NoElementCounter".
+
+pub const DEFAULT_CAPACITY: usize = 3;
+pub const DEFAULT_ELEMENT_COUNTER_SUFFIX: &str = "_element_counter";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynamicArrayStrategy {
+    // The capacity of unbounded sequences, bounded ones get their bound
+    pub capacity: usize,
+    // Sequences become arrays of variable size instead of fixed arrays
+    pub variable_size: bool,
+    // A fixed array is followed by a ULINT variable "<name><suffix>" holding the number of elements
+    pub element_counter: bool,
+    pub element_counter_suffix: String,
+}
+
+impl DynamicArrayStrategy {
+    pub fn element_counter_name(&self, var_name: &str) -> String {
+        format!("{var_name}{}", self.element_counter_suffix)
+    }
+}
+
+impl Default for DynamicArrayStrategy {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            variable_size: false,
+            element_counter: true,
+            element_counter_suffix: DEFAULT_ELEMENT_COUNTER_SUFFIX.to_string(),
+        }
+    }
+}
@@ -16,6 +16,10 @@ pub enum Error {
     DtpWriter(xmltree::Error),
     #[from]
     MsgReader(nom::error::Error<String>),
+    #[from]
+    TomlReader(toml::de::Error),
+    #[from]
+    YamlReader(serde_yaml::Error),
 }
 
 // -- Start: Convenience
@@ -35,7 +39,15 @@ impl From<&str> for Error {
 // -- Start: Boilerplate
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            Error::Custom(message) => write!(f, "{message}"),
+            Error::Io(error) => write!(f, "{error}"),
+            Error::DtpReader(error) => write!(f, "{error}"),
+            Error::DtpWriter(error) => write!(f, "{error}"),
+            Error::MsgReader(error) => write!(f, "{error}"),
+            Error::TomlReader(error) => write!(f, "{error}"),
+            Error::YamlReader(error) => write!(f, "{error}"),
+        }
     }
 }
 
@@ -1,31 +1,1066 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
 use crate::business::dtp_converter::*;
+pub use crate::business::conversion_options::{ConversionOptions, DerivedTypeStrategy};
+pub use crate::business::dynamic_array::{
+    DynamicArrayStrategy, DEFAULT_CAPACITY, DEFAULT_ELEMENT_COUNTER_SUFFIX,
+};
 use crate::business::error::Result;
+pub use crate::business::mapping_profile::MappingProfile;
+use crate::business::msg_converter::bundled_interfaces::BundledInterfaces;
+pub use crate::business::msg_converter::reference_resolver::SearchPaths;
+use crate::business::msg_converter::reference_resolver::{Dependency, ReferenceResolver};
 use crate::business::msg_converter::*;
-use log::{debug, info};
+use crate::business::type_name;
+pub use crate::business::type_name::{TypeNameTemplate, DEFAULT_TEMPLATE};
+use crate::core::idl::Interface;
+use crate::core::{action, dtp, idl, msg, package, srv};
+use log::{debug, info, warn};
+
+const SRV_FILE_EXTENSION: &str = "srv";
+const ACTION_FILE_EXTENSION: &str = "action";
+const SERVICE_SUFFIXES: [&str; 2] = [srv::REQUEST_SUFFIX, srv::RESPONSE_SUFFIX];
+const ACTION_SUFFIXES: [&str; 3] = [
+    action::GOAL_SUFFIX,
+    action::RESULT_SUFFIX,
+    action::FEEDBACK_SUFFIX,
+];
+// Interface folders of a package, named like the extension of their files
+const INTERFACE_DIRECTORIES: [&str; 3] = [
+    msg::INTERFACE_KIND,
+    srv::INTERFACE_KIND,
+    action::INTERFACE_KIND,
+];
+
+// Source files, which were converted, and the ones which failed together with their error
+#[derive(Debug, Default)]
+pub struct ConversionSummary {
+    pub converted: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+impl ConversionSummary {
+    fn append(&mut self, other: &mut ConversionSummary) {
+        self.converted.append(&mut other.converted);
+        self.failed.append(&mut other.failed);
+    }
+}
 
 pub fn convert_to_dtp(
     path_to_source_file: &str,
     path_to_destination_directory: &str,
-    package_name: &str,
+    package_name: Option<&str>,
+    with_implicit_action_types: bool,
+    options: &ConversionOptions,
 ) -> Result<()> {
+    let package = read_package(path_to_source_file)?;
+    let package_name = resolve_package_name(package_name, &package, path_to_source_file)?;
+    let options = &resolve_mapping_profile(options, path_to_source_file)?;
+    if has_extension(path_to_source_file, SRV_FILE_EXTENSION) {
+        return convert_srv_to_dtp(
+            path_to_source_file,
+            path_to_destination_directory,
+            &package_name,
+            &package,
+            options,
+        );
+    }
+    if has_extension(path_to_source_file, ACTION_FILE_EXTENSION) {
+        return convert_action_to_dtp(
+            path_to_source_file,
+            path_to_destination_directory,
+            &package_name,
+            &package,
+            with_implicit_action_types,
+            options,
+        );
+    }
+    if has_extension(path_to_source_file, idl::FILE_EXTENSION) {
+        return convert_idl_to_dtp(
+            path_to_source_file,
+            path_to_destination_directory,
+            &package_name,
+            &package,
+            with_implicit_action_types,
+            options,
+        );
+    }
     let msg_dto = msg_reader::read(path_to_source_file)?;
     debug!("msg_dto: {:?}", msg_dto);
-    let dtp_dto = msg_converter::convert(package_name, &msg_dto)?;
+    let dtp_dto = msg_converter::convert(&package_name, options, &package, &msg_dto)?;
     debug!("dtp_dto: {:?}", dtp_dto);
     dtp_writer::write(dtp_dto, path_to_destination_directory)?;
     Ok(())
 }
 
-pub fn convert_to_msg(
+fn convert_srv_to_dtp(
+    path_to_source_file: &str,
+    path_to_destination_directory: &str,
+    package_name: &str,
+    package: &Option<package::Package>,
+    options: &ConversionOptions,
+) -> Result<()> {
+    let srv_dto = srv_reader::read(path_to_source_file)?;
+    debug!("srv_dto: {:?}", srv_dto);
+    let dtp_dtos = msg_converter::convert_service(package_name, options, package, &srv_dto)?;
+    debug!("dtp_dtos: {:?}", dtp_dtos);
+    for dtp_dto in dtp_dtos {
+        dtp_writer::write(dtp_dto, path_to_destination_directory)?;
+    }
+    Ok(())
+}
+
+fn convert_action_to_dtp(
+    path_to_source_file: &str,
+    path_to_destination_directory: &str,
+    package_name: &str,
+    package: &Option<package::Package>,
+    with_implicit_types: bool,
+    options: &ConversionOptions,
+) -> Result<()> {
+    let action_dto = action_reader::read(path_to_source_file)?;
+    debug!("action_dto: {:?}", action_dto);
+    let dtp_dtos = msg_converter::convert_action(
+        package_name,
+        options,
+        package,
+        &action_dto,
+        with_implicit_types,
+    )?;
+    debug!("dtp_dtos: {:?}", dtp_dtos);
+    for dtp_dto in dtp_dtos {
+        dtp_writer::write(dtp_dto, path_to_destination_directory)?;
+    }
+    Ok(())
+}
+
+fn convert_idl_to_dtp(
+    path_to_source_file: &str,
+    path_to_destination_directory: &str,
+    package_name: &str,
+    package: &Option<package::Package>,
+    with_implicit_action_types: bool,
+    options: &ConversionOptions,
+) -> Result<()> {
+    let idl_dto = idl_reader::read(path_to_source_file)?;
+    debug!("idl_dto: {:?}", idl_dto);
+    let dtp_dtos = match &idl_dto {
+        Interface::Message(msg_dto) => {
+            vec![msg_converter::convert(
+                package_name,
+                options,
+                package,
+                msg_dto,
+            )?]
+        }
+        Interface::Service(srv_dto) => msg_converter::convert_service(
+            package_name,
+            options,
+            package,
+            srv_dto,
+        )?,
+        Interface::Action(action_dto) => msg_converter::convert_action(
+            package_name,
+            options,
+            package,
+            action_dto,
+            with_implicit_action_types,
+        )?,
+    };
+    debug!("dtp_dtos: {:?}", dtp_dtos);
+    for dtp_dto in dtp_dtos {
+        dtp_writer::write(dtp_dto, path_to_destination_directory)?;
+    }
+    Ok(())
+}
+
+// Converts the messages the source file depends on directly or indirectly before it,
+// each one with the name of its own package
+pub fn convert_to_dtp_with_dependencies(
+    path_to_source_file: &str,
+    path_to_destination_directory: &str,
+    package_name: Option<&str>,
+    with_implicit_action_types: bool,
+    search_paths: &SearchPaths,
+    options: &ConversionOptions,
+) -> Result<()> {
+    let package = read_package(path_to_source_file)?;
+    let package_name = resolve_package_name(package_name, &package, path_to_source_file)?;
+    let options = &resolve_mapping_profile(options, path_to_source_file)?;
+    let resolver = ReferenceResolver::new(search_paths)?;
+    let source = Dependency {
+        package_name: package_name.clone(),
+        path_to_file: PathBuf::from(path_to_source_file),
+    };
+    for dependency in resolver.resolve_closure(&[source])? {
+        info!("Converting dependency {:?}", dependency.path_to_file);
+        convert_to_dtp(
+            &dependency.path_to_file.to_string_lossy(),
+            path_to_destination_directory,
+            Some(&dependency.package_name),
+            false,
+            options,
+        )?;
+    }
+    convert_to_dtp(
+        path_to_source_file,
+        path_to_destination_directory,
+        Some(&package_name),
+        with_implicit_action_types,
+        options,
+    )
+}
+
+// Every package below the workspace is converted into "<destination>/<package name>/".
+// With search paths, the messages of other packages the workspace depends on are converted too.
+pub fn convert_workspace_to_dtp(
+    path_to_workspace_directory: &str,
+    path_to_destination_directory: &str,
+    with_implicit_action_types: bool,
+    dependency_search_paths: Option<&SearchPaths>,
+    options: &ConversionOptions,
+) -> Result<ConversionSummary> {
+    let options = &resolve_mapping_profile(options, path_to_workspace_directory)?;
+    let paths_to_packages = package_reader::find_packages(Path::new(path_to_workspace_directory))?;
+    if paths_to_packages.is_empty() {
+        return Err(format!(
+            "No {} found in workspace {:?}",
+            package::FILE_NAME,
+            path_to_workspace_directory
+        )
+        .into());
+    }
+    let mut summary = ConversionSummary::default();
+    let mut sources: Vec<Dependency> = Vec::new();
+    for path_to_package in paths_to_packages {
+        let (package, mut package_summary) = convert_package_files_to_dtp(
+            &path_to_package.to_string_lossy(),
+            path_to_destination_directory,
+            with_implicit_action_types,
+            options,
+        )?;
+        sources.extend(package_summary.converted.iter().map(|path_to_file| Dependency {
+            package_name: package.name().to_string(),
+            path_to_file: PathBuf::from(path_to_file),
+        }));
+        summary.append(&mut package_summary);
+    }
+    if let Some(dependency_search_paths) = dependency_search_paths {
+        let mut search_paths = dependency_search_paths.clone();
+        search_paths
+            .source_directories
+            .insert(0, path_to_workspace_directory.to_string());
+        summary.append(&mut convert_dependencies_to_dtp(
+            path_to_workspace_directory,
+            &sources,
+            path_to_destination_directory,
+            &search_paths,
+            options,
+        ));
+    }
+    Ok(summary)
+}
+
+// The folders "msg", "srv" and "action" of the package are mirrored into
+// "<destination>/<package name>/", a failing file does not stop the conversion
+pub fn convert_package_to_dtp(
+    path_to_package_directory: &str,
+    path_to_destination_directory: &str,
+    with_implicit_action_types: bool,
+    dependency_search_paths: Option<&SearchPaths>,
+    options: &ConversionOptions,
+) -> Result<ConversionSummary> {
+    let options = &resolve_mapping_profile(options, path_to_package_directory)?;
+    let (package, mut summary) = convert_package_files_to_dtp(
+        path_to_package_directory,
+        path_to_destination_directory,
+        with_implicit_action_types,
+        options,
+    )?;
+    if let Some(dependency_search_paths) = dependency_search_paths {
+        let mut search_paths = dependency_search_paths.clone();
+        search_paths
+            .source_directories
+            .insert(0, path_to_package_directory.to_string());
+        let sources: Vec<Dependency> = summary
+            .converted
+            .iter()
+            .map(|path_to_file| Dependency {
+                package_name: package.name().to_string(),
+                path_to_file: PathBuf::from(path_to_file),
+            })
+            .collect();
+        summary.append(&mut convert_dependencies_to_dtp(
+            path_to_package_directory,
+            &sources,
+            path_to_destination_directory,
+            &search_paths,
+            options,
+        ));
+    }
+    Ok(summary)
+}
+
+// Every package of the bundled interfaces is converted like "convert_package_to_dtp" does
+pub fn export_bundled_interfaces(
+    path_to_destination_directory: &str,
+    options: &ConversionOptions,
+) -> Result<ConversionSummary> {
+    let options = &resolve_mapping_profile(options, path_to_destination_directory)?;
+    let bundled_interfaces = BundledInterfaces::extract()?;
+    let mut summary = ConversionSummary::default();
+    for path_to_package in bundled_interfaces.package_directories() {
+        let (_, mut package_summary) = convert_package_files_to_dtp(
+            &path_to_package.to_string_lossy(),
+            path_to_destination_directory,
+            false,
+            options,
+        )?;
+        summary.append(&mut package_summary);
+    }
+    Ok(summary)
+}
+
+fn convert_package_files_to_dtp(
+    path_to_package_directory: &str,
+    path_to_destination_directory: &str,
+    with_implicit_action_types: bool,
+    options: &ConversionOptions,
+) -> Result<(package::Package, ConversionSummary)> {
+    let path_to_package_file = Path::new(path_to_package_directory).join(package::FILE_NAME);
+    let package = package_reader::read(&path_to_package_file.to_string_lossy())?;
+    info!("Start converting package {:?}", package.name());
+    let mut summary = ConversionSummary::default();
+    for interface_directory in INTERFACE_DIRECTORIES {
+        let paths_to_source_files = find_files(
+            &Path::new(path_to_package_directory).join(interface_directory),
+            interface_directory,
+        )?;
+        for path_to_source_file in paths_to_source_files {
+            convert_to_package_directory(
+                &path_to_source_file.to_string_lossy(),
+                path_to_destination_directory,
+                package.name(),
+                interface_directory,
+                with_implicit_action_types,
+                options,
+                &mut summary,
+            );
+        }
+    }
+    info!("Finished converting package {:?}", package.name());
+    Ok((package, summary))
+}
+
+// Messages of the packages, which the sources belong to, are already converted
+fn convert_dependencies_to_dtp(
+    path: &str,
+    sources: &[Dependency],
+    path_to_destination_directory: &str,
+    search_paths: &SearchPaths,
+    options: &ConversionOptions,
+) -> ConversionSummary {
+    let mut summary = ConversionSummary::default();
+    // The resolver keeps the bundled interfaces until their messages are converted
+    let resolved = ReferenceResolver::new(search_paths).and_then(|resolver| {
+        let dependencies = resolver.resolve_closure(sources)?;
+        Ok((resolver, dependencies))
+    });
+    let (_resolver, dependencies) = match resolved {
+        Ok(resolved) => resolved,
+        Err(error) => {
+            warn!("Failed to resolve the dependencies of {:?}: {}", path, error);
+            summary.failed.push((path.to_string(), error.to_string()));
+            return summary;
+        }
+    };
+    for dependency in dependencies {
+        if sources
+            .iter()
+            .any(|source| source.package_name == dependency.package_name)
+        {
+            continue;
+        }
+        convert_to_package_directory(
+            &dependency.path_to_file.to_string_lossy(),
+            path_to_destination_directory,
+            &dependency.package_name,
+            msg::INTERFACE_KIND,
+            false,
+            options,
+            &mut summary,
+        );
+    }
+    summary
+}
+
+// Converts into "<destination>/<package name>/<interface directory>/"
+fn convert_to_package_directory(
     path_to_source_file: &str,
     path_to_destination_directory: &str,
     package_name: &str,
+    interface_directory: &str,
+    with_implicit_action_types: bool,
+    options: &ConversionOptions,
+    summary: &mut ConversionSummary,
+) {
+    let path_to_target_directory = Path::new(path_to_destination_directory)
+        .join(package_name)
+        .join(interface_directory);
+    // The writers expect the directory to end with a separator
+    let result = std::fs::create_dir_all(&path_to_target_directory)
+        .map_err(Into::into)
+        .and_then(|_| {
+            convert_to_dtp(
+                path_to_source_file,
+                &format!("{}/", path_to_target_directory.to_string_lossy()),
+                Some(package_name),
+                with_implicit_action_types,
+                options,
+            )
+        });
+    match result {
+        Ok(()) => summary.converted.push(path_to_source_file.to_string()),
+        Err(error) => {
+            warn!("Failed to convert {:?}: {}", path_to_source_file, error);
+            summary
+                .failed
+                .push((path_to_source_file.to_string(), error.to_string()));
+        }
+    }
+}
+
+fn find_files(directory: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    if !directory.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut paths_to_files = std::fs::read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths_to_files.retain(|path| path.is_file() && has_extension(&path.to_string_lossy(), extension));
+    paths_to_files.sort();
+    Ok(paths_to_files)
+}
+
+// Every DTP file below the type library becomes an interface of the package "<destination>/<package name>/".
+// The sections of services and actions are found by the suffixes of their type names.
+pub fn convert_typelib_to_package(
+    path_to_typelib_directory: &str,
+    path_to_destination_directory: &str,
+    package_name: &str,
+    options: &ConversionOptions,
+) -> Result<ConversionSummary> {
+    info!("Start converting type library {:?}", path_to_typelib_directory);
+    let options = &resolve_mapping_profile(options, path_to_typelib_directory)?;
+    let mut summary = ConversionSummary::default();
+    let mut data_types: Vec<(String, dtp::DataType)> = Vec::new();
+    for path_to_source_file in find_files_recursively(
+        Path::new(path_to_typelib_directory),
+        dtp::FILE_EXTENSION,
+    )? {
+        let path_to_source_file = path_to_source_file.to_string_lossy().to_string();
+        match dtp_reader::read(&path_to_source_file) {
+            Ok(data_type) => data_types.push((path_to_source_file, data_type)),
+            Err(error) => {
+                warn!("Failed to read {:?}: {}", path_to_source_file, error);
+                summary.failed.push((path_to_source_file, error.to_string()));
+            }
+        }
+    }
+    let inlined_types: Vec<dtp::DataType> = data_types
+        .iter()
+        .map(|(_, data_type)| data_type)
+        .filter(|data_type| {
+            options.derived_type_strategy == DerivedTypeStrategy::Inline
+                && is_derived_type(data_type)
+        })
+        .cloned()
+        .collect();
+    let interfaces = group_sections(&data_types, package_name, options);
+    let mut message_names: HashMap<String, String> = HashMap::new();
+    for (_, data_type) in interfaces.iter().filter_map(|sections| match sections.as_slice() {
+        [message] => Some(*message),
+        _ => None,
+    }) {
+        message_names.insert(
+            type_name::qualified_name(data_type),
+            dtp_converter::convert_message_name(package_name, options, data_type)?,
+        );
+    }
+
+    let path_to_package_directory = Path::new(path_to_destination_directory).join(package_name);
+    let mut paths_to_interface_files: Vec<String> = Vec::new();
+    let mut dependencies: Vec<String> = Vec::new();
+    for sections in interfaces {
+        let paths_to_source_files: Vec<String> =
+            sections.iter().map(|(path, _)| path.to_string()).collect();
+        if let [(path_to_source_file, data_type)] = sections.as_slice() {
+            if inlined_types.iter().any(|inlined_type| inlined_type.name() == data_type.name()) {
+                info!("Skipping {:?}, it is inlined where it is used", path_to_source_file);
+                continue;
+            }
+        }
+        let interface = match sections.as_slice() {
+            [(_, data_type)] => dtp_converter::convert(
+                package_name,
+                options,
+                data_type,
+                &inlined_types,
+                &message_names,
+            )
+            .map(Interface::Message),
+            [(_, request), (_, response)] => dtp_converter::convert_service(
+                package_name,
+                options,
+                request,
+                response,
+                &inlined_types,
+                &message_names,
+            )
+            .map(Interface::Service),
+            [(_, goal), (_, result), (_, feedback)] => dtp_converter::convert_action(
+                package_name,
+                options,
+                goal,
+                result,
+                feedback,
+                &inlined_types,
+                &message_names,
+            )
+            .map(Interface::Action),
+            _ => Err("Unexpected number of sections".into()),
+        };
+        let written = interface.and_then(|interface| {
+            if !type_name::is_valid_ros_type_name(interface.name()) {
+                return Err(format!(
+                    "\"{}\" is no valid ROS 2 type name, it has to match \"^[A-Z][A-Za-z0-9]*$\"",
+                    interface.name()
+                )
+                .into());
+            }
+            let path_to_interface_file = interface_file_name(&interface);
+            if paths_to_interface_files.contains(&path_to_interface_file) {
+                return Err(format!(
+                    "\"{path_to_interface_file}\" was already converted from another data type"
+                )
+                .into());
+            }
+            write_interface(&interface, &path_to_package_directory)?;
+            for package in referenced_packages(&interface) {
+                if package != package_name && !dependencies.contains(&package) {
+                    dependencies.push(package);
+                }
+            }
+            Ok(path_to_interface_file)
+        });
+        match written {
+            Ok(path_to_interface_file) => {
+                paths_to_interface_files.push(path_to_interface_file);
+                summary.converted.extend(paths_to_source_files);
+            }
+            Err(error) => {
+                warn!("Failed to convert {:?}: {}", paths_to_source_files, error);
+                for path_to_source_file in paths_to_source_files {
+                    summary.failed.push((path_to_source_file, error.to_string()));
+                }
+            }
+        }
+    }
+    if paths_to_interface_files.is_empty() {
+        return Err(format!(
+            "No interface could be converted from type library {:?}",
+            path_to_typelib_directory
+        )
+        .into());
+    }
+    let package = create_package(package_name, &path_to_package_directory, &dependencies)?;
+    package_writer::write(
+        &package,
+        &paths_to_interface_files,
+        &format!("{}/", path_to_package_directory.to_string_lossy()),
+    )?;
+    info!("Finished converting type library {:?}", path_to_typelib_directory);
+    Ok(summary)
+}
+
+// Each entry contains either a message, the request and the response of a service
+// or the goal, the result and the feedback of an action. Only data types marked as
+// sections of a service or an action are grouped, if all of their sections exist.
+fn group_sections<'a>(
+    data_types: &'a [(String, dtp::DataType)],
+    package_name: &str,
+    options: &ConversionOptions,
+) -> Vec<Vec<&'a (String, dtp::DataType)>> {
+    let interface_kind = |data_type: &dtp::DataType| {
+        dtp_converter::find_interface_kind(package_name, options, data_type)
+            .ok()
+            .flatten()
+    };
+    let find = |name: &str, kind: &str| {
+        data_types.iter().find(|(_, data_type)| {
+            data_type.name() == name && interface_kind(data_type).as_deref() == Some(kind)
+        })
+    };
+    let find_sections = |name: &str, kind: &str, suffixes: &[&str]| {
+        suffixes
+            .iter()
+            .find_map(|suffix| section_names(name, suffix, suffixes))?
+            .iter()
+            .map(|section_name| find(section_name, kind))
+            .collect::<Option<Vec<_>>>()
+    };
+    let mut grouped_names: Vec<&str> = Vec::new();
+    let mut result = Vec::new();
+    for entry in data_types.iter() {
+        let name = entry.1.name();
+        if grouped_names.contains(&name) {
+            continue;
+        }
+        let sections = match interface_kind(&entry.1).as_deref() {
+            Some(srv::INTERFACE_KIND) => {
+                find_sections(name, srv::INTERFACE_KIND, &SERVICE_SUFFIXES)
+            }
+            Some(action::INTERFACE_KIND) => {
+                find_sections(name, action::INTERFACE_KIND, &ACTION_SUFFIXES)
+            }
+            _ => None,
+        };
+        match sections {
+            Some(sections) => {
+                grouped_names.extend(sections.iter().map(|(_, data_type)| data_type.name()));
+                result.push(sections);
+            }
+            None => result.push(vec![entry]),
+        }
+    }
+    // The remaining sections of services and actions were already found as a part of them
+    result.retain(|sections| {
+        sections.len() > 1 || !grouped_names.contains(&sections[0].1.name())
+    });
+    result
+}
+
+fn interface_directory(interface: &Interface) -> &str {
+    match interface {
+        Interface::Message(_) => msg::INTERFACE_KIND,
+        Interface::Service(_) => srv::INTERFACE_KIND,
+        Interface::Action(_) => action::INTERFACE_KIND,
+    }
+}
+
+// The path of the interface file relative to the package directory, e.g. "msg/<Name>.msg"
+fn interface_file_name(interface: &Interface) -> String {
+    let interface_directory = interface_directory(interface);
+    format!("{interface_directory}/{}.{interface_directory}", interface.name())
+}
+
+fn write_interface(interface: &Interface, path_to_package_directory: &Path) -> Result<()> {
+    let path_to_interface_directory = path_to_package_directory.join(interface_directory(interface));
+    std::fs::create_dir_all(&path_to_interface_directory)?;
+    let to_directory = format!("{}/", path_to_interface_directory.to_string_lossy());
+    match interface {
+        Interface::Message(msg_dto) => msg_writer::write(msg_dto, &to_directory)?,
+        Interface::Service(srv_dto) => msg_writer::write_service(srv_dto, &to_directory)?,
+        Interface::Action(action_dto) => msg_writer::write_action(action_dto, &to_directory)?,
+    }
+    Ok(())
+}
+
+fn referenced_packages(interface: &Interface) -> Vec<String> {
+    interface
+        .structured_types()
+        .into_iter()
+        .flat_map(|structured_type| structured_type.fields().iter())
+        .filter_map(|field| match field.base_type() {
+            msg::BaseType::Custom(msg::Reference::Absolute { package, .. }) => {
+                Some(package.clone())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// The metadata of an existing package.xml is kept, a new one gets placeholders like "ros2 pkg create"
+fn create_package(
+    package_name: &str,
+    path_to_package_directory: &Path,
+    dependencies: &[String],
+) -> Result<package::Package> {
+    let path_to_package_file = path_to_package_directory.join(package::FILE_NAME);
+    if !path_to_package_file.is_file() {
+        return Ok(package::Package::new(
+            package_name,
+            "0.0.0",
+            "Interfaces converted from a 4diac type library",
+            &[package::Person {
+                name: "TODO".to_string(),
+                email: Some("todo@todo.todo".to_string()),
+            }],
+            &["TODO: License declaration".to_string()],
+            dependencies,
+            "",
+        ));
+    }
+    let package = package_reader::read(&path_to_package_file.to_string_lossy())?;
+    let mut merged_dependencies = package.dependencies().clone();
+    for dependency in dependencies {
+        if !merged_dependencies.contains(dependency) {
+            merged_dependencies.push(dependency.clone());
+        }
+    }
+    Ok(package::Package::new(
+        package.name(),
+        package.version(),
+        package.description(),
+        package.maintainers(),
+        package.licenses(),
+        &merged_dependencies,
+        package.date(),
+    ))
+}
+
+fn find_files_recursively(directory: &Path, extension: &str) -> Result<Vec<PathBuf>> {
+    let mut paths = std::fs::read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+    let mut paths_to_files = Vec::new();
+    for path in paths {
+        if path
+            .file_name()
+            .is_some_and(|file_name| file_name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            paths_to_files.append(&mut find_files_recursively(&path, extension)?);
+        } else if has_extension(&path.to_string_lossy(), extension) {
+            paths_to_files.push(path);
+        }
+    }
+    Ok(paths_to_files)
+}
+
+// The package.xml is searched from the given path upwards,
+// without one generated types simply carry no provenance
+fn read_package(path: &str) -> Result<Option<package::Package>> {
+    let Some(path_to_package_file) = package_reader::find(path) else {
+        debug!("No {} found for {:?}", package::FILE_NAME, path);
+        return Ok(None);
+    };
+    let package = package_reader::read(&path_to_package_file.to_string_lossy())?;
+    debug!("package: {:?}", package);
+    Ok(Some(package))
+}
+
+// A given profile takes precedence over the one found from the given path upwards,
+// without either the built-in mapping applies
+fn resolve_mapping_profile(options: &ConversionOptions, path: &str) -> Result<ConversionOptions> {
+    if options.mapping_profile.is_some() {
+        return Ok(options.clone());
+    }
+    let mapping_profile = match MappingProfile::find(path) {
+        Some(path_to_profile) => {
+            info!("Using mapping profile {:?}", path_to_profile);
+            MappingProfile::load(&path_to_profile.to_string_lossy()).map_err(|error| {
+                format!("Invalid mapping profile {:?}: {error}", path_to_profile)
+            })?
+        }
+        None => MappingProfile::default(),
+    };
+    Ok(ConversionOptions {
+        mapping_profile: Some(mapping_profile),
+        ..options.clone()
+    })
+}
+
+// A given package name takes precedence over the one of the package.xml
+fn resolve_package_name(
+    package_name: Option<&str>,
+    package: &Option<package::Package>,
+    path: &str,
+) -> Result<String> {
+    match (package_name, package) {
+        (Some(package_name), Some(package)) => {
+            if package.name() != package_name {
+                warn!(
+                    "The package name {:?} differs from {:?} in the {} of {:?}",
+                    package_name,
+                    package.name(),
+                    package::FILE_NAME,
+                    path
+                );
+            }
+            Ok(package_name.to_string())
+        }
+        (Some(package_name), None) => Ok(package_name.to_string()),
+        (None, Some(package)) => Ok(package.name().to_string()),
+        (None, None) => Err(format!(
+            "No package name given and no {} found for {:?}",
+            package::FILE_NAME,
+            path
+        )
+        .into()),
+    }
+}
+
+// Without a package name, the one of the package the destination directory belongs to is used
+pub fn convert_to_msg(
+    path_to_source_file: &str,
+    path_to_destination_directory: &str,
+    package_name: Option<&str>,
+    options: &ConversionOptions,
 ) -> Result<()> {
+    let package = read_package(path_to_destination_directory)?;
+    let package_name = resolve_package_name(package_name, &package, path_to_destination_directory)?;
+    let options = &resolve_mapping_profile(options, path_to_source_file)?;
+    let Some(interface) = convert_dtp_to_interface(path_to_source_file, &package_name, options)?
+    else {
+        return Ok(());
+    };
+    match interface {
+        Interface::Message(msg_dto) => msg_writer::write(&msg_dto, path_to_destination_directory),
+        Interface::Service(srv_dto) => {
+            msg_writer::write_service(&srv_dto, path_to_destination_directory)
+        }
+        Interface::Action(action_dto) => {
+            msg_writer::write_action(&action_dto, path_to_destination_directory)
+        }
+    }
+}
+
+pub fn convert_to_idl(
+    path_to_source_file: &str,
+    path_to_destination_directory: &str,
+    package_name: Option<&str>,
+    options: &ConversionOptions,
+) -> Result<()> {
+    let package = read_package(path_to_destination_directory)?;
+    let package_name = resolve_package_name(package_name, &package, path_to_destination_directory)?;
+    let options = &resolve_mapping_profile(options, path_to_source_file)?;
+    let Some(idl_dto) = convert_dtp_to_interface(path_to_source_file, &package_name, options)?
+    else {
+        return Ok(());
+    };
+    idl_writer::write(&package_name, &idl_dto, path_to_destination_directory)
+}
+
+// Returns None for derived types, which are inlined instead of being converted
+fn convert_dtp_to_interface(
+    path_to_source_file: &str,
+    package_name: &str,
+    options: &ConversionOptions,
+) -> Result<Option<Interface>> {
     let dtp_dto = dtp_reader::read(path_to_source_file)?;
     debug!("dtp_dto: {:?}", dtp_dto);
-    let msg_dto = dtp_converter::convert(package_name, &dtp_dto)?;
+    match dtp_converter::find_interface_kind(package_name, options, &dtp_dto)?.as_deref() {
+        Some(srv::INTERFACE_KIND) => {
+            if let Some(paths) = find_siblings(path_to_source_file, &SERVICE_SUFFIXES) {
+                return convert_dtp_to_srv(&paths, package_name, options)
+                    .map(|srv_dto| Some(Interface::Service(srv_dto)));
+            }
+        }
+        Some(action::INTERFACE_KIND) => {
+            if let Some(paths) = find_siblings(path_to_source_file, &ACTION_SUFFIXES) {
+                return convert_dtp_to_action(&paths, package_name, options)
+                    .map(|action_dto| Some(Interface::Action(action_dto)));
+            }
+        }
+        _ => {}
+    }
+    if options.derived_type_strategy == DerivedTypeStrategy::Inline && is_derived_type(&dtp_dto) {
+        info!("Skipping {:?}, it is inlined where it is used", path_to_source_file);
+        return Ok(None);
+    }
+    let inlined_types = read_inlined_types(path_to_source_file, &[&dtp_dto], options)?;
+    let message_names =
+        read_message_names(path_to_source_file, &[&dtp_dto], package_name, options)?;
+    let msg_dto = dtp_converter::convert(
+        package_name,
+        options,
+        &dtp_dto,
+        &inlined_types,
+        &message_names,
+    )?;
     debug!("msg_dto: {:?}", msg_dto);
-    msg_writer::write(&msg_dto, path_to_destination_directory)?;
+    Ok(Some(Interface::Message(msg_dto)))
+}
+
+fn convert_dtp_to_srv(
+    paths_to_source_files: &[String],
+    package_name: &str,
+    options: &ConversionOptions,
+) -> Result<srv::Service> {
+    let request_dtp_dto = dtp_reader::read(&paths_to_source_files[0])?;
+    debug!("request_dtp_dto: {:?}", request_dtp_dto);
+    let response_dtp_dto = dtp_reader::read(&paths_to_source_files[1])?;
+    debug!("response_dtp_dto: {:?}", response_dtp_dto);
+    let inlined_types = read_inlined_types(
+        &paths_to_source_files[0],
+        &[&request_dtp_dto, &response_dtp_dto],
+        options,
+    )?;
+    let message_names = read_message_names(
+        &paths_to_source_files[0],
+        &[&request_dtp_dto, &response_dtp_dto],
+        package_name,
+        options,
+    )?;
+    let srv_dto = dtp_converter::convert_service(
+        package_name,
+        options,
+        &request_dtp_dto,
+        &response_dtp_dto,
+        &inlined_types,
+        &message_names,
+    )?;
+    debug!("srv_dto: {:?}", srv_dto);
+    Ok(srv_dto)
+}
+
+fn convert_dtp_to_action(
+    paths_to_source_files: &[String],
+    package_name: &str,
+    options: &ConversionOptions,
+) -> Result<action::Action> {
+    let goal_dtp_dto = dtp_reader::read(&paths_to_source_files[0])?;
+    debug!("goal_dtp_dto: {:?}", goal_dtp_dto);
+    let result_dtp_dto = dtp_reader::read(&paths_to_source_files[1])?;
+    debug!("result_dtp_dto: {:?}", result_dtp_dto);
+    let feedback_dtp_dto = dtp_reader::read(&paths_to_source_files[2])?;
+    debug!("feedback_dtp_dto: {:?}", feedback_dtp_dto);
+    let inlined_types = read_inlined_types(
+        &paths_to_source_files[0],
+        &[&goal_dtp_dto, &result_dtp_dto, &feedback_dtp_dto],
+        options,
+    )?;
+    let message_names = read_message_names(
+        &paths_to_source_files[0],
+        &[&goal_dtp_dto, &result_dtp_dto, &feedback_dtp_dto],
+        package_name,
+        options,
+    )?;
+    let action_dto = dtp_converter::convert_action(
+        package_name,
+        options,
+        &goal_dtp_dto,
+        &result_dtp_dto,
+        &feedback_dtp_dto,
+        &inlined_types,
+        &message_names,
+    )?;
+    debug!("action_dto: {:?}", action_dto);
+    Ok(action_dto)
+}
+
+// Derived types are looked up as "<Type>.dtp" next to the source file, "<Type>" being the
+// last segment of a package-qualified name
+fn read_inlined_types(
+    path_to_source_file: &str,
+    data_types: &[&dtp::DataType],
+    options: &ConversionOptions,
+) -> Result<Vec<dtp::DataType>> {
+    let mut inlined_types: Vec<dtp::DataType> = Vec::new();
+    if options.derived_type_strategy == DerivedTypeStrategy::Wrap {
+        return Ok(inlined_types);
+    }
+    let mut type_names: Vec<String> = data_types
+        .iter()
+        .flat_map(|data_type| referenced_type_names(data_type))
+        .collect();
+    let mut visited_type_names: Vec<String> = Vec::new();
+    while let Some(type_name) = type_names.pop() {
+        if visited_type_names.contains(&type_name) {
+            continue;
+        }
+        visited_type_names.push(type_name.clone());
+        let path_to_file = path_to_referenced_file(path_to_source_file, &type_name);
+        if !path_to_file.is_file() {
+            continue;
+        }
+        let data_type = dtp_reader::read(&path_to_file.to_string_lossy())?;
+        if is_derived_type(&data_type) {
+            type_names.append(&mut referenced_type_names(&data_type));
+            inlined_types.push(data_type);
+        }
+    }
+    Ok(inlined_types)
+}
+
+// Referenced types are looked up as "<Type>.dtp" next to the source file like derived types
+fn read_message_names(
+    path_to_source_file: &str,
+    data_types: &[&dtp::DataType],
+    package_name: &str,
+    options: &ConversionOptions,
+) -> Result<HashMap<String, String>> {
+    let mut message_names = HashMap::new();
+    for type_name in data_types
+        .iter()
+        .flat_map(|data_type| referenced_type_names(data_type))
+    {
+        if message_names.contains_key(&type_name) {
+            continue;
+        }
+        let path_to_file = path_to_referenced_file(path_to_source_file, &type_name);
+        if !path_to_file.is_file() {
+            continue;
+        }
+        let data_type = dtp_reader::read(&path_to_file.to_string_lossy())?;
+        if type_name::qualified_name(&data_type) == type_name {
+            let message_name =
+                dtp_converter::convert_message_name(package_name, options, &data_type)?;
+            message_names.insert(type_name, message_name);
+        }
+    }
+    Ok(message_names)
+}
+
+fn path_to_referenced_file(path_to_source_file: &str, type_name: &str) -> PathBuf {
+    let (_, name) = type_name::split_qualified_name(type_name);
+    Path::new(path_to_source_file).with_file_name(format!("{name}.{}", dtp::FILE_EXTENSION))
+}
+
+fn referenced_type_names(data_type: &dtp::DataType) -> Vec<String> {
+    let base_types: Vec<&dtp::BaseType> = match data_type.data_type_kind() {
+        dtp::DataTypeKind::DirectlyDerivedType(directly_derived_type) => {
+            vec![directly_derived_type.base_type()]
+        }
+        dtp::DataTypeKind::ArrayType(array_type) => vec![array_type.base_type()],
+        dtp::DataTypeKind::StructuredType(structured_type) => structured_type
+            .children()
+            .iter()
+            .filter_map(|child| match child {
+                dtp::StructuredTypeChild::VarDeclaration(var_declaration) => {
+                    Some(var_declaration.base_type())
+                }
+                dtp::StructuredTypeChild::SubrangeVarDeclaration(_) => None,
+            })
+            .collect(),
+        dtp::DataTypeKind::EnumeratedType(_) | dtp::DataTypeKind::SubrangeType(_) => Vec::new(),
+    };
+    base_types
+        .into_iter()
+        .filter_map(|base_type| match base_type {
+            dtp::BaseType::Custom(type_name) => Some(type_name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn is_derived_type(data_type: &dtp::DataType) -> bool {
+    matches!(
+        data_type.data_type_kind(),
+        dtp::DataTypeKind::DirectlyDerivedType(_) | dtp::DataTypeKind::ArrayType(_)
+    )
+}
+
+pub fn format_dtp(path_to_source_file: &str, path_to_destination_directory: &str) -> Result<()> {
+    info!("Start formatting file {:?}", path_to_source_file);
+    let data_type = dtp_reader::read(path_to_source_file)?;
+    dtp_writer::write(data_type, path_to_destination_directory)?;
+    info!("Finished formatting file {:?}", path_to_source_file);
     Ok(())
 }
 
@@ -35,6 +1070,66 @@ pub fn print_dtp(path_to_source_file: &str) -> Result<()> {
 }
 
 pub fn print_msg(path_to_source_file: &str) -> Result<()> {
-    info!("{:#?}", msg_reader::read(path_to_source_file)?);
+    if has_extension(path_to_source_file, SRV_FILE_EXTENSION) {
+        info!("{:#?}", srv_reader::read(path_to_source_file)?);
+    } else if has_extension(path_to_source_file, ACTION_FILE_EXTENSION) {
+        info!("{:#?}", action_reader::read(path_to_source_file)?);
+    } else if has_extension(path_to_source_file, idl::FILE_EXTENSION) {
+        info!("{:#?}", idl_reader::read(path_to_source_file)?);
+    } else {
+        info!("{:#?}", msg_reader::read(path_to_source_file)?);
+    }
     Ok(())
 }
+
+fn has_extension(path_to_file: &str, extension: &str) -> bool {
+    Path::new(path_to_file)
+        .extension()
+        .is_some_and(|file_extension| file_extension == extension)
+}
+
+// Services and actions are stored as one DTP file per section, e.g.
+// "<Name>_Request.dtp" and "<Name>_Response.dtp", which have to be
+// converted together. Returns the sibling paths in the order of the suffixes,
+// if the name has one of them and every sibling exists.
+fn find_siblings(path_to_file: &str, suffixes: &[&str]) -> Option<Vec<String>> {
+    let path = Path::new(path_to_file);
+    let file_stem = path.file_stem()?.to_str()?;
+    let section_names = suffixes
+        .iter()
+        .find_map(|suffix| section_names(file_stem, suffix, suffixes))?;
+    let paths: Vec<PathBuf> = section_names
+        .iter()
+        .map(|section_name| path.with_file_name(format!("{section_name}.{}", dtp::FILE_EXTENSION)))
+        .collect();
+    if let Some(missing_path) = paths.iter().find(|path| !path.is_file()) {
+        warn!(
+            "{:?} is missing, converting {:?} as a message",
+            missing_path, path_to_file
+        );
+        return None;
+    }
+    Some(
+        paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect(),
+    )
+}
+
+// A type name template may put text behind the name of the section like "{Type}_T", so the
+// suffix is searched inside the name. Returns the names with the suffix replaced by the others.
+fn section_names(name: &str, suffix: &str, suffixes: &[&str]) -> Option<Vec<String>> {
+    let position = name.rfind(suffix)?;
+    let (interface_name, rest) = (&name[..position], &name[position + suffix.len()..]);
+    // "<Name>_FeedbackMessage" is no feedback section
+    if rest.starts_with(|c: char| c.is_alphanumeric()) {
+        return None;
+    }
+    Some(
+        suffixes
+            .iter()
+            .map(|suffix| format!("{interface_name}{suffix}{rest}"))
+            .collect(),
+    )
+}
@@ -0,0 +1,349 @@
+use std::collections::HashSet;
+
+// IEC 61499 identifiers are case-insensitive and must not be keywords of IEC 61131-3 or IEC 61499,
+// ROS 2 names are snake_case for fields, UPPER_CASE for constants and must not be keywords of the
+// generated languages. Names, which are invalid on the other side, are renamed deterministically.
+// The caller keeps the original name, so the reverse conversion can restore it.
+
+// Longer names are shortened, as IEC 61131-3 runtimes commonly limit the length of identifiers
+const MAX_NAME_LENGTH: usize = 64;
+// Prepended to names, which would start with a digit
+const DIGIT_PREFIX: &str = "n_";
+const EMPTY_NAME: &str = "unnamed";
+
+// Keywords and elementary data types of IEC 61131-3 and IEC 61499
+const IEC_KEYWORDS: &str = "
+    ABSTRACT ACTION ADAPTER ALGORITHM AND ANY ANY_BIT ANY_CHAR ANY_CHARS ANY_DATE ANY_DERIVED
+    ANY_DURATION ANY_ELEMENTARY ANY_INT ANY_MAGNITUDE ANY_NUM ANY_REAL ANY_SIGNED ANY_STRING
+    ANY_UNSIGNED APPLICATION ARRAY AT BOOL BY BYTE CASE CHAR CLASS CONFIGURATION CONSTANT
+    CONTINUE DATA_CONNECTIONS DATE DATE_AND_TIME DEVICE DINT DO DT DWORD EC_STATES
+    EC_TRANSITIONS ELSE ELSIF EN END_ACTION END_ADAPTER END_ALGORITHM END_APPLICATION END_CASE
+    END_CLASS END_CONFIGURATION END_CONNECTIONS END_DEVICE END_EVENT END_FBS END_FOR
+    END_FUNCTION END_FUNCTION_BLOCK END_IF END_INTERFACE END_METHOD END_NAMESPACE END_PROGRAM
+    END_REPEAT END_RESOURCE END_SEGMENT END_SERVICE END_SERVICE_SEQUENCE END_STATES END_STEP
+    END_STRUCT END_SUBAPPLICATION END_SYSTEM END_TRANSITION END_TRANSITIONS END_TYPE END_VAR
+    END_WHILE ENO EVENT EVENT_CONNECTIONS EVENT_INPUT EVENT_OUTPUT EXIT EXTENDS FALSE FBS FINAL
+    FOR FROM FUNCTION FUNCTION_BLOCK F_EDGE IF IMPLEMENTS INITIAL_STEP INT INTERFACE INTERNAL
+    INTERVAL LDATE LDATE_AND_TIME LDT LINK LINT LREAL LTIME LTIME_OF_DAY LTOD LWORD MAPPING
+    METHOD MOD NAMESPACE NON_RETAIN NOT NULL OF ON OR OVERLAP OVERRIDE PLUGS PRIORITY PRIVATE
+    PROGRAM PROTECTED PUBLIC READ_ONLY READ_WRITE REAL REF REF_TO REPEAT RESOURCE RETAIN RETURN
+    R_EDGE SEGMENT SERVICE SERVICE_SEQUENCE SINGLE SINT SOCKETS STEP STRING STRUCT
+    SUBAPPLICATION SUPER SYSTEM TASK THEN THIS TIME TIME_OF_DAY TO TOD TRANSITION TRUE TYPE
+    UDINT UINT ULINT UNTIL USING USINT VAR VAR_ACCESS VAR_CONFIG VAR_EXTERNAL VAR_GLOBAL
+    VAR_INPUT VAR_IN_OUT VAR_OUTPUT VAR_TEMP WCHAR WHILE WITH WORD WSTRING XOR
+";
+
+// Keywords of C++ and Python, which would break the generated code
+const ROS_KEYWORDS: &str = "
+    alignas alignof and and_eq as asm assert async auto await bitand bitor bool break case catch
+    char char16_t char32_t char8_t class co_await co_return co_yield compl concept const
+    const_cast consteval constexpr constinit continue decltype def default del delete do double
+    dynamic_cast elif else enum except explicit export extern false finally float for friend
+    from global goto if import in inline int is lambda long mutable namespace new noexcept
+    nonlocal not not_eq nullptr operator or or_eq pass private protected public raise register
+    reinterpret_cast requires return short signed sizeof static static_assert static_cast struct
+    switch template this thread_local throw true try typedef typeid typename union unsigned
+    using virtual void volatile wchar_t while with xor xor_eq yield
+";
+
+// A field or constant of a message or structured type
+#[derive(Clone, Copy, Debug)]
+pub struct Identifier<'a> {
+    pub name: &'a str,
+    pub is_constant: bool,
+    // Restored from an attribute or annotation, it is kept as it is
+    pub is_original: bool,
+}
+
+pub fn to_iec_names(identifiers: &[Identifier]) -> Vec<String> {
+    rename(
+        identifiers,
+        is_valid_iec_name,
+        |identifier| clean(identifier.name),
+        |name| name.to_uppercase(),
+    )
+}
+
+pub fn to_ros_names(identifiers: &[Identifier]) -> Vec<String> {
+    rename(
+        identifiers,
+        is_valid_ros_name,
+        |identifier| {
+            let name = clean(&to_snake_case(identifier.name));
+            match identifier.is_constant {
+                true => name.to_uppercase(),
+                false => name.to_lowercase(),
+            }
+        },
+        |name| name.to_string(),
+    )
+}
+
+// Original and valid names are kept in this order, the first one wins if they collide.
+// The others get the first free name of "<name>", "<name>_1", "<name>_2", ...
+fn rename(
+    identifiers: &[Identifier],
+    is_valid: fn(&Identifier, &str) -> bool,
+    to_candidate: impl Fn(&Identifier) -> String,
+    to_key: fn(&str) -> String,
+) -> Vec<String> {
+    let mut names: Vec<Option<String>> = vec![None; identifiers.len()];
+    let mut taken: HashSet<String> = HashSet::new();
+    let keepers = identifiers
+        .iter()
+        .enumerate()
+        .filter(|(_, identifier)| identifier.is_original)
+        .chain(
+            identifiers
+                .iter()
+                .enumerate()
+                .filter(|(_, identifier)| !identifier.is_original)
+                .filter(|(_, identifier)| is_valid(identifier, identifier.name)),
+        );
+    for (index, identifier) in keepers {
+        if taken.insert(to_key(identifier.name)) {
+            names[index] = Some(identifier.name.to_string());
+        }
+    }
+    for (index, identifier) in identifiers.iter().enumerate() {
+        if names[index].is_some() {
+            continue;
+        }
+        let candidate = to_candidate(identifier);
+        let name = (0..)
+            .map(|counter| with_counter(&candidate, counter))
+            .find(|name| is_valid(identifier, name) && !taken.contains(&to_key(name)))
+            .expect("a free name to exist");
+        taken.insert(to_key(&name));
+        names[index] = Some(name);
+    }
+    names.into_iter().flatten().collect()
+}
+
+fn with_counter(candidate: &str, counter: usize) -> String {
+    let suffix = match counter {
+        0 => String::new(),
+        _ => format!("_{counter}"),
+    };
+    let length = candidate.len().min(MAX_NAME_LENGTH - suffix.len());
+    format!("{}{suffix}", candidate[..length].trim_end_matches('_'))
+}
+
+// Only ASCII letters, digits and single underscores in between
+fn clean(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|char| match char.is_ascii_alphanumeric() {
+            true => char,
+            false => '_',
+        })
+        .collect::<String>();
+    let name = name
+        .split('_')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    match name.chars().next() {
+        None => EMPTY_NAME.to_string(),
+        Some(char) if char.is_ascii_digit() => format!("{DIGIT_PREFIX}{name}"),
+        Some(_) => name,
+    }
+}
+
+// "MaxSpeed" becomes "Max_Speed", "HTTPServer" becomes "HTTP_Server"
+fn to_snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut snake_case = String::new();
+    for (index, char) in chars.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|previous| chars[previous]);
+        let next = chars.get(index + 1);
+        let starts_word = char.is_uppercase()
+            && previous.is_some_and(|previous| {
+                previous.is_lowercase()
+                    || previous.is_ascii_digit()
+                    || previous.is_uppercase() && next.is_some_and(|next| next.is_lowercase())
+            });
+        if starts_word {
+            snake_case.push('_');
+        }
+        snake_case.push(*char);
+    }
+    snake_case
+}
+
+fn has_valid_underscores(name: &str) -> bool {
+    !name.contains("__") && !name.ends_with('_')
+}
+
+fn is_valid_iec_name(_: &Identifier, name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|char| char.is_ascii_alphabetic() || char == '_')
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+        && has_valid_underscores(name)
+        && name.len() <= MAX_NAME_LENGTH
+        && !IEC_KEYWORDS
+            .split_whitespace()
+            .any(|keyword| keyword.eq_ignore_ascii_case(name))
+}
+
+fn is_valid_ros_name(identifier: &Identifier, name: &str) -> bool {
+    let is_cased = |char: char| match identifier.is_constant {
+        true => char.is_ascii_uppercase(),
+        false => char.is_ascii_lowercase(),
+    };
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_cased)
+        && chars.all(|char| is_cased(char) || char.is_ascii_digit() || char == '_')
+        && has_valid_underscores(name)
+        && name.len() <= MAX_NAME_LENGTH
+        && !ROS_KEYWORDS
+            .split_whitespace()
+            .any(|keyword| keyword == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    use crate::business::conversion_options::ConversionOptions;
+    use crate::business::dtp_converter::dtp_converter;
+    use crate::business::msg_converter::msg_converter;
+    use crate::business::msg_converter::msg_reader::parse_syntax_tree;
+    use crate::core::dtp;
+    use crate::core::msg;
+    use crate::core::msg_cst::Line;
+
+    fn identifier(name: &str, is_constant: bool) -> Identifier<'_> {
+        Identifier {
+            name,
+            is_constant,
+            is_original: false,
+        }
+    }
+
+    fn var_names(data_type: &dtp::DataType) -> Vec<&str> {
+        match data_type.data_type_kind() {
+            dtp::DataTypeKind::StructuredType(structured_type) => structured_type
+                .children()
+                .iter()
+                .map(|child| match child {
+                    dtp::StructuredTypeChild::VarDeclaration(var_declaration) => {
+                        var_declaration.name()
+                    }
+                    dtp::StructuredTypeChild::SubrangeVarDeclaration(subrange_var_declaration) => {
+                        subrange_var_declaration.name()
+                    }
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn field_names(structured_type: &msg::StructuredType) -> Vec<&str> {
+        structured_type
+            .fields()
+            .iter()
+            .map(msg::Field::name)
+            .collect()
+    }
+
+    #[test]
+    fn keywords_get_the_first_free_counter() {
+        assert_eq!(
+            to_iec_names(&[identifier("TYPE", true), identifier("type", false)]),
+            ["TYPE_1", "type_2"]
+        );
+        assert_eq!(
+            to_ros_names(&[identifier("class", false), identifier("Class", false)]),
+            ["class_1", "class_2"]
+        );
+    }
+
+    #[test]
+    fn long_names_are_truncated() {
+        let name = "a".repeat(70);
+        let names = to_iec_names(&[identifier(&name, false), identifier(&name, false)]);
+        assert_eq!(names[0], "a".repeat(MAX_NAME_LENGTH));
+        assert_eq!(names[1], format!("{}_1", "a".repeat(MAX_NAME_LENGTH - 2)));
+    }
+
+    #[test]
+    fn original_names_are_kept() {
+        let original = Identifier {
+            name: "TYPE",
+            is_constant: true,
+            is_original: true,
+        };
+        assert_eq!(
+            to_iec_names(&[identifier("type", false), original]),
+            ["type_1", "TYPE"]
+        );
+        assert_eq!(
+            to_ros_names(&[
+                identifier("MaxSpeed", false),
+                identifier("max_speed", false)
+            ]),
+            ["max_speed_1", "max_speed"]
+        );
+    }
+
+    #[test]
+    fn ros_names_are_restored_from_ros2_name() {
+        let options = ConversionOptions::default();
+        let syntax_tree = parse_syntax_tree("int32 TYPE=1\nint32 type\nfloat64 speed\n").unwrap();
+        let fields = syntax_tree
+            .lines()
+            .iter()
+            .filter_map(Line::field)
+            .cloned()
+            .collect();
+        let structured_type = msg::StructuredType::new("Namen", &None, fields, &None);
+        let data_type = msg_converter::convert("tests", &options, &None, &structured_type).unwrap();
+        assert_eq!(var_names(&data_type), ["TYPE_1", "type_2", "speed"]);
+        let restored =
+            dtp_converter::convert("tests", &options, &data_type, &[], &HashMap::new()).unwrap();
+        assert_eq!(field_names(&restored), ["TYPE", "type", "speed"]);
+    }
+
+    #[test]
+    fn iec_names_are_restored_from_the_annotation() {
+        let options = ConversionOptions::default();
+        let var_declaration = |name: &str| {
+            dtp::StructuredTypeChild::VarDeclaration(dtp::VarDeclaration::new(
+                name,
+                &dtp::BaseType::LREAL,
+                &None,
+                &None,
+                &None,
+                &[],
+                &dtp::ForeignXml::default(),
+            ))
+        };
+        let data_type = dtp::DataType::new(
+            "Namen",
+            &None,
+            &None,
+            &[],
+            &None,
+            &dtp::DataTypeKind::StructuredType(dtp::StructuredType::new(
+                &None,
+                &[var_declaration("MaxSpeed"), var_declaration("max_speed")],
+                &dtp::ForeignXml::default(),
+            )),
+            &[],
+            &dtp::ForeignXml::default(),
+        );
+        let structured_type =
+            dtp_converter::convert("tests", &options, &data_type, &[], &HashMap::new()).unwrap();
+        assert_eq!(field_names(&structured_type), ["max_speed_1", "max_speed"]);
+        assert_eq!(
+            structured_type.fields()[0].comment().map(String::as_str),
+            Some("@IEC61499_Name(MaxSpeed).")
+        );
+        let restored = msg_converter::convert("tests", &options, &None, &structured_type).unwrap();
+        assert_eq!(var_names(&restored), ["MaxSpeed", "max_speed"]);
+    }
+}
@@ -0,0 +1,671 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use log::info;
+use serde::Deserialize;
+
+use crate::business::error::Result;
+use crate::core::{dtp, msg};
+
+// A mapping profile overrides which IEC 61131-3 type a primitive type of ROS 2 becomes and the
+// other way round, for all packages, single packages or single fields. It also renames the
+// annotations written into MSG files. What the profile leaves out keeps the built-in mapping.
+//
+//   [to_iec]
+//   uint16 = "WORD"
+//   string = "STRING[80]"
+//   [to_ros]
+//   CHAR = "uint8"
+//   [packages.anlage_msgs.to_iec]
+//   float64 = "REAL"
+//   [fields."anlage_msgs/Antrieb.status"]
+//   to_iec = "BYTE"
+//   [annotations]
+//   prefix = "SPS_"
+//   names = { StartIndex = "Offset" }
+
+// Looked up in the directory of the source and above, if no profile is given
+pub const FILE_NAMES: [&str; 3] = [
+    "ros2-4diac-converter.toml",
+    "ros2-4diac-converter.yaml",
+    "ros2-4diac-converter.yml",
+];
+const YAML_FILE_EXTENSIONS: [&str; 2] = ["yaml", "yml"];
+pub const ANNOTATION_PREFIX: &str = "IEC61499_";
+// The annotations besides the ones naming an IEC 61131-3 type, e.g. "@IEC61499_WORD"
+const ANNOTATION_NAMES: [&str; 11] = [
+    "ArrayType",
+    "Capacity",
+    "DirectlyDerivedType",
+    "ElementCounter",
+    "EnumeratedType",
+    "Name",
+    "NoElementCounter",
+    "StartIndex",
+    "Subrange",
+    "SubrangeType",
+    "TypeName",
+];
+
+// Strings without their bound
+const ROS_TYPES: [(&str, msg::BaseType); 15] = [
+    ("bool", msg::BaseType::Bool),
+    ("byte", msg::BaseType::Byte),
+    ("char", msg::BaseType::Char),
+    ("int8", msg::BaseType::Int8),
+    ("uint8", msg::BaseType::Uint8),
+    ("int16", msg::BaseType::Int16),
+    ("uint16", msg::BaseType::Uint16),
+    ("int32", msg::BaseType::Int32),
+    ("uint32", msg::BaseType::Uint32),
+    ("int64", msg::BaseType::Int64),
+    ("uint64", msg::BaseType::Uint64),
+    ("float32", msg::BaseType::Float32),
+    ("float64", msg::BaseType::Float64),
+    ("string", msg::BaseType::String(None)),
+    ("wstring", msg::BaseType::Wstring(None)),
+];
+
+const IEC_TYPES: [(&str, dtp::BaseType); 18] = [
+    ("BOOL", dtp::BaseType::BOOL),
+    ("BYTE", dtp::BaseType::BYTE),
+    ("WORD", dtp::BaseType::WORD),
+    ("DWORD", dtp::BaseType::DWORD),
+    ("LWORD", dtp::BaseType::LWORD),
+    ("CHAR", dtp::BaseType::CHAR),
+    ("SINT", dtp::BaseType::SINT),
+    ("USINT", dtp::BaseType::USINT),
+    ("INT", dtp::BaseType::INT),
+    ("UINT", dtp::BaseType::UINT),
+    ("DINT", dtp::BaseType::DINT),
+    ("UDINT", dtp::BaseType::UDINT),
+    ("LINT", dtp::BaseType::LINT),
+    ("ULINT", dtp::BaseType::ULINT),
+    ("REAL", dtp::BaseType::REAL),
+    ("LREAL", dtp::BaseType::LREAL),
+    ("STRING", dtp::BaseType::STRING(None)),
+    ("WSTRING", dtp::BaseType::WSTRING(None)),
+];
+
+const DEFAULT_TO_IEC: [(&str, &str); 15] = [
+    ("bool", "BOOL"),
+    ("byte", "BYTE"),
+    ("char", "CHAR"),
+    ("int8", "SINT"),
+    ("uint8", "USINT"),
+    ("int16", "INT"),
+    ("uint16", "UINT"),
+    ("int32", "DINT"),
+    ("uint32", "UDINT"),
+    ("int64", "LINT"),
+    ("uint64", "ULINT"),
+    ("float32", "REAL"),
+    ("float64", "LREAL"),
+    ("string", "STRING"),
+    ("wstring", "WSTRING"),
+];
+
+const DEFAULT_TO_ROS: [(&str, &str); 18] = [
+    ("BOOL", "bool"),
+    ("BYTE", "byte"),
+    ("WORD", "uint16"),
+    ("DWORD", "uint32"),
+    ("LWORD", "uint64"),
+    ("CHAR", "char"),
+    ("SINT", "int8"),
+    ("USINT", "uint8"),
+    ("INT", "int16"),
+    ("UINT", "uint16"),
+    ("DINT", "int32"),
+    ("UDINT", "uint32"),
+    ("LINT", "int64"),
+    ("ULINT", "uint64"),
+    ("REAL", "float32"),
+    ("LREAL", "float64"),
+    ("STRING", "string"),
+    ("WSTRING", "wstring"),
+];
+
+// Types may only be mapped onto types of the same kind, so that their values can be converted
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Bool,
+    Integer,
+    Real,
+    String,
+}
+
+#[derive(Clone, Debug, Default)]
+struct TypeMapping {
+    // Keyed by the name of the ROS 2 type without bound, e.g. "uint16" or "string"
+    to_iec: HashMap<String, dtp::BaseType>,
+    // Keyed by the name of the IEC 61131-3 type without bound, e.g. "WORD" or "STRING"
+    to_ros: HashMap<String, msg::BaseType>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct FieldOverride {
+    to_iec: Option<dtp::BaseType>,
+    to_ros: Option<msg::BaseType>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotationVocabulary {
+    prefix: String,
+    // The own names by the names of the converter
+    names: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MappingProfile {
+    types: TypeMapping,
+    packages: HashMap<String, TypeMapping>,
+    // Keyed by "<package>/<type>.<field>"
+    fields: HashMap<String, FieldOverride>,
+    annotations: AnnotationVocabulary,
+}
+
+// The mappings applying to a single field, the most specific one first
+pub struct FieldMapping<'a> {
+    name: String,
+    field: Option<&'a FieldOverride>,
+    package: Option<&'a TypeMapping>,
+    types: &'a TypeMapping,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ProfileFile {
+    to_iec: BTreeMap<String, String>,
+    to_ros: BTreeMap<String, String>,
+    packages: BTreeMap<String, PackageFile>,
+    fields: BTreeMap<String, FieldFile>,
+    annotations: AnnotationsFile,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PackageFile {
+    to_iec: BTreeMap<String, String>,
+    to_ros: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FieldFile {
+    to_iec: Option<String>,
+    to_ros: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AnnotationsFile {
+    prefix: String,
+    names: BTreeMap<String, String>,
+}
+
+impl Default for AnnotationsFile {
+    fn default() -> Self {
+        Self {
+            prefix: ANNOTATION_PREFIX.to_string(),
+            names: BTreeMap::new(),
+        }
+    }
+}
+
+impl MappingProfile {
+    // YAML files are recognized by their extension, all others are read as TOML
+    pub fn load(path_to_file: &str) -> Result<Self> {
+        info!("Start reading mapping profile {:?}", path_to_file);
+        let text = std::fs::read_to_string(path_to_file)?;
+        let is_yaml = std::path::Path::new(path_to_file)
+            .extension()
+            .is_some_and(|extension| YAML_FILE_EXTENSIONS.iter().any(|yaml| extension == *yaml));
+        let profile_file: ProfileFile = match is_yaml {
+            true => serde_yaml::from_str(&text)?,
+            false => toml::from_str(&text)?,
+        };
+        Self::from_file(profile_file)
+    }
+
+    // The nearest profile in the directory of the path or above
+    pub fn find(path: &str) -> Option<PathBuf> {
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+        path.ancestors()
+            .flat_map(|directory| FILE_NAMES.iter().map(|file_name| directory.join(file_name)))
+            .find(|path_to_file| path_to_file.is_file())
+    }
+
+    pub fn field_mapping(
+        &self,
+        package_name: &str,
+        type_name: &str,
+        field_name: &str,
+    ) -> FieldMapping<'_> {
+        let name = format!("{package_name}/{type_name}.{field_name}");
+        FieldMapping {
+            field: self.fields.get(&name),
+            name,
+            package: self.packages.get(package_name),
+            types: &self.types,
+        }
+    }
+
+    pub fn annotations(&self) -> &AnnotationVocabulary {
+        &self.annotations
+    }
+
+    fn from_file(profile_file: ProfileFile) -> Result<Self> {
+        let mut types = Self::default().types;
+        let overrides = parse_type_mapping(&profile_file.to_iec, &profile_file.to_ros)?;
+        types.to_iec.extend(overrides.to_iec);
+        types.to_ros.extend(overrides.to_ros);
+        let packages = profile_file
+            .packages
+            .iter()
+            .map(|(package_name, package_file)| {
+                parse_type_mapping(&package_file.to_iec, &package_file.to_ros)
+                    .map(|type_mapping| (package_name.clone(), type_mapping))
+                    .map_err(|error| format!("Package \"{package_name}\": {error}"))
+            })
+            .collect::<std::result::Result<HashMap<_, _>, _>>()?;
+        let fields = profile_file
+            .fields
+            .iter()
+            .map(|(name, field_file)| {
+                parse_field_override(name, field_file)
+                    .map(|field_override| (name.clone(), field_override))
+                    .map_err(|error| format!("Field \"{name}\": {error}"))
+            })
+            .collect::<std::result::Result<HashMap<_, _>, _>>()?;
+        let annotations = AnnotationVocabulary::new(
+            &profile_file.annotations.prefix,
+            &profile_file.annotations.names,
+        )?;
+        Ok(Self {
+            types,
+            packages,
+            fields,
+            annotations,
+        })
+    }
+}
+
+impl Default for MappingProfile {
+    fn default() -> Self {
+        let to_iec = DEFAULT_TO_IEC
+            .iter()
+            .map(|(ros_name, iec_name)| (ros_name.to_string(), find_iec_type(iec_name).unwrap()))
+            .collect();
+        let to_ros = DEFAULT_TO_ROS
+            .iter()
+            .map(|(iec_name, ros_name)| (iec_name.to_string(), find_ros_type(ros_name).unwrap()))
+            .collect();
+        Self {
+            types: TypeMapping { to_iec, to_ros },
+            packages: HashMap::new(),
+            fields: HashMap::new(),
+            annotations: AnnotationVocabulary {
+                prefix: ANNOTATION_PREFIX.to_string(),
+                names: HashMap::new(),
+            },
+        }
+    }
+}
+
+impl FieldMapping<'_> {
+    // A bounded string keeps its bound
+    pub fn to_iec_type(&self, base_type: &msg::BaseType) -> Result<dtp::BaseType> {
+        let key = ros_type_key(base_type)
+            .ok_or_else(|| format!("\"{}\" is no primitive type", ros_type_name(base_type)))?;
+        let iec_type = match self.field.and_then(|field| field.to_iec.as_ref()) {
+            Some(iec_type) => {
+                check_kinds(ros_kind(base_type), iec_kind(iec_type), &self.name)?;
+                iec_type
+            }
+            None => self
+                .package
+                .and_then(|package| package.to_iec.get(key))
+                .or_else(|| self.types.to_iec.get(key))
+                .ok_or_else(|| format!("No IEC 61131-3 type known for \"{key}\""))?,
+        };
+        Ok(with_iec_bound(iec_type, ros_bound(base_type)))
+    }
+
+    pub fn to_ros_type(&self, base_type: &dtp::BaseType) -> Result<msg::BaseType> {
+        let key = iec_type_name(base_type)
+            .ok_or_else(|| format!("\"{}\" is no elementary type", iec_type_text(base_type)))?;
+        let ros_type = match self.field.and_then(|field| field.to_ros.as_ref()) {
+            Some(ros_type) => {
+                check_kinds(ros_kind(ros_type), iec_kind(base_type), &self.name)?;
+                ros_type
+            }
+            None => self
+                .package
+                .and_then(|package| package.to_ros.get(key))
+                .or_else(|| self.types.to_ros.get(key))
+                .ok_or_else(|| format!("No ROS 2 type known for \"{key}\""))?,
+        };
+        Ok(with_ros_bound(ros_type, iec_bound(base_type)))
+    }
+}
+
+impl AnnotationVocabulary {
+    fn new(prefix: &str, names: &BTreeMap<String, String>) -> Result<Self> {
+        if !prefix.chars().all(is_identifier_char) {
+            return Err(format!("Invalid annotation prefix \"{prefix}\"").into());
+        }
+        let canonical_names = canonical_annotation_names().collect::<Vec<_>>();
+        for (name, own_name) in names {
+            if !canonical_names.contains(&name.as_str()) {
+                return Err(format!(
+                    "Unknown annotation \"{name}\", expected an IEC 61131-3 type or one of {}",
+                    ANNOTATION_NAMES.join(", ")
+                )
+                .into());
+            }
+            if own_name.is_empty() || !own_name.chars().all(is_identifier_char) {
+                return Err(format!("Invalid name \"{own_name}\" of annotation \"{name}\"").into());
+            }
+        }
+        // Every own name has to lead back to a single annotation
+        for own_name in canonical_names
+            .iter()
+            .map(|name| names.get(*name).map_or(*name, String::as_str))
+        {
+            let count = canonical_names
+                .iter()
+                .filter(|name| names.get(**name).map_or(**name, String::as_str) == own_name)
+                .count();
+            if count > 1 {
+                return Err(format!("Several annotations are named \"{own_name}\"").into());
+            }
+        }
+        Ok(Self {
+            prefix: prefix.to_string(),
+            names: names.clone().into_iter().collect(),
+        })
+    }
+
+    // "@SPS_Offset(2)" becomes "@IEC61499_StartIndex(2)"
+    pub fn canonicalize(&self, comment: &str) -> String {
+        rename_annotations(comment, &self.prefix, ANNOTATION_PREFIX, |name| {
+            self.names
+                .iter()
+                .find(|(_, own_name)| *own_name == name)
+                .map_or(name, |(canonical_name, _)| canonical_name)
+        })
+    }
+
+    // "@IEC61499_StartIndex(2)" becomes "@SPS_Offset(2)"
+    pub fn localize(&self, comment: &str) -> String {
+        rename_annotations(comment, ANNOTATION_PREFIX, &self.prefix, |name| {
+            self.names.get(name).map_or(name, String::as_str)
+        })
+    }
+}
+
+fn canonical_annotation_names() -> impl Iterator<Item = &'static str> {
+    ANNOTATION_NAMES
+        .into_iter()
+        .chain(IEC_TYPES.iter().map(|(name, _)| *name))
+}
+
+fn rename_annotations<'a>(
+    comment: &'a str,
+    from_prefix: &str,
+    to_prefix: &str,
+    rename: impl Fn(&'a str) -> &'a str,
+) -> String {
+    let marker = format!("@{from_prefix}");
+    let mut result = String::new();
+    let mut rest = comment;
+    while let Some(position) = rest.find(&marker) {
+        result.push_str(&rest[..position]);
+        rest = &rest[position + marker.len()..];
+        let end = rest
+            .find(|char| !is_identifier_char(char))
+            .unwrap_or(rest.len());
+        result.push_str(&format!("@{to_prefix}{}", rename(&rest[..end])));
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+fn is_identifier_char(char: char) -> bool {
+    char.is_ascii_alphanumeric() || char == '_'
+}
+
+fn parse_type_mapping(
+    to_iec: &BTreeMap<String, String>,
+    to_ros: &BTreeMap<String, String>,
+) -> Result<TypeMapping> {
+    let mut type_mapping = TypeMapping::default();
+    for (ros_name, iec_text) in to_iec {
+        let ros_type =
+            find_ros_type(ros_name).ok_or_else(|| format!("Unknown ROS 2 type \"{ros_name}\""))?;
+        let iec_type = parse_iec_type(iec_text)?;
+        check_kinds(ros_kind(&ros_type), iec_kind(&iec_type), ros_name)?;
+        type_mapping.to_iec.insert(ros_name.clone(), iec_type);
+    }
+    for (iec_name, ros_text) in to_ros {
+        let iec_type = find_iec_type(iec_name)
+            .ok_or_else(|| format!("Unknown IEC 61131-3 type \"{iec_name}\""))?;
+        let ros_type = parse_ros_type(ros_text)?;
+        check_kinds(ros_kind(&ros_type), iec_kind(&iec_type), iec_name)?;
+        let key = iec_type_name(&iec_type).unwrap_or_default();
+        type_mapping.to_ros.insert(key.to_string(), ros_type);
+    }
+    Ok(type_mapping)
+}
+
+fn parse_field_override(name: &str, field_file: &FieldFile) -> Result<FieldOverride> {
+    let is_valid_name = name
+        .split_once('/')
+        .and_then(|(package_name, rest)| Some((package_name, rest.split_once('.')?)))
+        .is_some_and(|(package_name, (type_name, field_name))| {
+            !package_name.is_empty() && !type_name.is_empty() && !field_name.is_empty()
+        });
+    if !is_valid_name {
+        return Err("Fields are named \"<package>/<type>.<field>\"".into());
+    }
+    Ok(FieldOverride {
+        to_iec: field_file
+            .to_iec
+            .as_deref()
+            .map(parse_iec_type)
+            .transpose()?,
+        to_ros: field_file
+            .to_ros
+            .as_deref()
+            .map(parse_ros_type)
+            .transpose()?,
+    })
+}
+
+fn check_kinds(ros_kind: Option<Kind>, iec_kind: Option<Kind>, name: &str) -> Result<()> {
+    match ros_kind == iec_kind {
+        true => Ok(()),
+        false => Err(format!(
+            "\"{name}\" can only be mapped onto a type of the same kind (bool, integer, real or string)"
+        )
+        .into()),
+    }
+}
+
+// "string<=10" or "uint8"
+pub fn parse_ros_type(text: &str) -> Result<msg::BaseType> {
+    let (name, bound) = match text.split_once("<=") {
+        Some((name, bound)) => (name, Some(parse_bound(text, bound)?)),
+        None => (text, None),
+    };
+    let base_type = find_ros_type(name).ok_or_else(|| format!("Unknown ROS 2 type \"{text}\""))?;
+    match (bound, ros_kind(&base_type)) {
+        (Some(_), Some(kind)) if kind != Kind::String => {
+            Err(format!("Only strings have a bound, not \"{text}\"").into())
+        }
+        _ => Ok(with_ros_bound(&base_type, bound)),
+    }
+}
+
+// "STRING[80]" or "WORD"
+pub fn parse_iec_type(text: &str) -> Result<dtp::BaseType> {
+    let (name, bound) = match text.strip_suffix(']').and_then(|text| text.split_once('[')) {
+        Some((name, bound)) => (name, Some(parse_bound(text, bound)?)),
+        None => (text, None),
+    };
+    let base_type =
+        find_iec_type(name).ok_or_else(|| format!("Unknown IEC 61131-3 type \"{text}\""))?;
+    match (bound, iec_kind(&base_type)) {
+        (Some(_), Some(kind)) if kind != Kind::String => {
+            Err(format!("Only strings have a bound, not \"{text}\"").into())
+        }
+        _ => Ok(with_iec_bound(&base_type, bound)),
+    }
+}
+
+fn parse_bound(text: &str, bound: &str) -> Result<usize> {
+    bound
+        .parse()
+        .map_err(|_| format!("Invalid bound of \"{text}\"").into())
+}
+
+fn find_ros_type(name: &str) -> Option<msg::BaseType> {
+    ROS_TYPES
+        .iter()
+        .find(|(ros_name, _)| *ros_name == name)
+        .map(|(_, base_type)| base_type.clone())
+}
+
+// IEC 61131-3 does not distinguish the case
+fn find_iec_type(name: &str) -> Option<dtp::BaseType> {
+    IEC_TYPES
+        .iter()
+        .find(|(iec_name, _)| iec_name.eq_ignore_ascii_case(name))
+        .map(|(_, base_type)| base_type.clone())
+}
+
+// Strings match regardless of their bound
+fn ros_type_key(base_type: &msg::BaseType) -> Option<&'static str> {
+    ROS_TYPES
+        .iter()
+        .find(|(_, ros_type)| std::mem::discriminant(ros_type) == std::mem::discriminant(base_type))
+        .map(|(name, _)| *name)
+}
+
+// The name of an elementary type without bound, e.g. "STRING" or "WORD"
+pub fn iec_type_name(base_type: &dtp::BaseType) -> Option<&'static str> {
+    IEC_TYPES
+        .iter()
+        .find(|(_, iec_type)| std::mem::discriminant(iec_type) == std::mem::discriminant(base_type))
+        .map(|(name, _)| *name)
+}
+
+pub fn iec_type_names() -> impl Iterator<Item = &'static str> {
+    IEC_TYPES.iter().map(|(name, _)| *name)
+}
+
+// The type as written in a MSG file, e.g. "string<=10"
+pub fn ros_type_name(base_type: &msg::BaseType) -> String {
+    match (ros_type_key(base_type), ros_bound(base_type)) {
+        (Some(name), Some(bound)) => format!("{name}<={bound}"),
+        (Some(name), None) => name.to_string(),
+        (None, _) => format!("{base_type:?}"),
+    }
+}
+
+fn iec_type_text(base_type: &dtp::BaseType) -> String {
+    match (iec_type_name(base_type), iec_bound(base_type)) {
+        (Some(name), Some(bound)) => format!("{name}[{bound}]"),
+        (Some(name), None) => name.to_string(),
+        (None, _) => format!("{base_type:?}"),
+    }
+}
+
+// A type annotated in a MSG file, e.g. "@IEC61499_WORD", gets the bound of the field
+pub fn annotated_iec_type(type_name: &str, base_type: &msg::BaseType) -> Result<dtp::BaseType> {
+    let iec_type = find_iec_type(type_name)
+        .ok_or_else(|| format!("Unknown IEC 61131-3 type \"{type_name}\""))?;
+    check_kinds(ros_kind(base_type), iec_kind(&iec_type), type_name)?;
+    Ok(with_iec_bound(&iec_type, ros_bound(base_type)))
+}
+
+// The type given by the attribute "ROS2_BaseType"
+pub fn recorded_ros_type(text: &str, base_type: &dtp::BaseType) -> Result<msg::BaseType> {
+    let ros_type = parse_ros_type(text)?;
+    check_kinds(ros_kind(&ros_type), iec_kind(base_type), text)?;
+    Ok(ros_type)
+}
+
+fn ros_kind(base_type: &msg::BaseType) -> Option<Kind> {
+    match base_type {
+        msg::BaseType::Bool => Some(Kind::Bool),
+        msg::BaseType::Byte
+        | msg::BaseType::Char
+        | msg::BaseType::Int8
+        | msg::BaseType::Uint8
+        | msg::BaseType::Int16
+        | msg::BaseType::Uint16
+        | msg::BaseType::Int32
+        | msg::BaseType::Uint32
+        | msg::BaseType::Int64
+        | msg::BaseType::Uint64 => Some(Kind::Integer),
+        msg::BaseType::Float32 | msg::BaseType::Float64 => Some(Kind::Real),
+        msg::BaseType::String(_) | msg::BaseType::Wstring(_) => Some(Kind::String),
+        msg::BaseType::Custom(_) => None,
+    }
+}
+
+fn iec_kind(base_type: &dtp::BaseType) -> Option<Kind> {
+    match base_type {
+        dtp::BaseType::BOOL => Some(Kind::Bool),
+        dtp::BaseType::BYTE
+        | dtp::BaseType::WORD
+        | dtp::BaseType::DWORD
+        | dtp::BaseType::LWORD
+        | dtp::BaseType::CHAR
+        | dtp::BaseType::SINT
+        | dtp::BaseType::USINT
+        | dtp::BaseType::INT
+        | dtp::BaseType::UINT
+        | dtp::BaseType::DINT
+        | dtp::BaseType::UDINT
+        | dtp::BaseType::LINT
+        | dtp::BaseType::ULINT => Some(Kind::Integer),
+        dtp::BaseType::REAL | dtp::BaseType::LREAL => Some(Kind::Real),
+        dtp::BaseType::STRING(_) | dtp::BaseType::WSTRING(_) => Some(Kind::String),
+        dtp::BaseType::Custom(_) => None,
+    }
+}
+
+fn ros_bound(base_type: &msg::BaseType) -> Option<usize> {
+    match base_type {
+        msg::BaseType::String(bound) | msg::BaseType::Wstring(bound) => *bound,
+        _ => None,
+    }
+}
+
+fn iec_bound(base_type: &dtp::BaseType) -> Option<usize> {
+    match base_type {
+        dtp::BaseType::STRING(bound) | dtp::BaseType::WSTRING(bound) => *bound,
+        _ => None,
+    }
+}
+
+// Without a bound of its own a string keeps the one of the mapping
+fn with_ros_bound(base_type: &msg::BaseType, bound: Option<usize>) -> msg::BaseType {
+    match base_type {
+        msg::BaseType::String(own_bound) => msg::BaseType::String(bound.or(*own_bound)),
+        msg::BaseType::Wstring(own_bound) => msg::BaseType::Wstring(bound.or(*own_bound)),
+        base_type => base_type.clone(),
+    }
+}
+
+fn with_iec_bound(base_type: &dtp::BaseType, bound: Option<usize>) -> dtp::BaseType {
+    match base_type {
+        dtp::BaseType::STRING(own_bound) => dtp::BaseType::STRING(bound.or(*own_bound)),
+        dtp::BaseType::WSTRING(own_bound) => dtp::BaseType::WSTRING(bound.or(*own_bound)),
+        base_type => base_type.clone(),
+    }
+}
@@ -1,4 +1,10 @@
 pub mod error;
 pub mod handler;
+mod conversion_options;
 mod dtp_converter;
+mod dynamic_array;
+mod identifier;
+mod mapping_profile;
 mod msg_converter;
+mod string_codec;
+mod type_name;
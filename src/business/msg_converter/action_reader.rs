@@ -0,0 +1,25 @@
+use std::path::Path;
+
+use log::info;
+
+use crate::business::error::Result;
+use crate::business::msg_converter::msg_reader::{
+    parse_file_name, parse_structured_type, split_sections,
+};
+use crate::core::action::*;
+
+pub fn read(path_to_source_file: &str) -> Result<Action> {
+    info!("Start reading file {:?}", path_to_source_file);
+    let source_file_path = Path::new(path_to_source_file);
+    let file_name = parse_file_name(source_file_path)?;
+    let file_content = std::fs::read_to_string(source_file_path)?;
+    let sections = split_sections(&file_content, 3)?;
+    let goal =
+        parse_structured_type(&format!("{file_name}{GOAL_SUFFIX}"), &sections[0])?;
+    let result =
+        parse_structured_type(&format!("{file_name}{RESULT_SUFFIX}"), &sections[1])?;
+    let feedback =
+        parse_structured_type(&format!("{file_name}{FEEDBACK_SUFFIX}"), &sections[2])?;
+    info!("Finished reading file {:?}", path_to_source_file);
+    Ok(Action::new(&file_name, &goal, &result, &feedback))
+}
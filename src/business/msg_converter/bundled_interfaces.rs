@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use tempfile::TempDir;
+
+use crate::business::error::Result;
+use crate::business::msg_converter::reference_resolver::{
+    AMENT_INDEX_DIRECTORY, ROSIDL_INTERFACES_RESOURCE_TYPE, SHARE_DIRECTORY,
+};
+
+// Definitions of common_interfaces and builtin_interfaces as of ROS 2 Humble, which are
+// embedded into the binary, so referenced standard messages resolve without a ROS 2 installation
+macro_rules! bundled_file {
+    ($path:literal) => {
+        ($path, include_str!(concat!("../../../bundled/", $path)))
+    };
+}
+
+// Paths relative to the install prefix like "share/std_msgs/msg/Header.msg"
+const FILES: &[(&str, &str)] = &[
+    bundled_file!("share/action_msgs/package.xml"),
+    bundled_file!("share/action_msgs/msg/GoalInfo.msg"),
+    bundled_file!("share/action_msgs/msg/GoalStatus.msg"),
+    bundled_file!("share/builtin_interfaces/package.xml"),
+    bundled_file!("share/builtin_interfaces/msg/Duration.msg"),
+    bundled_file!("share/builtin_interfaces/msg/Time.msg"),
+    bundled_file!("share/unique_identifier_msgs/package.xml"),
+    bundled_file!("share/unique_identifier_msgs/msg/UUID.msg"),
+    bundled_file!("share/std_msgs/package.xml"),
+    bundled_file!("share/std_msgs/msg/Bool.msg"),
+    bundled_file!("share/std_msgs/msg/Byte.msg"),
+    bundled_file!("share/std_msgs/msg/ByteMultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/Char.msg"),
+    bundled_file!("share/std_msgs/msg/ColorRGBA.msg"),
+    bundled_file!("share/std_msgs/msg/Empty.msg"),
+    bundled_file!("share/std_msgs/msg/Float32.msg"),
+    bundled_file!("share/std_msgs/msg/Float32MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/Float64.msg"),
+    bundled_file!("share/std_msgs/msg/Float64MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/Header.msg"),
+    bundled_file!("share/std_msgs/msg/Int16.msg"),
+    bundled_file!("share/std_msgs/msg/Int16MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/Int32.msg"),
+    bundled_file!("share/std_msgs/msg/Int32MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/Int64.msg"),
+    bundled_file!("share/std_msgs/msg/Int64MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/Int8.msg"),
+    bundled_file!("share/std_msgs/msg/Int8MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/MultiArrayDimension.msg"),
+    bundled_file!("share/std_msgs/msg/MultiArrayLayout.msg"),
+    bundled_file!("share/std_msgs/msg/String.msg"),
+    bundled_file!("share/std_msgs/msg/UInt16.msg"),
+    bundled_file!("share/std_msgs/msg/UInt16MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/UInt32.msg"),
+    bundled_file!("share/std_msgs/msg/UInt32MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/UInt64.msg"),
+    bundled_file!("share/std_msgs/msg/UInt64MultiArray.msg"),
+    bundled_file!("share/std_msgs/msg/UInt8.msg"),
+    bundled_file!("share/std_msgs/msg/UInt8MultiArray.msg"),
+    bundled_file!("share/geometry_msgs/package.xml"),
+    bundled_file!("share/geometry_msgs/msg/Accel.msg"),
+    bundled_file!("share/geometry_msgs/msg/AccelStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/AccelWithCovariance.msg"),
+    bundled_file!("share/geometry_msgs/msg/AccelWithCovarianceStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Inertia.msg"),
+    bundled_file!("share/geometry_msgs/msg/InertiaStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Point.msg"),
+    bundled_file!("share/geometry_msgs/msg/Point32.msg"),
+    bundled_file!("share/geometry_msgs/msg/PointStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Polygon.msg"),
+    bundled_file!("share/geometry_msgs/msg/PolygonStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Pose.msg"),
+    bundled_file!("share/geometry_msgs/msg/Pose2D.msg"),
+    bundled_file!("share/geometry_msgs/msg/PoseArray.msg"),
+    bundled_file!("share/geometry_msgs/msg/PoseStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/PoseWithCovariance.msg"),
+    bundled_file!("share/geometry_msgs/msg/PoseWithCovarianceStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Quaternion.msg"),
+    bundled_file!("share/geometry_msgs/msg/QuaternionStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Transform.msg"),
+    bundled_file!("share/geometry_msgs/msg/TransformStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Twist.msg"),
+    bundled_file!("share/geometry_msgs/msg/TwistStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/TwistWithCovariance.msg"),
+    bundled_file!("share/geometry_msgs/msg/TwistWithCovarianceStamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Vector3.msg"),
+    bundled_file!("share/geometry_msgs/msg/Vector3Stamped.msg"),
+    bundled_file!("share/geometry_msgs/msg/Wrench.msg"),
+    bundled_file!("share/geometry_msgs/msg/WrenchStamped.msg"),
+    bundled_file!("share/sensor_msgs/package.xml"),
+    bundled_file!("share/sensor_msgs/msg/BatteryState.msg"),
+    bundled_file!("share/sensor_msgs/msg/CameraInfo.msg"),
+    bundled_file!("share/sensor_msgs/msg/ChannelFloat32.msg"),
+    bundled_file!("share/sensor_msgs/msg/CompressedImage.msg"),
+    bundled_file!("share/sensor_msgs/msg/FluidPressure.msg"),
+    bundled_file!("share/sensor_msgs/msg/Illuminance.msg"),
+    bundled_file!("share/sensor_msgs/msg/Image.msg"),
+    bundled_file!("share/sensor_msgs/msg/Imu.msg"),
+    bundled_file!("share/sensor_msgs/msg/JointState.msg"),
+    bundled_file!("share/sensor_msgs/msg/Joy.msg"),
+    bundled_file!("share/sensor_msgs/msg/JoyFeedback.msg"),
+    bundled_file!("share/sensor_msgs/msg/JoyFeedbackArray.msg"),
+    bundled_file!("share/sensor_msgs/msg/LaserEcho.msg"),
+    bundled_file!("share/sensor_msgs/msg/LaserScan.msg"),
+    bundled_file!("share/sensor_msgs/msg/MagneticField.msg"),
+    bundled_file!("share/sensor_msgs/msg/MultiDOFJointState.msg"),
+    bundled_file!("share/sensor_msgs/msg/MultiEchoLaserScan.msg"),
+    bundled_file!("share/sensor_msgs/msg/NavSatFix.msg"),
+    bundled_file!("share/sensor_msgs/msg/NavSatStatus.msg"),
+    bundled_file!("share/sensor_msgs/msg/PointCloud.msg"),
+    bundled_file!("share/sensor_msgs/msg/PointCloud2.msg"),
+    bundled_file!("share/sensor_msgs/msg/PointField.msg"),
+    bundled_file!("share/sensor_msgs/msg/Range.msg"),
+    bundled_file!("share/sensor_msgs/msg/RegionOfInterest.msg"),
+    bundled_file!("share/sensor_msgs/msg/RelativeHumidity.msg"),
+    bundled_file!("share/sensor_msgs/msg/Temperature.msg"),
+    bundled_file!("share/sensor_msgs/msg/TimeReference.msg"),
+    bundled_file!("share/sensor_msgs/srv/SetCameraInfo.srv"),
+];
+
+// Prefix of the name of the temporary directory
+const PREFIX_DIRECTORY: &str = concat!(env!("CARGO_PKG_NAME"), "-", env!("CARGO_PKG_VERSION"), "-");
+
+// The bundled files written as an install prefix including its ament index, so they are read
+// like any other installed package. Each extraction gets its own temporary directory, which
+// only the current user can access and which is removed on drop.
+#[derive(Debug)]
+pub struct BundledInterfaces {
+    directory: TempDir,
+}
+
+impl BundledInterfaces {
+    pub fn extract() -> Result<Self> {
+        let directory = tempfile::Builder::new()
+            .prefix(PREFIX_DIRECTORY)
+            .tempdir()?;
+        let prefix = directory.path();
+        info!("Extracting bundled interfaces to {:?}", prefix);
+        for (path_to_file, content) in FILES {
+            write(&prefix.join(path_to_file), content)?;
+        }
+        let path_to_resource_directory = prefix
+            .join(AMENT_INDEX_DIRECTORY)
+            .join(ROSIDL_INTERFACES_RESOURCE_TYPE);
+        for (package_name, interface_files) in interface_files() {
+            let content: String = interface_files
+                .iter()
+                .map(|interface_file| format!("{interface_file}\n"))
+                .collect();
+            write(&path_to_resource_directory.join(package_name), &content)?;
+        }
+        Ok(Self { directory })
+    }
+
+    pub fn prefix(&self) -> &Path {
+        self.directory.path()
+    }
+
+    pub fn package_directories(&self) -> Vec<PathBuf> {
+        interface_files()
+            .into_keys()
+            .map(|package_name| self.prefix().join(SHARE_DIRECTORY).join(package_name))
+            .collect()
+    }
+}
+
+// The content of a bundled file by its path relative to the install prefix
+pub fn find(path_to_file: &str) -> Option<&'static str> {
+    FILES
+        .iter()
+        .find(|(path, _)| *path == path_to_file)
+        .map(|(_, content)| *content)
+}
+
+// Files of each package relative to its directory like "msg/Header.msg"
+fn interface_files() -> BTreeMap<&'static str, Vec<&'static str>> {
+    let mut interface_files: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (path_to_file, _) in FILES {
+        let Some(path_in_share) = path_to_file.strip_prefix(&format!("{SHARE_DIRECTORY}/")) else {
+            continue;
+        };
+        let Some((package_name, interface_file)) = path_in_share.split_once('/') else {
+            continue;
+        };
+        let entry = interface_files.entry(package_name).or_default();
+        if interface_file.contains('/') {
+            entry.push(interface_file);
+        }
+    }
+    interface_files
+}
+
+fn write(path_to_file: &Path, content: &str) -> Result<()> {
+    debug!("Writing bundled file {:?}", path_to_file);
+    if let Some(directory) = path_to_file.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+    std::fs::write(path_to_file, content)?;
+    Ok(())
+}
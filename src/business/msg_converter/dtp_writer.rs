@@ -1,26 +1,95 @@
 use std::borrow::Cow;
-use std::fs::File;
+use std::fs;
 use std::string::ToString;
 
 use xmltree::{Element, EmitterConfig, XMLNode};
 
 use crate::business::error::Result;
+use crate::business::string_codec::encode_iec_string;
 use crate::core::dtp::*;
 
+// Written in the layout of the 4diac IDE, so that saving
+// a converted file in the IDE does not change it
 pub fn write(data_type: DataType, to_directory: &str) -> Result<()> {
     let data_type_name = data_type.name().to_string();
-    let custom_data_type_element = create_data_type_element(data_type);
+    let mut custom_data_type_element = create_data_type_element(data_type);
+    escape_element(&mut custom_data_type_element);
 
     let mut config = EmitterConfig::new();
     config.perform_escaping = false;
     config.perform_indent = true;
-    config.indent_string = Cow::Borrowed("    ");
+    config.indent_string = Cow::Borrowed("\t");
     config.pad_self_closing = false;
-    let file = File::create(format!("{to_directory}{data_type_name}.dtp"))?;
-    custom_data_type_element.write_with_config(file, config)?;
+    let mut content = Vec::new();
+    custom_data_type_element.write_with_config(&mut content, config)?;
+    content.push(b'\n');
+    fs::write(format!("{to_directory}{data_type_name}.dtp"), content)?;
     Ok(())
 }
 
+// The 4diac IDE keeps apostrophes and writes line breaks as character references
+fn escape_element(element: &mut Element) {
+    for value in element.attributes.values_mut() {
+        *value = value
+            .chars()
+            .map(|char| match char {
+                '&' => "&amp;".to_string(),
+                '<' => "&lt;".to_string(),
+                '>' => "&gt;".to_string(),
+                '"' => "&quot;".to_string(),
+                '\n' => "&#10;".to_string(),
+                '\r' => "&#13;".to_string(),
+                '\t' => "&#9;".to_string(),
+                _ => char.to_string(),
+            })
+            .collect();
+    }
+    for child in element.children.iter_mut() {
+        match child {
+            XMLNode::Element(child) => escape_element(child),
+            XMLNode::Text(text) => {
+                *text = text
+                    .replace('&', "&amp;")
+                    .replace('<', "&lt;")
+                    .replace('>', "&gt;")
+            }
+            _ => {}
+        }
+    }
+}
+
+// Puts the XML, which is not modeled, back to where it was read from
+fn insert_foreign_xml(element: &mut Element, foreign_xml: &ForeignXml) {
+    if !foreign_xml.attributes.is_empty() {
+        let mut attributes: Vec<(String, String)> = element.attributes.drain(..).collect();
+        for (position, name, value) in foreign_xml.attributes.iter() {
+            let position = (*position).min(attributes.len());
+            attributes.insert(position, (name.clone(), value.clone()));
+        }
+        element.attributes = attributes.into_iter().collect();
+    }
+    for (position, foreign_element) in foreign_xml.elements.iter() {
+        let position = (*position).min(element.children.len());
+        element
+            .children
+            .insert(position, create_foreign_element(foreign_element));
+    }
+}
+
+fn create_foreign_element(foreign_element: &ForeignElement) -> XMLNode {
+    let mut element = Element::new(&foreign_element.name);
+    element.attributes = foreign_element.attributes.iter().cloned().collect();
+    element.children = foreign_element
+        .children
+        .iter()
+        .map(|child| match child {
+            ForeignNode::Element(child) => create_foreign_element(child),
+            ForeignNode::Text(text) => XMLNode::Text(text.clone()),
+        })
+        .collect();
+    XMLNode::Element(element)
+}
+
 fn create_data_type_element(data_type: DataType) -> Element {
     let mut data_type_element = Element::new(XML_TAG_DATA_TYPE);
     data_type_element
@@ -31,16 +100,249 @@ fn create_data_type_element(data_type: DataType) -> Element {
             .attributes
             .insert(XML_ATTRIBUTE_COMMENT.to_string(), comment.clone());
     }
+    if let Some(identification) = data_type.identification() {
+        data_type_element
+            .children
+            .push(create_identification_element(identification));
+    }
+    data_type_element.children.append(
+        &mut data_type
+            .version_info()
+            .iter()
+            .map(create_version_info_element)
+            .collect(),
+    );
+    if let Some(compiler_info) = data_type.compiler_info() {
+        data_type_element
+            .children
+            .push(create_compiler_info_element(compiler_info));
+    }
     data_type_element
         .children
         .push(match data_type.data_type_kind() {
+            DataTypeKind::DirectlyDerivedType(directly_derived_type) => {
+                create_directly_derived_type_element(directly_derived_type)
+            }
+            DataTypeKind::ArrayType(array_type) => create_array_type_element(array_type),
+            DataTypeKind::EnumeratedType(enumerated_type) => {
+                create_enumerated_type_element(enumerated_type)
+            }
+            DataTypeKind::SubrangeType(subrange_type) => {
+                create_subrange_type_element(subrange_type)
+            }
             DataTypeKind::StructuredType(structured_type) => {
                 create_structured_type_element(structured_type)
             }
         });
+    data_type_element.children.append(
+        &mut data_type
+            .attributes()
+            .iter()
+            .map(create_attribute_element)
+            .collect(),
+    );
+    insert_foreign_xml(&mut data_type_element, data_type.foreign_xml());
     data_type_element
 }
 
+fn create_identification_element(identification: &Identification) -> XMLNode {
+    let mut identification_element = Element::new(XML_TAG_IDENTIFICATION);
+    for (attribute_name, value) in [
+        (XML_ATTRIBUTE_STANDARD, &identification.standard),
+        (XML_ATTRIBUTE_CLASSIFICATION, &identification.classification),
+        (XML_ATTRIBUTE_APPLICATION_DOMAIN, &identification.application_domain),
+        (XML_ATTRIBUTE_FUNCTION, &identification.function),
+        (XML_ATTRIBUTE_TYPE, &identification.identification_type),
+        (XML_ATTRIBUTE_DESCRIPTION, &identification.description),
+    ] {
+        if let Some(value) = value {
+            identification_element
+                .attributes
+                .insert(attribute_name.to_string(), value.clone());
+        }
+    }
+    XMLNode::Element(identification_element)
+}
+
+fn create_version_info_element(version_info: &VersionInfo) -> XMLNode {
+    let mut version_info_element = Element::new(XML_TAG_VERSION_INFO);
+    if let Some(organization) = &version_info.organization {
+        version_info_element
+            .attributes
+            .insert(XML_ATTRIBUTE_ORGANIZATION.to_string(), organization.clone());
+    }
+    version_info_element
+        .attributes
+        .insert(XML_ATTRIBUTE_VERSION.to_string(), version_info.version.clone());
+    version_info_element
+        .attributes
+        .insert(XML_ATTRIBUTE_AUTHOR.to_string(), version_info.author.clone());
+    version_info_element
+        .attributes
+        .insert(XML_ATTRIBUTE_DATE.to_string(), version_info.date.clone());
+    if let Some(remarks) = &version_info.remarks {
+        version_info_element
+            .attributes
+            .insert(XML_ATTRIBUTE_REMARKS.to_string(), remarks.clone());
+    }
+    XMLNode::Element(version_info_element)
+}
+
+fn create_compiler_info_element(compiler_info: &CompilerInfo) -> XMLNode {
+    let mut compiler_info_element = Element::new(XML_TAG_COMPILER_INFO);
+    if let Some(package_name) = &compiler_info.package_name {
+        compiler_info_element
+            .attributes
+            .insert(XML_ATTRIBUTE_PACKAGE_NAME.to_string(), package_name.clone());
+    }
+    if let Some(header) = &compiler_info.header {
+        compiler_info_element
+            .attributes
+            .insert(XML_ATTRIBUTE_HEADER.to_string(), header.clone());
+    }
+    if let Some(classdef) = &compiler_info.classdef {
+        compiler_info_element
+            .attributes
+            .insert(XML_ATTRIBUTE_CLASSDEF.to_string(), classdef.clone());
+    }
+    for compiler in compiler_info.compilers.iter() {
+        let mut compiler_element = Element::new(XML_TAG_COMPILER);
+        for (attribute_name, value) in [
+            (XML_ATTRIBUTE_LANGUAGE, &compiler.language),
+            (XML_ATTRIBUTE_VENDOR, &compiler.vendor),
+            (XML_ATTRIBUTE_PRODUCT, &compiler.product),
+            (XML_ATTRIBUTE_VERSION, &compiler.version),
+        ] {
+            compiler_element
+                .attributes
+                .insert(attribute_name.to_string(), value.clone());
+        }
+        compiler_info_element
+            .children
+            .push(XMLNode::Element(compiler_element));
+    }
+    XMLNode::Element(compiler_info_element)
+}
+
+fn create_directly_derived_type_element(directly_derived_type: &DirectlyDerivedType) -> XMLNode {
+    let mut directly_derived_type_element = Element::new(XML_TAG_DIRECTLY_DERIVED_TYPE);
+    directly_derived_type_element.attributes.insert(
+        XML_ATTRIBUTE_BASE_TYPE.to_string(),
+        base_type_to_string(directly_derived_type.base_type()),
+    );
+    if let Some(initial_value) = directly_derived_type.initial_value() {
+        directly_derived_type_element.attributes.insert(
+            XML_ATTRIBUTE_INITIAL_VALUE.to_string(),
+            initial_value_to_string(initial_value),
+        );
+    }
+    if let Some(comment) = directly_derived_type.comment() {
+        directly_derived_type_element
+            .attributes
+            .insert(XML_ATTRIBUTE_COMMENT.to_string(), comment.clone());
+    }
+    XMLNode::Element(directly_derived_type_element)
+}
+
+fn create_array_type_element(array_type: &ArrayType) -> XMLNode {
+    let mut array_type_element = Element::new(XML_TAG_ARRAY_TYPE);
+    array_type_element.attributes.insert(
+        XML_ATTRIBUTE_BASE_TYPE.to_string(),
+        base_type_to_string(array_type.base_type()),
+    );
+    if let Some(initial_values) = array_type.initial_values() {
+        array_type_element.attributes.insert(
+            XML_ATTRIBUTE_INITIAL_VALUES.to_string(),
+            initial_value_to_string(initial_values),
+        );
+    }
+    if let Some(comment) = array_type.comment() {
+        array_type_element
+            .attributes
+            .insert(XML_ATTRIBUTE_COMMENT.to_string(), comment.clone());
+    }
+    array_type_element.children.append(
+        &mut array_type
+            .subranges()
+            .iter()
+            .map(create_subrange_element)
+            .collect(),
+    );
+    XMLNode::Element(array_type_element)
+}
+
+fn create_enumerated_type_element(enumerated_type: &EnumeratedType) -> XMLNode {
+    let mut enumerated_type_element = Element::new(XML_TAG_ENUMERATED_TYPE);
+    if let Some(initial_value) = enumerated_type.initial_value() {
+        enumerated_type_element
+            .attributes
+            .insert(XML_ATTRIBUTE_INITIAL_VALUE.to_string(), initial_value.clone());
+    }
+    if let Some(comment) = enumerated_type.comment() {
+        enumerated_type_element
+            .attributes
+            .insert(XML_ATTRIBUTE_COMMENT.to_string(), comment.clone());
+    }
+    enumerated_type_element.children.append(
+        &mut enumerated_type
+            .values()
+            .iter()
+            .map(create_enumerated_value_element)
+            .collect(),
+    );
+    XMLNode::Element(enumerated_type_element)
+}
+
+fn create_enumerated_value_element(enumerated_value: &EnumeratedValue) -> XMLNode {
+    let mut enumerated_value_element = Element::new(XML_TAG_ENUMERATED_VALUE);
+    enumerated_value_element.attributes.insert(
+        XML_ATTRIBUTE_NAME.to_string(),
+        enumerated_value.name().to_string(),
+    );
+    if let Some(comment) = enumerated_value.comment() {
+        enumerated_value_element
+            .attributes
+            .insert(XML_ATTRIBUTE_COMMENT.to_string(), comment.clone());
+    }
+    XMLNode::Element(enumerated_value_element)
+}
+
+fn create_subrange_type_element(subrange_type: &SubrangeType) -> XMLNode {
+    let mut subrange_type_element = Element::new(XML_TAG_SUBRANGE_TYPE);
+    subrange_type_element.attributes.insert(
+        XML_ATTRIBUTE_BASE_TYPE.to_string(),
+        base_type_to_string(subrange_type.base_type()),
+    );
+    if let Some(initial_value) = subrange_type.initial_value() {
+        subrange_type_element.attributes.insert(
+            XML_ATTRIBUTE_INITIAL_VALUE.to_string(),
+            initial_value_to_string(initial_value),
+        );
+    }
+    if let Some(comment) = subrange_type.comment() {
+        subrange_type_element
+            .attributes
+            .insert(XML_ATTRIBUTE_COMMENT.to_string(), comment.clone());
+    }
+    subrange_type_element
+        .children
+        .push(create_subrange_element(subrange_type.subrange()));
+    XMLNode::Element(subrange_type_element)
+}
+
+fn create_subrange_element(subrange: &Subrange) -> XMLNode {
+    let mut subrange_element = Element::new(XML_TAG_SUBRANGE);
+    subrange_element.attributes.insert(
+        XML_ATTRIBUTE_LOWER_LIMIT.to_string(),
+        subrange.lower_limit.to_string(),
+    );
+    subrange_element.attributes.insert(
+        XML_ATTRIBUTE_UPPER_LIMIT.to_string(),
+        subrange.upper_limit.to_string(),
+    );
+    XMLNode::Element(subrange_element)
+}
+
 fn create_structured_type_element(structured_type: &StructuredType) -> XMLNode {
     let mut structured_type_element = Element::new(XML_TAG_STRUCTURED_TYPE);
     if let Some(comment) = structured_type.comment() {
@@ -56,9 +358,13 @@ fn create_structured_type_element(structured_type: &StructuredType) -> XMLNode {
                 StructuredTypeChild::VarDeclaration(var_declaration) => {
                     create_var_declaration_element(var_declaration)
                 }
+                StructuredTypeChild::SubrangeVarDeclaration(subrange_var_declaration) => {
+                    create_subrange_var_declaration_element(subrange_var_declaration)
+                }
             })
             .collect(),
     );
+    insert_foreign_xml(&mut structured_type_element, structured_type.foreign_xml());
     XMLNode::Element(structured_type_element)
 }
 
@@ -100,9 +406,48 @@ fn create_var_declaration_element(var_declaration: &VarDeclaration) -> XMLNode {
             .map(create_attribute_element)
             .collect(),
     );
+    insert_foreign_xml(&mut var_declaration_element, var_declaration.foreign_xml());
     XMLNode::Element(var_declaration_element)
 }
 
+fn create_subrange_var_declaration_element(
+    subrange_var_declaration: &SubrangeVarDeclaration,
+) -> XMLNode {
+    let mut subrange_var_declaration_element = Element::new(XML_TAG_SUBRANGE_VAR_DECLARATION);
+    subrange_var_declaration_element.attributes.insert(
+        XML_ATTRIBUTE_NAME.to_string(),
+        subrange_var_declaration.name().to_string(),
+    );
+    subrange_var_declaration_element.attributes.insert(
+        XML_ATTRIBUTE_TYPE.to_string(),
+        base_type_to_string(subrange_var_declaration.base_type()),
+    );
+    if let Some(initial_value) = subrange_var_declaration.initial_value() {
+        subrange_var_declaration_element.attributes.insert(
+            XML_ATTRIBUTE_INITIAL_VALUE.to_string(),
+            initial_value_to_string(initial_value),
+        );
+    }
+    if let Some(comment) = subrange_var_declaration.comment() {
+        subrange_var_declaration_element
+            .attributes
+            .insert(XML_ATTRIBUTE_COMMENT.to_string(), comment.clone());
+    }
+    if let Some(subrange) = subrange_var_declaration.subrange() {
+        subrange_var_declaration_element
+            .children
+            .push(create_subrange_element(subrange));
+    }
+    subrange_var_declaration_element.children.append(
+        &mut subrange_var_declaration
+            .attributes()
+            .iter()
+            .map(create_attribute_element)
+            .collect(),
+    );
+    XMLNode::Element(subrange_var_declaration_element)
+}
+
 fn create_attribute_element(attribute: &Attribute) -> XMLNode {
     let mut attribute_element = Element::new(XML_TAG_ATTRIBUTE);
     attribute_element.attributes.insert(
@@ -172,9 +517,11 @@ fn initial_value_to_string(initial_value: &InitialValue) -> String {
         InitialValue::REAL(value) => value.to_string(),
         InitialValue::LREAL(value) => value.to_string(),
         InitialValue::CHAR(value) => char_literal_as_string(value),
-        InitialValue::STRING(value) => format!("'{value}'"),
-        InitialValue::WSTRING(value) => format!("&quot;{value}&quot;"),
+        InitialValue::STRING(value) => format!("'{}'", encode_iec_string(value, false)),
+        InitialValue::WSTRING(value) => format!("\"{}\"", encode_iec_string(value, true)),
         InitialValue::Array(values) => array_of_initial_values_as_string(values),
+        InitialValue::Struct(elements) => struct_of_initial_values_as_string(elements),
+        InitialValue::Enumerated(value) => value.clone(),
     }
 }
 
@@ -214,3 +561,60 @@ fn array_of_initial_values_as_string(values: &[InitialValue]) -> String {
             .join(", ")
     )
 }
+
+fn struct_of_initial_values_as_string(elements: &[StructElement]) -> String {
+    format!(
+        "({})",
+        elements
+            .iter()
+            .map(|element| format!("{}:={}", element.name, initial_value_to_string(&element.value)))
+            .collect::<Vec<String>>()
+            .join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::business::dtp_converter::dtp_reader;
+
+    // Written in the layout of the 4diac IDE, they have to be written byte by byte the same
+    const REFERENCE_FILES: [&str; 6] = [
+        "Iec61499Betriebsart.dtp",
+        "Iec61499Bezeichnung.dtp",
+        "Iec61499Matrix.dtp",
+        "Iec61499Metadaten.dtp",
+        "Iec61499Prozent.dtp",
+        "Iec61499Sonderzeichen.dtp",
+    ];
+
+    fn rewrite(file_name: &str) -> (String, String) {
+        let path_to_file = format!("{}/test/0-4diac/{file_name}", env!("CARGO_MANIFEST_DIR"));
+        let directory = tempfile::tempdir().unwrap();
+        let to_directory = format!("{}/", directory.path().to_string_lossy());
+        let data_type = dtp_reader::read(&path_to_file).unwrap();
+        write(data_type, &to_directory).unwrap();
+        (
+            fs::read_to_string(&path_to_file).unwrap(),
+            fs::read_to_string(format!("{to_directory}{file_name}")).unwrap(),
+        )
+    }
+
+    #[test]
+    fn reference_files_are_reproduced() {
+        for file_name in REFERENCE_FILES {
+            let (original, written) = rewrite(file_name);
+            assert_eq!(written, original, "{file_name}");
+        }
+    }
+
+    #[test]
+    fn special_characters_are_escaped_like_the_ide() {
+        let (_, written) = rewrite("Iec61499Sonderzeichen.dtp");
+        assert!(written.contains(r#"Comment="Comments with &lt;tags&gt;, &amp; and &quot;quotes&quot; that's&#10;spanning two lines""#));
+        assert!(
+            written.contains(r#"InitialValue="'it$'s &lt;b&gt; &amp; &quot;c&quot; for $$5$L'""#)
+        );
+        assert!(written.contains(r#"Comment="Tab&#9;separated""#));
+    }
+}
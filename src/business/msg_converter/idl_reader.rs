@@ -0,0 +1,668 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use log::info;
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take, take_until};
+use nom::character::complete::{alpha1, alphanumeric1, char, digit1, multispace1, not_line_ending, satisfy};
+use nom::combinator::{all_consuming, map, map_res, not, opt, peek, recognize, value};
+use nom::multi::{many0, many1, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::{Finish, IResult};
+
+use crate::business::error::Result;
+use crate::business::msg_converter::msg_reader;
+use crate::business::string_codec::{decode_ros_string, encode_ros_string};
+use crate::core::idl::*;
+use crate::core::msg::*;
+use crate::core::{action, msg, srv};
+
+type Typedefs = HashMap<String, (BaseType, Option<Constraint>)>;
+
+#[derive(Debug)]
+struct Definition {
+    annotations: Vec<Annotation>,
+    kind: DefinitionKind,
+}
+
+#[derive(Debug)]
+enum DefinitionKind {
+    Module(String, Vec<Definition>),
+    Struct(String, Vec<Member>),
+    // Type, name and the literal as written
+    Const(TypeSpec, String, String),
+    Typedef(TypeSpec, String, Option<usize>),
+}
+
+#[derive(Debug)]
+struct Member {
+    annotations: Vec<Annotation>,
+    type_spec: TypeSpec,
+    name: String,
+    array_size: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+enum TypeSpec {
+    // Primitive type, typedef or scoped name, e.g. "unsigned long" or "std_msgs::msg::Header"
+    Named(String),
+    String(Option<usize>),
+    Wstring(Option<usize>),
+    Sequence(Box<TypeSpec>, Option<usize>),
+}
+
+pub fn read(path_to_source_file: &str) -> Result<Interface> {
+    info!("Start reading file {:?}", path_to_source_file);
+    let source_file_path = Path::new(path_to_source_file);
+    let file_name = msg_reader::parse_file_name(source_file_path)?;
+    let file_content = std::fs::read_to_string(source_file_path)?;
+    let (_, definitions) = parse_specification(&file_content)
+        .map_err(|err| err.to_owned())
+        .finish()?;
+    let interface = convert_specification(&file_name, &definitions)?;
+    info!("Finished reading file {:?}", path_to_source_file);
+    Ok(interface)
+}
+
+// rosidl nests every interface in the modules "<package>::<msg|srv|action>"
+fn convert_specification(file_name: &str, definitions: &[Definition]) -> Result<Interface> {
+    let (package_name, package_definitions) = find_module(definitions)?;
+    let (interface_kind, definitions) = find_module(package_definitions)?;
+    let typedefs = convert_typedefs(package_name, definitions)?;
+    let convert = |name: &str| convert_struct(package_name, definitions, &typedefs, name);
+    match interface_kind {
+        msg::INTERFACE_KIND => Ok(Interface::Message(convert(file_name)?)),
+        srv::INTERFACE_KIND => Ok(Interface::Service(srv::Service::new(
+            file_name,
+            &convert(&format!("{file_name}{}", srv::REQUEST_SUFFIX))?,
+            &convert(&format!("{file_name}{}", srv::RESPONSE_SUFFIX))?,
+        ))),
+        action::INTERFACE_KIND => Ok(Interface::Action(action::Action::new(
+            file_name,
+            &convert(&format!("{file_name}{}", action::GOAL_SUFFIX))?,
+            &convert(&format!("{file_name}{}", action::RESULT_SUFFIX))?,
+            &convert(&format!("{file_name}{}", action::FEEDBACK_SUFFIX))?,
+        ))),
+        other => Err(format!(
+            "Unknown interface kind \"{other}\", expected \"{}\", \"{}\" or \"{}\"",
+            msg::INTERFACE_KIND,
+            srv::INTERFACE_KIND,
+            action::INTERFACE_KIND
+        )
+        .into()),
+    }
+}
+
+fn find_module(definitions: &[Definition]) -> Result<(&str, &[Definition])> {
+    definitions
+        .iter()
+        .find_map(|definition| match &definition.kind {
+            DefinitionKind::Module(name, definitions) => Some((name.as_str(), definitions.as_slice())),
+            _ => None,
+        })
+        .ok_or_else(|| "Expected a module".into())
+}
+
+fn convert_typedefs(package_name: &str, definitions: &[Definition]) -> Result<Typedefs> {
+    let mut typedefs = Typedefs::new();
+    for definition in definitions.iter() {
+        if let DefinitionKind::Typedef(type_spec, name, array_size) = &definition.kind {
+            let resolved = convert_type_spec(package_name, &typedefs, type_spec)?;
+            let resolved = apply_array_size(name, resolved, *array_size)?;
+            typedefs.insert(name.clone(), resolved);
+        }
+    }
+    Ok(typedefs)
+}
+
+fn convert_struct(
+    package_name: &str,
+    definitions: &[Definition],
+    typedefs: &Typedefs,
+    name: &str,
+) -> Result<StructuredType> {
+    let (annotations, members) = definitions
+        .iter()
+        .find_map(|definition| match &definition.kind {
+            DefinitionKind::Struct(struct_name, members) if struct_name == name => {
+                Some((&definition.annotations, members))
+            }
+            _ => None,
+        })
+        .ok_or(format!("No structure \"{name}\" found"))?;
+
+    let mut fields = Vec::new();
+    let constants_module_name = format!("{name}{CONSTANTS_MODULE_SUFFIX}");
+    for definition in definitions.iter() {
+        match &definition.kind {
+            DefinitionKind::Module(module_name, constants) if *module_name == constants_module_name => {
+                for constant in constants.iter() {
+                    if let DefinitionKind::Const(type_spec, name, literal) = &constant.kind {
+                        fields.push(convert_const(
+                            package_name,
+                            typedefs,
+                            &constant.annotations,
+                            type_spec,
+                            name,
+                            literal,
+                        )?);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for member in members
+        .iter()
+        .filter(|member| member.name != EMPTY_STRUCTURE_MEMBER_NAME)
+    {
+        fields.push(convert_member(package_name, typedefs, member)?);
+    }
+    Ok(StructuredType::new(
+        name,
+        &convert_to_comment(annotations),
+        fields,
+        &None,
+    ))
+}
+
+fn convert_const(
+    package_name: &str,
+    typedefs: &Typedefs,
+    annotations: &[Annotation],
+    type_spec: &TypeSpec,
+    name: &str,
+    literal: &str,
+) -> Result<Field> {
+    let (base_type, constraint) = convert_type_spec(package_name, typedefs, type_spec)?;
+    let initial_value = convert_literal(&base_type, &constraint, literal)?;
+    Ok(Field::new(
+        &base_type,
+        &constraint,
+        name,
+        &FieldType::Constant(initial_value),
+        &convert_to_comment(annotations),
+        &convert_to_annotations(annotations),
+    ))
+}
+
+fn convert_member(package_name: &str, typedefs: &Typedefs, member: &Member) -> Result<Field> {
+    let resolved = convert_type_spec(package_name, typedefs, &member.type_spec)?;
+    let (base_type, constraint) = apply_array_size(&member.name, resolved, member.array_size)?;
+    let initial_value = find_annotation_parameter(&member.annotations, "default", "value")
+        .map(|literal| convert_literal(&base_type, &constraint, &literal))
+        .transpose()?;
+    Ok(Field::new(
+        &base_type,
+        &constraint,
+        &member.name,
+        &FieldType::Variable(initial_value),
+        &convert_to_comment(&member.annotations),
+        &convert_to_annotations(&member.annotations),
+    ))
+}
+
+fn apply_array_size(
+    name: &str,
+    (base_type, constraint): (BaseType, Option<Constraint>),
+    array_size: Option<usize>,
+) -> Result<(BaseType, Option<Constraint>)> {
+    match (constraint, array_size) {
+        (None, Some(size)) => Ok((base_type, Some(Constraint::StaticArray(size)))),
+        (constraint, None) => Ok((base_type, constraint)),
+        (Some(_), Some(_)) => Err(format!("Nested arrays are not supported: \"{name}\"").into()),
+    }
+}
+
+fn convert_type_spec(
+    package_name: &str,
+    typedefs: &Typedefs,
+    type_spec: &TypeSpec,
+) -> Result<(BaseType, Option<Constraint>)> {
+    Ok(match type_spec {
+        TypeSpec::String(bound) => (BaseType::String(*bound), None),
+        TypeSpec::Wstring(bound) => (BaseType::Wstring(*bound), None),
+        TypeSpec::Sequence(element_type_spec, bound) => {
+            match convert_type_spec(package_name, typedefs, element_type_spec)? {
+                (base_type, None) => (
+                    base_type,
+                    Some(match bound {
+                        Some(bound) => Constraint::BoundedDynamicArray(*bound),
+                        None => Constraint::UnboundedDynamicArray,
+                    }),
+                ),
+                _ => return Err("Nested sequences are not supported".into()),
+            }
+        }
+        TypeSpec::Named(name) => match typedefs.get(name) {
+            Some(resolved) => resolved.clone(),
+            None => (convert_type_name(package_name, name)?, None),
+        },
+    })
+}
+
+fn convert_type_name(package_name: &str, name: &str) -> Result<BaseType> {
+    Ok(match name {
+        "boolean" => BaseType::Bool,
+        "octet" => BaseType::Byte,
+        "char" => BaseType::Char,
+        "float" => BaseType::Float32,
+        "double" => BaseType::Float64,
+        "int8" => BaseType::Int8,
+        "uint8" => BaseType::Uint8,
+        "int16" | "short" => BaseType::Int16,
+        "uint16" | "unsigned short" => BaseType::Uint16,
+        "int32" | "long" => BaseType::Int32,
+        "uint32" | "unsigned long" => BaseType::Uint32,
+        "int64" | "long long" => BaseType::Int64,
+        "uint64" | "unsigned long long" => BaseType::Uint64,
+        "wchar" | "long double" | "any" => {
+            return Err(format!("Unsupported IDL type \"{name}\"").into())
+        }
+        _ => BaseType::Custom(convert_reference(package_name, name)?),
+    })
+}
+
+fn convert_reference(package_name: &str, scoped_name: &str) -> Result<Reference> {
+    let parts: Vec<&str> = scoped_name.split("::").collect();
+    match parts.as_slice() {
+        [file] => Ok(Reference::Relative {
+            file: file.to_string(),
+        }),
+        [package, msg::INTERFACE_KIND, file] if *package == package_name => Ok(Reference::Relative {
+            file: file.to_string(),
+        }),
+        [package, msg::INTERFACE_KIND, file] => Ok(Reference::Absolute {
+            package: package.to_string(),
+            file: file.to_string(),
+        }),
+        _ => Err(format!("Unsupported type reference \"{scoped_name}\"").into()),
+    }
+}
+
+// IDL literals are translated to MSG syntax, so that the msg_reader's value parsing applies
+fn convert_literal(
+    base_type: &BaseType,
+    constraint: &Option<Constraint>,
+    literal: &str,
+) -> Result<InitialValue> {
+    let spelling = match (base_type, constraint) {
+        (_, Some(_)) => {
+            // rosidl stores array defaults as string of a Python tuple, e.g. "(1, 2)"
+            let tuple = parse_string_literal(literal).unwrap_or(literal.to_string());
+            let tuple = tuple.trim();
+            let elements = tuple
+                .strip_prefix(['(', '['])
+                .and_then(|tuple| tuple.strip_suffix([')', ']']))
+                .unwrap_or(tuple);
+            let elements: Vec<String> = split_unquoted(elements, ',')
+                .into_iter()
+                .map(str::trim)
+                .filter(|element| !element.is_empty())
+                .map(convert_scalar_literal)
+                .collect();
+            format!("[{}]", elements.join(","))
+        }
+        (BaseType::String(_), None) | (BaseType::Wstring(_), None) => {
+            let value = parse_string_literal(literal)
+                .ok_or(format!("Invalid string literal {literal}"))?;
+            format!("\"{}\"", encode_ros_string(&value, '"'))
+        }
+        (_, None) => convert_scalar_literal(literal),
+    };
+    msg_reader::parse_field_value(base_type, constraint, &spelling)
+        .ok_or_else(|| format!("Invalid value {literal} for type {base_type:?}").into())
+}
+
+fn convert_scalar_literal(literal: &str) -> String {
+    match literal {
+        "TRUE" | "True" => "true".to_string(),
+        "FALSE" | "False" => "false".to_string(),
+        // IDL octal literals start with a zero, e.g. "017"
+        _ if literal.len() > 1
+            && literal.starts_with('0')
+            && literal.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            format!("0o{}", &literal[1..])
+        }
+        // Character literals of char fields are numeric in MSG, e.g. 'A'
+        _ if literal.len() == 3 && literal.starts_with('\'') && literal.ends_with('\'') => literal
+            .chars()
+            .nth(1)
+            .map(|char| (char as u32).to_string())
+            .unwrap_or_default(),
+        _ => literal.to_string(),
+    }
+}
+
+fn convert_to_comment(annotations: &[Annotation]) -> Option<String> {
+    let comments: Vec<String> = annotations
+        .iter()
+        .filter(|annotation| annotation.name == "verbatim")
+        .filter_map(|annotation| {
+            let parameters = split_parameters(annotation.parameters.as_deref()?);
+            let language = find_parameter(&parameters, "language")
+                .and_then(|language| parse_string_literal(&language))?;
+            let text = find_parameter(&parameters, "text")
+                .and_then(|text| parse_string_literal(&text))?;
+            (language == "comment").then_some(text)
+        })
+        .collect();
+    match comments.is_empty() {
+        false => Some(comments.join("\n")),
+        true => None,
+    }
+}
+
+fn convert_to_annotations(annotations: &[Annotation]) -> Vec<Annotation> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.name != "default" && annotation.name != "verbatim")
+        .cloned()
+        .collect()
+}
+
+fn find_annotation_parameter(
+    annotations: &[Annotation],
+    annotation_name: &str,
+    parameter_name: &str,
+) -> Option<String> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.name == annotation_name)
+        .find_map(|annotation| {
+            find_parameter(
+                &split_parameters(annotation.parameters.as_deref()?),
+                parameter_name,
+            )
+        })
+}
+
+fn find_parameter(parameters: &[(String, String)], name: &str) -> Option<String> {
+    parameters
+        .iter()
+        .find(|(parameter_name, _)| parameter_name == name)
+        .map(|(_, value)| value.clone())
+}
+
+// "language=\"comment\", text=\"...\"" to its name/value pairs.
+// A single parameter without a name, e.g. @unit("m"), is called "value".
+fn split_parameters(parameters: &str) -> Vec<(String, String)> {
+    split_unquoted(parameters, ',')
+        .into_iter()
+        .map(|parameter| match find_unquoted(parameter, '=') {
+            Some(position) => (
+                parameter[..position].trim().to_string(),
+                parameter[position + 1..].trim().to_string(),
+            ),
+            None => ("value".to_string(), parameter.trim().to_string()),
+        })
+        .collect()
+}
+
+fn split_unquoted(text: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = text;
+    while let Some(position) = find_unquoted(rest, delimiter) {
+        parts.push(&rest[..position]);
+        rest = &rest[position + 1..];
+    }
+    parts.push(rest);
+    parts
+}
+
+// Position of the delimiter outside of string literals and parentheses
+fn find_unquoted(text: &str, delimiter: char) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    for (position, c) in text.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(closing) if c == closing => quote = None,
+            Some(_) => {}
+            None if c == delimiter && depth == 0 => return Some(position),
+            None => match c {
+                '"' | '\'' => quote = Some(c),
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                _ => {}
+            },
+        }
+    }
+    None
+}
+
+// Adjacent string literals are concatenated, e.g. "a" "\n" "b"
+fn parse_string_literal(text: &str) -> Option<String> {
+    all_consuming(terminated(
+        many1(token(preceded(
+            opt(char('L')),
+            delimited(char('"'), parse_string_content, char('"')),
+        ))),
+        skip_trivia,
+    ))(text)
+    .ok()
+    .map(|(_, parts)| parts.concat())
+}
+
+fn parse_string_content(input: &str) -> IResult<&str, String> {
+    let mut chars = input.char_indices();
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '"' => {
+                return map_res(take(position), decode_ros_string)(input);
+            }
+            '\\' if chars.next().is_none() => break,
+            _ => {}
+        }
+    }
+    Err(nom::Err::Error(nom::error::Error::new(
+        input,
+        nom::error::ErrorKind::Char,
+    )))
+}
+
+fn parse_specification(input: &str) -> IResult<&str, Vec<Definition>> {
+    all_consuming(terminated(many0(parse_definition), skip_trivia))(input)
+}
+
+fn parse_definition(input: &str) -> IResult<&str, Definition> {
+    map(
+        pair(
+            many0(parse_annotation),
+            alt((parse_module, parse_struct, parse_const, parse_typedef)),
+        ),
+        |(annotations, kind)| Definition { annotations, kind },
+    )(input)
+}
+
+fn parse_module(input: &str) -> IResult<&str, DefinitionKind> {
+    map(
+        tuple((
+            keyword("module"),
+            parse_identifier,
+            symbol("{"),
+            many0(parse_definition),
+            symbol("}"),
+            symbol(";"),
+        )),
+        |(_, name, _, definitions, _, _)| DefinitionKind::Module(name.to_string(), definitions),
+    )(input)
+}
+
+fn parse_struct(input: &str) -> IResult<&str, DefinitionKind> {
+    map(
+        tuple((
+            keyword("struct"),
+            parse_identifier,
+            symbol("{"),
+            many0(parse_member),
+            symbol("}"),
+            symbol(";"),
+        )),
+        |(_, name, _, members, _, _)| DefinitionKind::Struct(name.to_string(), members),
+    )(input)
+}
+
+fn parse_const(input: &str) -> IResult<&str, DefinitionKind> {
+    map(
+        tuple((
+            keyword("const"),
+            parse_type_spec,
+            parse_identifier,
+            symbol("="),
+            token(take_until_unquoted(';')),
+            symbol(";"),
+        )),
+        |(_, type_spec, name, _, literal, _)| {
+            DefinitionKind::Const(type_spec, name.to_string(), literal.trim().to_string())
+        },
+    )(input)
+}
+
+fn parse_typedef(input: &str) -> IResult<&str, DefinitionKind> {
+    map(
+        tuple((
+            keyword("typedef"),
+            parse_type_spec,
+            parse_identifier,
+            opt(parse_array_size),
+            symbol(";"),
+        )),
+        |(_, type_spec, name, array_size, _)| {
+            DefinitionKind::Typedef(type_spec, name.to_string(), array_size)
+        },
+    )(input)
+}
+
+fn parse_member(input: &str) -> IResult<&str, Member> {
+    map(
+        tuple((
+            many0(parse_annotation),
+            parse_type_spec,
+            parse_identifier,
+            opt(parse_array_size),
+            symbol(";"),
+        )),
+        |(annotations, type_spec, name, array_size, _)| Member {
+            annotations,
+            type_spec,
+            name: name.to_string(),
+            array_size,
+        },
+    )(input)
+}
+
+fn parse_annotation(input: &str) -> IResult<&str, Annotation> {
+    map(
+        pair(
+            preceded(token(char('@')), parse_identifier),
+            opt(delimited(symbol("("), take_until_unquoted(')'), char(')'))),
+        ),
+        |(name, parameters)| Annotation {
+            name: name.to_string(),
+            parameters: parameters.map(|parameters| parameters.trim().to_string()),
+        },
+    )(input)
+}
+
+fn parse_type_spec(input: &str) -> IResult<&str, TypeSpec> {
+    alt((
+        map(
+            tuple((
+                keyword("sequence"),
+                symbol("<"),
+                parse_type_spec,
+                opt(preceded(symbol(","), parse_size)),
+                symbol(">"),
+            )),
+            |(_, _, type_spec, bound, _)| TypeSpec::Sequence(Box::new(type_spec), bound),
+        ),
+        map(
+            preceded(keyword("string"), opt(delimited(symbol("<"), parse_size, symbol(">")))),
+            TypeSpec::String,
+        ),
+        map(
+            preceded(keyword("wstring"), opt(delimited(symbol("<"), parse_size, symbol(">")))),
+            TypeSpec::Wstring,
+        ),
+        map(parse_multi_word_type, |name| TypeSpec::Named(name.to_string())),
+        map(parse_scoped_name, TypeSpec::Named),
+    ))(input)
+}
+
+fn parse_multi_word_type(input: &str) -> IResult<&str, &str> {
+    alt((
+        value(
+            "unsigned long long",
+            tuple((keyword("unsigned"), keyword("long"), keyword("long"))),
+        ),
+        value("unsigned long", pair(keyword("unsigned"), keyword("long"))),
+        value("unsigned short", pair(keyword("unsigned"), keyword("short"))),
+        value("long long", pair(keyword("long"), keyword("long"))),
+        value("long double", pair(keyword("long"), keyword("double"))),
+    ))(input)
+}
+
+fn parse_array_size(input: &str) -> IResult<&str, usize> {
+    delimited(symbol("["), parse_size, symbol("]"))(input)
+}
+
+fn parse_size(input: &str) -> IResult<&str, usize> {
+    map_res(token(digit1), |digits: &str| digits.parse::<usize>())(input)
+}
+
+fn parse_scoped_name(input: &str) -> IResult<&str, String> {
+    map(separated_list1(symbol("::"), parse_identifier), |parts| {
+        parts.join("::")
+    })(input)
+}
+
+fn parse_identifier(input: &str) -> IResult<&str, &str> {
+    token(recognize(pair(
+        alt((alpha1, tag("_"))),
+        many0(alt((alphanumeric1, tag("_")))),
+    )))(input)
+}
+
+fn keyword<'a>(keyword: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    token(terminated(
+        tag(keyword),
+        not(peek(satisfy(|c: char| c.is_alphanumeric() || c == '_'))),
+    ))
+}
+
+fn symbol<'a>(symbol: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    token(tag(symbol))
+}
+
+fn token<'a, O>(
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, O> {
+    preceded(skip_trivia, parser)
+}
+
+fn take_until_unquoted<'a>(delimiter: char) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    move |input: &'a str| match find_unquoted(input, delimiter) {
+        Some(position) => Ok((&input[position..], &input[..position])),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::TakeUntil,
+        ))),
+    }
+}
+
+// Whitespace, comments and preprocessor directives like "#include"
+fn skip_trivia(input: &str) -> IResult<&str, ()> {
+    value(
+        (),
+        many0(alt((
+            multispace1,
+            recognize(pair(tag("//"), not_line_ending)),
+            recognize(tuple((tag("/*"), take_until("*/"), tag("*/")))),
+            recognize(pair(tag("#"), not_line_ending)),
+        ))),
+    )(input)
+}
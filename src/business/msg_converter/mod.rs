@@ -1,3 +1,11 @@
+pub mod msg_lexer;
 pub mod msg_reader;
+pub mod srv_reader;
+pub mod action_reader;
+pub mod idl_reader;
+pub mod package_reader;
+pub mod reference_resolver;
+pub mod bundled_interfaces;
 pub mod dtp_writer;
+#[allow(clippy::module_inception)]
 pub mod msg_converter;
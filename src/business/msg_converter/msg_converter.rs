@@ -1,73 +1,822 @@
+use crate::business::conversion_options::ConversionOptions;
+use crate::business::dynamic_array::DynamicArrayStrategy;
 use crate::business::error::Result;
-use crate::core::{dtp, msg};
+use crate::business::identifier::{self, Identifier};
+use crate::business::mapping_profile::{self, FieldMapping, MappingProfile};
+use crate::business::msg_converter::{bundled_interfaces, msg_reader};
+use crate::business::type_name;
+use crate::core::{action, dtp, msg, package, srv};
 use nom::branch::alt;
-use nom::bytes::complete::tag;
-use nom::character::complete::digit1;
-use nom::combinator::{map_res, opt, recognize};
-use nom::sequence::{delimited, tuple};
+use nom::bytes::complete::{tag, take_while1};
+use nom::character::complete::{digit1, space0};
+use nom::combinator::{map, map_res, opt, recognize};
+use nom::sequence::{delimited, preceded, separated_pair, tuple};
 use nom::{Finish, IResult};
 
-const ELEMENT_COUNTER_SUFFIX: &'static str = "_element_counter";
+const ANNOTATION_ATTRIBUTE_PREFIX: &str = "ROS2_Annotation_";
+const IDENTIFICATION_STANDARD: &str = "1131-3";
+const IDENTIFICATION_APPLICATION_DOMAIN: &str = "ROS 2";
+const ACTION_MSGS_PACKAGE_NAME: &str = "action_msgs";
+const GOAL_TYPE_NAMES: [&str; 2] = ["GoalInfo", "GoalStatus"];
 
-pub fn convert(package_name: &str, structured_type: &msg::StructuredType) -> Result<dtp::DataType> {
-    let name = convert_structured_type_name(package_name, structured_type.name());
-    let mut structured_type_children = Vec::new();
+pub fn convert(
+    package_name: &str,
+    options: &ConversionOptions,
+    package: &Option<package::Package>,
+    structured_type: &msg::StructuredType,
+) -> Result<dtp::DataType> {
+    convert_structured_type(
+        package_name,
+        options,
+        package,
+        msg::INTERFACE_KIND,
+        structured_type,
+    )
+}
+
+pub fn convert_service(
+    package_name: &str,
+    options: &ConversionOptions,
+    package: &Option<package::Package>,
+    service: &srv::Service,
+) -> Result<Vec<dtp::DataType>> {
+    Ok(vec![
+        convert_structured_type(
+            package_name,
+            options,
+            package,
+            srv::INTERFACE_KIND,
+            service.request(),
+        )?,
+        convert_structured_type(
+            package_name,
+            options,
+            package,
+            srv::INTERFACE_KIND,
+            service.response(),
+        )?,
+    ])
+}
+
+pub fn convert_action(
+    package_name: &str,
+    options: &ConversionOptions,
+    package: &Option<package::Package>,
+    action: &action::Action,
+    with_implicit_types: bool,
+) -> Result<Vec<dtp::DataType>> {
+    let mut data_types = vec![
+        convert_structured_type(
+            package_name,
+            options,
+            package,
+            action::INTERFACE_KIND,
+            action.goal(),
+        )?,
+        convert_structured_type(
+            package_name,
+            options,
+            package,
+            action::INTERFACE_KIND,
+            action.result(),
+        )?,
+        convert_structured_type(
+            package_name,
+            options,
+            package,
+            action::INTERFACE_KIND,
+            action.feedback(),
+        )?,
+    ];
+    if with_implicit_types {
+        data_types.append(&mut create_implicit_action_types(
+            package_name,
+            options,
+            package,
+            action,
+        )?);
+    }
+    Ok(data_types)
+}
+
+fn convert_structured_type(
+    package_name: &str,
+    options: &ConversionOptions,
+    package: &Option<package::Package>,
+    interface_kind: &str,
+    structured_type: &msg::StructuredType,
+) -> Result<dtp::DataType> {
+    let structured_type = &canonicalize_annotations(options.mapping_profile(), structured_type);
+    // "@IEC61499_TypeName(Lager_Platz)" restores the name of a type, which was renamed for ROS 2
+    let name = match structured_type
+        .comment()
+        .and_then(|comment| type_name::decode_type_name_annotation(comment))
+    {
+        Some(iec_name) => iec_name.to_string(),
+        None => convert_structured_type_name(
+            package_name,
+            options,
+            interface_kind,
+            structured_type.name(),
+        ),
+    };
+    let comment = convert_to_type_comment(structured_type.comment());
+    let attributes =
+        convert_to_data_type_attributes(package_name, options, interface_kind, structured_type);
+    let data_type_kind = if is_enumeration(structured_type) {
+        dtp::DataTypeKind::EnumeratedType(convert_to_enumerated_type(structured_type, &comment)?)
+    } else if is_directly_derived_type(structured_type) {
+        dtp::DataTypeKind::DirectlyDerivedType(convert_to_directly_derived_type(
+            package_name,
+            options,
+            structured_type,
+            &comment,
+        )?)
+    } else if is_array_type(structured_type) {
+        dtp::DataTypeKind::ArrayType(convert_to_array_type(
+            package_name,
+            options,
+            structured_type,
+            &comment,
+        )?)
+    } else if is_subrange_type(structured_type) {
+        dtp::DataTypeKind::SubrangeType(convert_to_subrange_type(
+            package_name,
+            options,
+            structured_type,
+            &comment,
+        )?)
+    } else {
+        let mut structured_type_children = Vec::new();
+        let var_names = convert_to_var_names(structured_type.fields())?;
+        let ros_names = identifier::to_ros_names(&convert_to_identifiers(
+            structured_type.fields(),
+            &var_names,
+        ));
+        for ((field, var_name), ros_name) in structured_type
+            .fields()
+            .iter()
+            .zip(var_names)
+            .zip(ros_names)
+        {
+            // The reverse conversion would not restore the name without the attribute
+            let original_name = (ros_name != field.name()).then_some(field.name());
+            let children = &mut convert_field(
+                package_name,
+                options,
+                &options.mapping_profile().field_mapping(
+                    package_name,
+                    structured_type.name(),
+                    field.name(),
+                ),
+                field,
+                &var_name,
+                original_name,
+            )?;
+            structured_type_children.append(children)
+        }
+        create_structured_type_kind(&comment, &structured_type_children)
+    };
+    Ok(create_data_type(
+        &name,
+        &comment,
+        package,
+        &data_type_kind,
+        &attributes,
+    ))
+}
+
+// The own names of the annotations are replaced by the ones of the converter
+fn canonicalize_annotations(
+    mapping_profile: &MappingProfile,
+    structured_type: &msg::StructuredType,
+) -> msg::StructuredType {
+    let annotations = mapping_profile.annotations();
+    let canonicalize =
+        |comment: Option<&String>| comment.map(|comment| annotations.canonicalize(comment));
+    let fields = structured_type
+        .fields()
+        .iter()
+        .map(|field| {
+            msg::Field::new(
+                field.base_type(),
+                &field.constraint().cloned(),
+                field.name(),
+                field.field_type(),
+                &canonicalize(field.comment()),
+                field.annotations(),
+            )
+        })
+        .collect();
+    msg::StructuredType::new(
+        structured_type.name(),
+        &canonicalize(structured_type.comment()),
+        fields,
+        &structured_type.syntax_tree().cloned(),
+    )
+}
+
+fn create_structured_type_kind(
+    comment: &Option<String>,
+    structured_type_children: &[dtp::StructuredTypeChild],
+) -> dtp::DataTypeKind {
+    dtp::DataTypeKind::StructuredType(dtp::StructuredType::new(
+        comment,
+        structured_type_children,
+        &dtp::ForeignXml::default(),
+    ))
+}
+
+fn create_data_type(
+    name: &str,
+    comment: &Option<String>,
+    package: &Option<package::Package>,
+    data_type_kind: &dtp::DataTypeKind,
+    attributes: &[dtp::Attribute],
+) -> dtp::DataType {
+    let identification = package.as_ref().map(convert_to_identification);
+    let version_info: Vec<dtp::VersionInfo> =
+        package.iter().map(convert_to_version_info).collect();
+    // Package-qualified names of 4diac IDE 3 keep their package in the compiler info
+    let (package_name, name) = type_name::split_qualified_name(name);
+    let compiler_info = package_name.map(|package_name| dtp::CompilerInfo {
+        package_name: Some(package_name.to_string()),
+        header: None,
+        classdef: None,
+        compilers: Vec::new(),
+    });
+    dtp::DataType::new(
+        name,
+        comment,
+        &identification,
+        &version_info,
+        &compiler_info,
+        data_type_kind,
+        attributes,
+        &dtp::ForeignXml::default(),
+    )
+}
+
+// The generated types refer to the ROS 2 package, which they were created from
+fn convert_to_identification(package: &package::Package) -> dtp::Identification {
+    dtp::Identification {
+        standard: Some(IDENTIFICATION_STANDARD.to_string()),
+        application_domain: Some(IDENTIFICATION_APPLICATION_DOMAIN.to_string()),
+        description: Some(package.description().to_string()).filter(|value| !value.is_empty()),
+        ..Default::default()
+    }
+}
+
+fn convert_to_version_info(package: &package::Package) -> dtp::VersionInfo {
+    let author = package
+        .maintainers()
+        .iter()
+        .map(|maintainer| match &maintainer.email {
+            Some(email) => format!("{} <{email}>", maintainer.name),
+            None => maintainer.name.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let remarks = Some(package.licenses().join(", "))
+        .filter(|licenses| !licenses.is_empty())
+        .map(|licenses| format!("License: {licenses}"));
+    dtp::VersionInfo {
+        organization: None,
+        version: package.version().to_string(),
+        author,
+        date: package.date().to_string(),
+        remarks,
+    }
+}
+
+fn is_enumeration(structured_type: &msg::StructuredType) -> bool {
+    structured_type.fields().iter().any(is_enumeration_value)
+}
+
+fn is_enumeration_value(field: &msg::Field) -> bool {
+    field
+        .comment()
+        .is_some_and(|comment| comment.contains("@IEC61499_EnumeratedType"))
+}
+
+// The constants are the enumerated values in the order of their numbers,
+// which have to count up from 0. The annotated field selects the initial value.
+fn convert_to_enumerated_type(
+    structured_type: &msg::StructuredType,
+    comment: &Option<String>,
+) -> Result<dtp::EnumeratedType> {
+    let mut values = Vec::new();
+    let mut initial_value = None;
+    // "@IEC61499_Name(Idle)" restores the name of a value, which was renamed for ROS 2
+    let constants = structured_type
+        .fields()
+        .iter()
+        .filter(|field| is_constant(field))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut value_names = convert_to_var_names(&constants)?.into_iter();
     for field in structured_type.fields().iter() {
-        let children = &mut convert_field(package_name, field)?;
-        structured_type_children.append(children)
+        match field.field_type() {
+            msg::FieldType::Constant(value) => {
+                if get_integer_value(value) != Some(values.len() as i64) {
+                    return Err(format!(
+                        "Enumerated value \"{}\" of \"{}\" has to be {}",
+                        field.name(),
+                        structured_type.name(),
+                        values.len()
+                    )
+                    .into());
+                }
+                values.push(dtp::EnumeratedValue::new(
+                    &value_names.next().unwrap_or_default(),
+                    &convert_to_var_comment(field)?,
+                ));
+            }
+            msg::FieldType::Variable(value) if is_enumeration_value(field) => {
+                initial_value = value.as_ref().map(get_integer_value)
+            }
+            msg::FieldType::Variable(_) => {
+                return Err(format!(
+                    "Enumeration \"{}\" must not contain the field \"{}\"",
+                    structured_type.name(),
+                    field.name()
+                )
+                .into())
+            }
+        }
     }
-    let structured_type = dtp::StructuredType::new(&None, &structured_type_children);
-    let data_type_kind = dtp::DataTypeKind::StructuredType(structured_type);
-    Ok(dtp::DataType::new(&name, &None, &data_type_kind))
+    let initial_value = initial_value
+        .map(|initial_value| {
+            initial_value
+                .and_then(|index| values.get(usize::try_from(index).ok()?))
+                .map(|value: &dtp::EnumeratedValue| value.name().to_string())
+                .ok_or(format!(
+                    "Invalid initial value of enumeration \"{}\"",
+                    structured_type.name()
+                ))
+        })
+        .transpose()?;
+    Ok(dtp::EnumeratedType::new(comment, &initial_value, &values))
+}
+
+fn is_directly_derived_type(structured_type: &msg::StructuredType) -> bool {
+    structured_type
+        .fields()
+        .iter()
+        .any(|field| find_annotation(field, "@IEC61499_DirectlyDerivedType").is_some())
+}
+
+fn convert_to_directly_derived_type(
+    package_name: &str,
+    options: &ConversionOptions,
+    structured_type: &msg::StructuredType,
+    comment: &Option<String>,
+) -> Result<dtp::DirectlyDerivedType> {
+    let field = get_wrapped_field(structured_type)?;
+    if field.constraint().is_some() {
+        return Err(format!(
+            "Directly derived type \"{}\" must not be an array",
+            structured_type.name()
+        )
+        .into());
+    }
+    let field_mapping =
+        options
+            .mapping_profile()
+            .field_mapping(package_name, structured_type.name(), field.name());
+    let base_type = convert_to_var_base_type(package_name, options, &field_mapping, field)?;
+    Ok(dtp::DirectlyDerivedType::new(
+        &base_type,
+        &convert_to_var_optional_initial_value(field, &base_type)?,
+        comment,
+    ))
+}
+
+fn is_array_type(structured_type: &msg::StructuredType) -> bool {
+    structured_type
+        .fields()
+        .iter()
+        .any(|field| find_annotation(field, "@IEC61499_ArrayType").is_some())
 }
 
-fn convert_structured_type_name(package_name: &str, structured_type_name: &str) -> String {
-    let package_name = package_name
-        .replace("_", "")
-        .replace(" ", "")
-        .replace("-", "");
-    format!("ROS2_{package_name}_msg_{structured_type_name}")
+fn convert_to_array_type(
+    package_name: &str,
+    options: &ConversionOptions,
+    structured_type: &msg::StructuredType,
+    comment: &Option<String>,
+) -> Result<dtp::ArrayType> {
+    let field = get_wrapped_field(structured_type)?;
+    let subrange = match convert_to_var_optional_array_size(field)? {
+        Some(dtp::ArraySize::Static(dtp::Capacity::InPlace(capacity))) if field_is_static_array(field) => {
+            dtp::Subrange {
+                lower_limit: 0,
+                upper_limit: capacity as i64 - 1,
+            }
+        }
+        Some(dtp::ArraySize::Static(dtp::Capacity::Shifted(start, end))) => dtp::Subrange {
+            lower_limit: start,
+            upper_limit: end,
+        },
+        _ => {
+            return Err(format!(
+                "Array type \"{}\" must be a static array",
+                structured_type.name()
+            )
+            .into())
+        }
+    };
+    let field_mapping =
+        options
+            .mapping_profile()
+            .field_mapping(package_name, structured_type.name(), field.name());
+    let base_type = convert_to_var_base_type(package_name, options, &field_mapping, field)?;
+    Ok(dtp::ArrayType::new(
+        &base_type,
+        &[subrange],
+        &convert_to_var_optional_initial_value(field, &base_type)?,
+        comment,
+    ))
+}
+
+fn field_is_static_array(field: &msg::Field) -> bool {
+    matches!(field.constraint(), Some(msg::Constraint::StaticArray(_)))
 }
 
-fn convert_field(package_name: &str, field: &msg::Field) -> Result<Vec<dtp::StructuredTypeChild>> {
+fn is_subrange_type(structured_type: &msg::StructuredType) -> bool {
+    structured_type
+        .fields()
+        .iter()
+        .any(|field| find_annotation(field, "@IEC61499_SubrangeType").is_some())
+}
+
+// A subrange type is a message with nothing but the annotated field
+fn convert_to_subrange_type(
+    package_name: &str,
+    options: &ConversionOptions,
+    structured_type: &msg::StructuredType,
+    comment: &Option<String>,
+) -> Result<dtp::SubrangeType> {
+    let field = get_wrapped_field(structured_type)?;
+    let field_mapping =
+        options
+            .mapping_profile()
+            .field_mapping(package_name, structured_type.name(), field.name());
+    let base_type = convert_to_subrange_base_type(package_name, options, &field_mapping, field)?;
+    let subrange = get_subrange(field, "@IEC61499_SubrangeType")?.ok_or(format!(
+        "No limits given for subrange \"{}\"",
+        structured_type.name()
+    ))?;
+    let initial_value = convert_to_var_optional_initial_value(field, &base_type)?;
+    Ok(dtp::SubrangeType::new(
+        &base_type,
+        &initial_value,
+        comment,
+        &subrange,
+    ))
+}
+
+// Derived types and subrange types wrap their underlying type into a single field
+fn get_wrapped_field(structured_type: &msg::StructuredType) -> Result<&msg::Field> {
+    match structured_type.fields().as_slice() {
+        [field] if matches!(field.field_type(), msg::FieldType::Variable(_)) => Ok(field),
+        _ => Err(format!(
+            "\"{}\" must contain exactly one field, which is not a constant",
+            structured_type.name()
+        )
+        .into()),
+    }
+}
+
+fn get_integer_value(initial_value: &msg::InitialValue) -> Option<i64> {
+    match initial_value {
+        msg::InitialValue::Byte(literal)
+        | msg::InitialValue::Int8(literal)
+        | msg::InitialValue::Uint8(literal)
+        | msg::InitialValue::Int16(literal)
+        | msg::InitialValue::Uint16(literal)
+        | msg::InitialValue::Int32(literal)
+        | msg::InitialValue::Uint32(literal)
+        | msg::InitialValue::Int64(literal)
+        | msg::InitialValue::Uint64(literal) => Some(match literal {
+            msg::IntLiteral::SignedDecimalInt(i64) => *i64,
+            msg::IntLiteral::UnsignedDecimalInt(u64)
+            | msg::IntLiteral::BinaryInt(u64)
+            | msg::IntLiteral::OctalInt(u64)
+            | msg::IntLiteral::HexalInt(u64) => i64::try_from(*u64).ok()?,
+        }),
+        _ => None,
+    }
+}
+
+// On demand the original source is kept, so that the msg file can be
+// reproduced with its layout on the way back.
+fn convert_to_data_type_attributes(
+    package_name: &str,
+    options: &ConversionOptions,
+    interface_kind: &str,
+    structured_type: &msg::StructuredType,
+) -> Vec<dtp::Attribute> {
+    let mut attributes = vec![create_type_attribute(
+        package_name,
+        interface_kind,
+        structured_type.name(),
+    )];
+    if let Some(syntax_tree) = structured_type
+        .syntax_tree()
+        .filter(|_| options.keep_msg_source)
+    {
+        attributes.push(dtp::Attribute {
+            name: "ROS2_MsgSource".to_string(),
+            base_type: dtp::BaseType::STRING(None),
+            value: dtp::InitialValue::STRING(syntax_tree.text()),
+            comment: None,
+        });
+    }
+    attributes
+}
+
+// The name of the type cannot be recovered from the name of the data type in every case
+fn create_type_attribute(package_name: &str, interface_kind: &str, name: &str) -> dtp::Attribute {
+    dtp::Attribute {
+        name: "ROS2_Type".to_string(),
+        base_type: dtp::BaseType::STRING(None),
+        value: dtp::InitialValue::STRING(type_name::encode_full_name(
+            package_name,
+            interface_kind,
+            name,
+        )),
+        comment: None,
+    }
+}
+
+// The types rosidl generates implicitly for every action,
+// see https://design.ros2.org/articles/actions.html
+fn create_implicit_action_types(
+    package_name: &str,
+    options: &ConversionOptions,
+    package: &Option<package::Package>,
+    action: &action::Action,
+) -> Result<Vec<dtp::DataType>> {
+    let goal_id = create_reference_field("goal_id", "unique_identifier_msgs", "UUID");
+    let stamp = create_reference_field("stamp", "builtin_interfaces", "Time");
+    let accepted = msg::Field::new(
+        &msg::BaseType::Bool,
+        &None,
+        "accepted",
+        &msg::FieldType::Variable(None),
+        &None,
+        &[],
+    );
+    let status = msg::Field::new(
+        &msg::BaseType::Int8,
+        &None,
+        "status",
+        &msg::FieldType::Variable(None),
+        &None,
+        &[],
+    );
+    let mut data_types = vec![
+        create_implicit_action_type(
+            package_name,
+            options,
+            package,
+            action,
+            "_SendGoal_Request",
+            std::slice::from_ref(&goal_id),
+            Some(("goal", action::GOAL_SUFFIX)),
+        )?,
+        create_implicit_action_type(
+            package_name,
+            options,
+            package,
+            action,
+            "_SendGoal_Response",
+            &[accepted, stamp],
+            None,
+        )?,
+        create_implicit_action_type(
+            package_name,
+            options,
+            package,
+            action,
+            "_GetResult_Request",
+            std::slice::from_ref(&goal_id),
+            None,
+        )?,
+        create_implicit_action_type(
+            package_name,
+            options,
+            package,
+            action,
+            "_GetResult_Response",
+            &[status],
+            Some(("result", action::RESULT_SUFFIX)),
+        )?,
+        create_implicit_action_type(
+            package_name,
+            options,
+            package,
+            action,
+            "_FeedbackMessage",
+            &[goal_id],
+            Some(("feedback", action::FEEDBACK_SUFFIX)),
+        )?,
+    ];
+    data_types.append(&mut convert_goal_types(options)?);
+    Ok(data_types)
+}
+
+// The goals of every action are described by GoalInfo and GoalStatus of action_msgs,
+// whose definitions are bundled
+fn convert_goal_types(options: &ConversionOptions) -> Result<Vec<dtp::DataType>> {
+    let mut data_types = Vec::new();
+    for name in GOAL_TYPE_NAMES {
+        let path_to_file = format!(
+            "share/{ACTION_MSGS_PACKAGE_NAME}/{}/{name}.{}",
+            msg::INTERFACE_KIND,
+            msg::INTERFACE_KIND
+        );
+        let source = bundled_interfaces::find(&path_to_file)
+            .ok_or_else(|| format!("No bundled definition of \"{path_to_file}\""))?;
+        data_types.push(convert_structured_type(
+            ACTION_MSGS_PACKAGE_NAME,
+            options,
+            &None,
+            msg::INTERFACE_KIND,
+            &msg_reader::parse_structured_type(name, source)?,
+        )?);
+    }
+    Ok(data_types)
+}
+
+fn create_implicit_action_type(
+    package_name: &str,
+    options: &ConversionOptions,
+    package: &Option<package::Package>,
+    action: &action::Action,
+    suffix: &str,
+    fields: &[msg::Field],
+    nested_field: Option<(&str, &str)>,
+) -> Result<dtp::DataType> {
     let mut structured_type_children = Vec::new();
+    for field in fields.iter() {
+        structured_type_children.append(&mut convert_field(
+            package_name,
+            options,
+            &options.mapping_profile().field_mapping(
+                package_name,
+                &format!("{}{suffix}", action.name()),
+                field.name(),
+            ),
+            field,
+            field.name(),
+            None,
+        )?);
+    }
+    if let Some((field_name, nested_suffix)) = nested_field {
+        let nested_type_name = convert_structured_type_name(
+            package_name,
+            options,
+            action::INTERFACE_KIND,
+            &format!("{}{nested_suffix}", action.name()),
+        );
+        structured_type_children.push(dtp::StructuredTypeChild::VarDeclaration(
+            dtp::VarDeclaration::new(
+                field_name,
+                &dtp::BaseType::Custom(nested_type_name),
+                &None,
+                &None,
+                &None,
+                &[],
+                &dtp::ForeignXml::default(),
+            ),
+        ));
+    }
+    let structured_type_name = format!("{}{suffix}", action.name());
+    let name = convert_structured_type_name(
+        package_name,
+        options,
+        action::INTERFACE_KIND,
+        &structured_type_name,
+    );
+    Ok(create_data_type(
+        &name,
+        &None,
+        package,
+        &create_structured_type_kind(&None, &structured_type_children),
+        &[create_type_attribute(
+            package_name,
+            action::INTERFACE_KIND,
+            &structured_type_name,
+        )],
+    ))
+}
+
+fn create_reference_field(name: &str, package: &str, file: &str) -> msg::Field {
+    msg::Field::new(
+        &msg::BaseType::Custom(msg::Reference::Absolute {
+            package: package.to_string(),
+            file: file.to_string(),
+        }),
+        &None,
+        name,
+        &msg::FieldType::Variable(None),
+        &None,
+        &[],
+    )
+}
+
+fn convert_structured_type_name(
+    package_name: &str,
+    options: &ConversionOptions,
+    interface_kind: &str,
+    structured_type_name: &str,
+) -> String {
+    options
+        .type_name_template
+        .render(package_name, interface_kind, structured_type_name)
+}
+
+fn convert_field(
+    package_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    field: &msg::Field,
+    var_name: &str,
+    original_name: Option<&str>,
+) -> Result<Vec<dtp::StructuredTypeChild>> {
+    let mut structured_type_children = Vec::new();
+
+    if find_annotation(field, "@IEC61499_Subrange").is_some() {
+        structured_type_children.push(dtp::StructuredTypeChild::SubrangeVarDeclaration(
+            convert_to_subrange_var_declaration(
+                package_name,
+                options,
+                field_mapping,
+                field,
+                var_name,
+                original_name,
+            )?,
+        ));
+        return Ok(structured_type_children);
+    }
 
-    let var_name = convert_to_var_name(field)?;
-    let base_type = convert_to_var_base_type(package_name, field);
-    let array_size = convert_to_var_optional_array_size(field)?;
-    let initial_value = convert_to_var_optional_initial_value(field)?;
+    let dynamic_array = convert_to_dynamic_array(&options.dynamic_array_strategy, field, var_name)?;
+    let base_type = convert_to_var_base_type(package_name, options, field_mapping, field)?;
+    let array_size = match &dynamic_array {
+        Some(dynamic_array) => Some(dynamic_array.array_size.clone()),
+        None => convert_to_var_optional_array_size(field)?,
+    };
+    let initial_value = match &dynamic_array {
+        // The element counter tells how many of the elements are set
+        Some(DynamicArray {
+            array_size: dtp::ArraySize::Static(dtp::Capacity::InPlace(capacity)),
+            element_counter: Some(_),
+        }) => fill_initial_value(
+            field,
+            &base_type,
+            convert_to_var_optional_initial_value(field, &base_type)?,
+            *capacity,
+        ),
+        _ => convert_to_var_optional_initial_value(field, &base_type)?,
+    };
     let comment = convert_to_var_comment(field)?;
-    let attributes = convert_to_attributes(field)?;
+    let recorded_base_type = convert_to_recorded_base_type(field_mapping, field, &base_type)?;
+    let attributes = convert_to_attributes(field, original_name, recorded_base_type)?;
     structured_type_children.push(dtp::StructuredTypeChild::VarDeclaration(
         dtp::VarDeclaration::new(
-            &var_name,
+            var_name,
             &base_type,
             &array_size,
             &initial_value,
             &comment,
             &attributes,
+            &dtp::ForeignXml::default(),
         ),
     ));
 
-    if let Some(msg::Constraint::BoundedDynamicArray(_))
-    | Some(msg::Constraint::UnboundedDynamicArray) = field.constraint()
+    if let Some(DynamicArray {
+        element_counter: Some(element_counter_name),
+        ..
+    }) = &dynamic_array
     {
         let default_count = compute_element_counter_default_count(field);
         structured_type_children.push(dtp::StructuredTypeChild::VarDeclaration(
             dtp::VarDeclaration::new(
-                &format!("{var_name}{ELEMENT_COUNTER_SUFFIX}"),
+                element_counter_name,
                 &dtp::BaseType::ULINT,
                 &None,
                 &Some(dtp::InitialValue::ULINT(
                     dtp::IntLiteral::UnsignedDecimalInt(default_count),
                 )),
                 &None,
-                &vec![dtp::Attribute {
+                &[dtp::Attribute {
                     name: "ROS2_ElementCounter".to_string(),
                     base_type: dtp::BaseType::STRING(None),
-                    value: dtp::InitialValue::STRING(field.name().to_string()),
+                    value: dtp::InitialValue::STRING(var_name.to_string()),
                     comment: None,
                 }],
+                &dtp::ForeignXml::default(),
             ),
         ));
     };
@@ -75,6 +824,112 @@ fn convert_field(package_name: &str, field: &msg::Field) -> Result<Vec<dtp::Stru
     Ok(structured_type_children)
 }
 
+fn convert_to_subrange_var_declaration(
+    package_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    field: &msg::Field,
+    var_name: &str,
+    original_name: Option<&str>,
+) -> Result<dtp::SubrangeVarDeclaration> {
+    if field.constraint().is_some() {
+        return Err(format!("Subrange field \"{}\" must not be an array", field.name()).into());
+    }
+    if let msg::FieldType::Constant(_) = field.field_type() {
+        return Err(format!("Subrange field \"{}\" must not be a constant", field.name()).into());
+    }
+    let base_type = convert_to_subrange_base_type(package_name, options, field_mapping, field)?;
+    let recorded_base_type = convert_to_recorded_base_type(field_mapping, field, &base_type)?;
+    Ok(dtp::SubrangeVarDeclaration::new(
+        var_name,
+        &base_type,
+        &get_subrange(field, "@IEC61499_Subrange")?,
+        &convert_to_var_optional_initial_value(field, &base_type)?,
+        &convert_to_var_comment(field)?,
+        &convert_to_attributes(field, original_name, recorded_base_type)?,
+    ))
+}
+
+// Only integers can be limited to a subrange
+fn convert_to_subrange_base_type(
+    package_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    field: &msg::Field,
+) -> Result<dtp::BaseType> {
+    match convert_to_var_base_type(package_name, options, field_mapping, field)? {
+        base_type @ (dtp::BaseType::SINT
+        | dtp::BaseType::INT
+        | dtp::BaseType::DINT
+        | dtp::BaseType::LINT
+        | dtp::BaseType::USINT
+        | dtp::BaseType::UINT
+        | dtp::BaseType::UDINT
+        | dtp::BaseType::ULINT) => Ok(base_type),
+        _ => Err(format!("Subrange field \"{}\" must be an integer", field.name()).into()),
+    }
+}
+
+// Returns the annotation with its parameters and the rest of the comment.
+// The name must not be followed by further characters of an identifier,
+// e.g. "@IEC61499_Subrange" does not match "@IEC61499_SubrangeType".
+fn find_annotation<'a>(field: &'a msg::Field, name: &str) -> Option<&'a str> {
+    let comment = field.comment()?;
+    comment.match_indices(name).find_map(|(pos, _)| {
+        let rest = &comment[pos..];
+        let is_complete = !rest[name.len()..]
+            .starts_with(|char: char| char.is_alphanumeric() || char == '_');
+        is_complete.then_some(rest)
+    })
+}
+
+fn get_subrange(field: &msg::Field, annotation_name: &str) -> Result<Option<dtp::Subrange>> {
+    let Some(input) = find_annotation(field, annotation_name) else {
+        return Ok(None);
+    };
+    let subrange = preceded(tag(annotation_name), opt(parse_subrange))(input)
+        .map_err(|err| err.to_owned())
+        .finish()?
+        .1;
+    match subrange {
+        Some(subrange) if subrange.lower_limit > subrange.upper_limit => Err(format!(
+            "Invalid subrange {}..{} of \"{}\"",
+            subrange.lower_limit,
+            subrange.upper_limit,
+            field.name()
+        )
+        .into()),
+        _ => Ok(subrange),
+    }
+}
+
+fn parse_subrange(input: &str) -> IResult<&str, dtp::Subrange> {
+    map(
+        delimited(
+            tag("("),
+            separated_pair(parse_signed_int, tuple((tag(","), space0)), parse_signed_int),
+            tag(")"),
+        ),
+        |(lower_limit, upper_limit)| dtp::Subrange {
+            lower_limit,
+            upper_limit,
+        },
+    )(input)
+}
+
+// The annotation of the type name, which leads the comment, is no part of the data type
+fn convert_to_type_comment(comment: Option<&String>) -> Option<String> {
+    let comment = comment?;
+    let rest = type_name::decode_type_name_annotation(comment).and_then(|iec_name| {
+        comment.strip_prefix(&type_name::encode_type_name_annotation(iec_name))
+    });
+    let Some(rest) = rest else {
+        return Some(comment.clone());
+    };
+    let rest = rest.strip_prefix([',', '.']).unwrap_or(rest).trim();
+    (!rest.is_empty()).then(|| rest.to_string())
+}
+
 fn convert_to_var_comment(field: &msg::Field) -> Result<Option<String>> {
     if let Some(comment) = field.comment() {
         if comment.is_empty() {
@@ -101,6 +956,139 @@ fn convert_to_var_comment(field: &msg::Field) -> Result<Option<String>> {
     }
 }
 
+// A sequence as fixed array or array of variable size, optionally with an element counter
+#[derive(Debug, PartialEq)]
+pub(crate) struct DynamicArray {
+    array_size: dtp::ArraySize,
+    element_counter: Option<String>,
+}
+
+// The annotations of the field take precedence over the strategy
+pub(crate) fn convert_to_dynamic_array(
+    dynamic_array_strategy: &DynamicArrayStrategy,
+    field: &msg::Field,
+    var_name: &str,
+) -> Result<Option<DynamicArray>> {
+    let bound = match field.constraint() {
+        Some(msg::Constraint::UnboundedDynamicArray) => None,
+        Some(msg::Constraint::BoundedDynamicArray(bound)) => Some(*bound),
+        _ => return Ok(None),
+    };
+    let array_size = match (get_capacity(field)?, bound) {
+        (Some(dtp::ArraySize::Static(dtp::Capacity::InPlace(capacity))), Some(bound))
+            if capacity != bound =>
+        {
+            return Err(format!(
+                "The capacity of \"{}\" has to match its bound {bound}",
+                field.name()
+            )
+            .into())
+        }
+        (Some(array_size), _) => array_size,
+        (None, _) if dynamic_array_strategy.variable_size => dtp::ArraySize::Dynamic,
+        (None, bound) => dtp::ArraySize::Static(dtp::Capacity::InPlace(
+            bound.unwrap_or(dynamic_array_strategy.capacity),
+        )),
+    };
+    if let dtp::ArraySize::Static(dtp::Capacity::InPlace(capacity)) = array_size {
+        let default_count = compute_element_counter_default_count(field);
+        if default_count > capacity as u64 {
+            return Err(format!(
+                "The default value of \"{}\" has {default_count} elements, but its capacity is {capacity}",
+                field.name()
+            )
+            .into());
+        }
+    }
+    let is_counted_by_default =
+        dynamic_array_strategy.element_counter && matches!(array_size, dtp::ArraySize::Static(_));
+    let element_counter = match get_element_counter(field)? {
+        _ if find_annotation(field, "@IEC61499_NoElementCounter").is_some() => None,
+        Some(Some(element_counter_name)) => Some(element_counter_name.to_string()),
+        Some(None) => Some(dynamic_array_strategy.element_counter_name(var_name)),
+        None if is_counted_by_default => {
+            Some(dynamic_array_strategy.element_counter_name(var_name))
+        }
+        None => None,
+    };
+    Ok(Some(DynamicArray {
+        array_size,
+        element_counter,
+    }))
+}
+
+// "@IEC61499_Capacity(10)" or "@IEC61499_Capacity(*)" for an array of variable size
+fn get_capacity(field: &msg::Field) -> Result<Option<dtp::ArraySize>> {
+    let Some(input) = find_annotation(field, "@IEC61499_Capacity") else {
+        return Ok(None);
+    };
+    let array_size = parse_capacity(input)
+        .map_err(|err| err.to_owned())
+        .finish()?
+        .1;
+    Ok(Some(array_size))
+}
+
+fn parse_capacity(input: &str) -> IResult<&str, dtp::ArraySize> {
+    delimited(
+        tag("@IEC61499_Capacity("),
+        alt((
+            map(tag("*"), |_| dtp::ArraySize::Dynamic),
+            map_res(digit1, |digits: &str| {
+                digits
+                    .parse::<usize>()
+                    .map(|capacity| dtp::ArraySize::Static(dtp::Capacity::InPlace(capacity)))
+            }),
+        )),
+        tag(")"),
+    )(input)
+}
+
+// "@IEC61499_ElementCounter" or "@IEC61499_ElementCounter(<name>)"
+fn get_element_counter(field: &msg::Field) -> Result<Option<Option<&str>>> {
+    let Some(input) = find_annotation(field, "@IEC61499_ElementCounter") else {
+        return Ok(None);
+    };
+    let element_counter_name = parse_element_counter(input)
+        .map_err(|err| err.to_owned())
+        .finish()?
+        .1;
+    Ok(Some(element_counter_name))
+}
+
+fn parse_element_counter(input: &str) -> IResult<&str, Option<&str>> {
+    preceded(
+        tag("@IEC61499_ElementCounter"),
+        opt(delimited(
+            tag("("),
+            take_while1(|char: char| char.is_alphanumeric() || char == '_'),
+            tag(")"),
+        )),
+    )(input)
+}
+
+// Elements without a default value get a filler
+fn fill_initial_value(
+    field: &msg::Field,
+    base_type: &dtp::BaseType,
+    initial_value: Option<dtp::InitialValue>,
+    capacity: usize,
+) -> Option<dtp::InitialValue> {
+    match initial_value {
+        Some(dtp::InitialValue::Array(mut values)) if capacity > values.len() => {
+            let sample_initial_value = match field.field_type() {
+                msg::FieldType::Variable(Some(msg::InitialValue::Array(values)))
+                | msg::FieldType::Constant(msg::InitialValue::Array(values)) => values.first(),
+                _ => None,
+            };
+            let filler_initial_value = create_filler_initial_value(base_type, sample_initial_value);
+            values.resize(capacity, filler_initial_value);
+            Some(dtp::InitialValue::Array(values))
+        }
+        initial_value => initial_value,
+    }
+}
+
 fn compute_element_counter_default_count(field: &msg::Field) -> u64 {
     match field.field_type() {
         msg::FieldType::Variable(Some(msg::InitialValue::Array(initial_value)))
@@ -111,8 +1099,28 @@ fn compute_element_counter_default_count(field: &msg::Field) -> u64 {
     }
 }
 
-fn convert_to_attributes(field: &msg::Field) -> Result<Vec<dtp::Attribute>> {
+fn convert_to_attributes(
+    field: &msg::Field,
+    original_name: Option<&str>,
+    recorded_base_type: Option<String>,
+) -> Result<Vec<dtp::Attribute>> {
     let mut attributes = Vec::new();
+    if let Some(original_name) = original_name {
+        attributes.push(dtp::Attribute {
+            name: "ROS2_Name".to_string(),
+            base_type: dtp::BaseType::STRING(None),
+            value: dtp::InitialValue::STRING(original_name.to_string()),
+            comment: None,
+        })
+    }
+    if let Some(recorded_base_type) = recorded_base_type {
+        attributes.push(dtp::Attribute {
+            name: "ROS2_BaseType".to_string(),
+            base_type: dtp::BaseType::STRING(None),
+            value: dtp::InitialValue::STRING(recorded_base_type),
+            comment: None,
+        })
+    }
     if let Some(msg::Constraint::UnboundedDynamicArray) = field.constraint() {
         attributes.push(dtp::Attribute {
             name: "ROS2_DynamicArray".to_string(),
@@ -137,71 +1145,128 @@ fn convert_to_attributes(field: &msg::Field) -> Result<Vec<dtp::Attribute>> {
             comment: None,
         })
     }
-    if let msg::BaseType::Custom(msg::Reference::Relative { .. }) = field.base_type() {
+    // The reference is kept as written, as the package cannot be recovered from the type name
+    if let msg::BaseType::Custom(reference) = field.base_type() {
+        let name = match reference {
+            msg::Reference::Relative { .. } => "ROS2_RelativeReference",
+            msg::Reference::Absolute { .. } => "ROS2_AbsoluteReference",
+        };
         attributes.push(dtp::Attribute {
-            name: "ROS2_RelativeReference".to_string(),
-            base_type: dtp::BaseType::BOOL,
-            value: dtp::InitialValue::BOOL(dtp::BoolLiteral::Int(true)),
+            name: name.to_string(),
+            base_type: dtp::BaseType::STRING(None),
+            value: dtp::InitialValue::STRING(type_name::encode_reference(reference)),
             comment: None,
         })
     }
-    if let msg::BaseType::Custom(msg::Reference::Absolute { .. }) = field.base_type() {
+    for annotation in field.annotations().iter() {
         attributes.push(dtp::Attribute {
-            name: "ROS2_AbsoluteReference".to_string(),
-            base_type: dtp::BaseType::BOOL,
-            value: dtp::InitialValue::BOOL(dtp::BoolLiteral::Int(true)),
+            name: format!("{ANNOTATION_ATTRIBUTE_PREFIX}{}", annotation.name),
+            base_type: dtp::BaseType::STRING(None),
+            value: dtp::InitialValue::STRING(annotation.parameters.clone().unwrap_or_default()),
             comment: None,
         })
     }
     Ok(attributes)
 }
 
-fn convert_to_var_name(field: &msg::Field) -> Result<String> {
-    Ok(field.name().to_string())
-}
-
-fn convert_to_var_base_type(package_name: &str, field: &msg::Field) -> dtp::BaseType {
-    match field.base_type() {
-        msg::BaseType::Bool => dtp::BaseType::BOOL,
-        msg::BaseType::Byte => dtp::BaseType::BYTE,
-        msg::BaseType::Uint16 if is_word(field) => dtp::BaseType::WORD,
-        msg::BaseType::Uint32 if is_dword(field) => dtp::BaseType::DWORD,
-        msg::BaseType::Uint64 if is_lword(field) => dtp::BaseType::LWORD,
-        msg::BaseType::Int8 => dtp::BaseType::SINT,
-        msg::BaseType::Int16 => dtp::BaseType::INT,
-        msg::BaseType::Int32 => dtp::BaseType::DINT,
-        msg::BaseType::Int64 => dtp::BaseType::LINT,
-        msg::BaseType::Uint8 => dtp::BaseType::USINT,
-        msg::BaseType::Uint16 => dtp::BaseType::UINT,
-        msg::BaseType::Uint32 => dtp::BaseType::UDINT,
-        msg::BaseType::Uint64 => dtp::BaseType::ULINT,
-        msg::BaseType::Float32 => dtp::BaseType::REAL,
-        msg::BaseType::Float64 => dtp::BaseType::LREAL,
-        msg::BaseType::Char => dtp::BaseType::CHAR,
-        msg::BaseType::String(opt_bound) => dtp::BaseType::STRING(opt_bound.clone()),
-        msg::BaseType::Wstring(opt_bound) => dtp::BaseType::WSTRING(opt_bound.clone()),
-        msg::BaseType::Custom(a_ref) => {
-            dtp::BaseType::Custom(convert_reference(package_name, a_ref))
-        }
-    }
+// The names are sanitized together, as IEC 61499 does not distinguish "speed" from "Speed".
+// "@IEC61499_Name(Speed)" restores the name of a variable, which was renamed for ROS 2.
+fn convert_to_var_names(fields: &[msg::Field]) -> Result<Vec<String>> {
+    let original_names = fields
+        .iter()
+        .map(get_original_name)
+        .collect::<Result<Vec<_>>>()?;
+    let identifiers = fields
+        .iter()
+        .zip(&original_names)
+        .map(|(field, original_name)| Identifier {
+            name: original_name.unwrap_or(field.name()),
+            is_constant: is_constant(field),
+            is_original: original_name.is_some(),
+        })
+        .collect::<Vec<_>>();
+    Ok(identifier::to_iec_names(&identifiers))
 }
 
-fn is_word(field: &msg::Field) -> bool {
-    field
-        .comment()
-        .is_some_and(|comment| comment.contains("@IEC61499_WORD"))
+fn convert_to_identifiers<'a>(
+    fields: &[msg::Field],
+    var_names: &'a [String],
+) -> Vec<Identifier<'a>> {
+    fields
+        .iter()
+        .zip(var_names)
+        .map(|(field, var_name)| Identifier {
+            name: var_name,
+            is_constant: is_constant(field),
+            is_original: false,
+        })
+        .collect()
 }
 
-fn is_dword(field: &msg::Field) -> bool {
-    field
-        .comment()
-        .is_some_and(|comment| comment.contains("@IEC61499_DWORD"))
+fn get_original_name(field: &msg::Field) -> Result<Option<&str>> {
+    let Some(input) = find_annotation(field, "@IEC61499_Name") else {
+        return Ok(None);
+    };
+    let name = parse_original_name(input)
+        .map_err(|err| err.to_owned())
+        .finish()?
+        .1;
+    Ok(Some(name))
 }
 
-fn is_lword(field: &msg::Field) -> bool {
-    field
-        .comment()
-        .is_some_and(|comment| comment.contains("@IEC61499_LWORD"))
+fn parse_original_name(input: &str) -> IResult<&str, &str> {
+    delimited(
+        tag("@IEC61499_Name("),
+        take_while1(|char: char| char.is_alphanumeric() || char == '_'),
+        tag(")"),
+    )(input)
+}
+
+fn is_constant(field: &msg::Field) -> bool {
+    matches!(field.field_type(), msg::FieldType::Constant(_))
+}
+
+// An annotated type, e.g. "@IEC61499_WORD", takes precedence over the mapping profile,
+// "@IEC61499_TypeName(Lager_Platz)" over the name of the referenced message
+fn convert_to_var_base_type(
+    package_name: &str,
+    options: &ConversionOptions,
+    field_mapping: &FieldMapping,
+    field: &msg::Field,
+) -> Result<dtp::BaseType> {
+    match (field.base_type(), get_type_annotation(field)) {
+        (msg::BaseType::Custom(a_ref), _) => Ok(dtp::BaseType::Custom(
+            match field
+                .comment()
+                .and_then(|comment| type_name::decode_type_name_annotation(comment))
+            {
+                Some(iec_name) => iec_name.to_string(),
+                None => convert_reference(package_name, options, a_ref),
+            },
+        )),
+        (base_type, Some(type_name)) => mapping_profile::annotated_iec_type(type_name, base_type)
+            .map_err(|err| format!("Field \"{}\": {err}", field.name()).into()),
+        (base_type, None) => field_mapping.to_iec_type(base_type),
+    }
+}
+
+fn get_type_annotation(field: &msg::Field) -> Option<&'static str> {
+    mapping_profile::iec_type_names()
+        .find(|type_name| find_annotation(field, &format!("@IEC61499_{type_name}")).is_some())
+}
+
+// The reverse conversion would not restore the ROS 2 type without the attribute
+fn convert_to_recorded_base_type(
+    field_mapping: &FieldMapping,
+    field: &msg::Field,
+    base_type: &dtp::BaseType,
+) -> Result<Option<String>> {
+    if let msg::BaseType::Custom(_) = field.base_type() {
+        return Ok(None);
+    }
+    let restored_base_type = field_mapping.to_ros_type(base_type)?;
+    Ok((restored_base_type != *field.base_type())
+        .then(|| mapping_profile::ros_type_name(field.base_type())))
 }
 
 fn is_shifted_static_array(field: &msg::Field) -> bool {
@@ -227,13 +1292,13 @@ fn get_start_index(field: &msg::Field) -> Result<i64> {
 }
 
 fn parse_start_index(input: &str) -> IResult<&str, i64> {
+    delimited(tag("@IEC61499_StartIndex("), parse_signed_int, tag(")"))(input)
+}
+
+fn parse_signed_int(input: &str) -> IResult<&str, i64> {
     map_res(
-        delimited(
-            tag("@IEC61499_StartIndex("),
-            recognize(tuple((opt(alt((tag("-"), tag("+")))), digit1))),
-            tag(")"),
-        ),
-        |str: &str| i64::from_str_radix(str, 10),
+        recognize(tuple((opt(alt((tag("-"), tag("+")))), digit1))),
+        |str: &str| str.parse::<i64>(),
     )(input)
 }
 
@@ -247,177 +1312,149 @@ fn convert_to_var_optional_array_size(field: &msg::Field) -> Result<Option<dtp::
         Some(msg::Constraint::StaticArray(capacity)) => {
             Some(dtp::ArraySize::Static(dtp::Capacity::InPlace(*capacity)))
         }
-        Some(msg::Constraint::UnboundedDynamicArray) => {
-            Some(dtp::ArraySize::Static(dtp::Capacity::InPlace(3)))
-        }
-        Some(msg::Constraint::BoundedDynamicArray(bound)) => {
-            Some(dtp::ArraySize::Static(dtp::Capacity::InPlace(*bound)))
-        }
-        None => None,
+        // Sequences depend on the dynamic array strategy
+        Some(msg::Constraint::UnboundedDynamicArray)
+        | Some(msg::Constraint::BoundedDynamicArray(_))
+        | None => None,
     })
 }
 
-fn convert_to_var_optional_initial_value(field: &msg::Field) -> Result<Option<dtp::InitialValue>> {
+fn convert_to_var_optional_initial_value(
+    field: &msg::Field,
+    base_type: &dtp::BaseType,
+) -> Result<Option<dtp::InitialValue>> {
     let optional_initial_value = match field.field_type() {
         msg::FieldType::Variable(optional_initial_value) => optional_initial_value.as_ref(),
         msg::FieldType::Constant(initial_value) => Some(initial_value),
     };
 
     match optional_initial_value {
-        Some(initial_value) => Ok(Some(convert_initial_value(initial_value, field)?)),
+        Some(initial_value) => Ok(Some(convert_initial_value(
+            initial_value,
+            base_type,
+            field,
+        )?)),
         None => Ok(None),
     }
 }
 
-fn convert_reference(package_name: &str, reference: &msg::Reference) -> String {
+fn convert_reference(
+    package_name: &str,
+    options: &ConversionOptions,
+    reference: &msg::Reference,
+) -> String {
     match reference {
-        msg::Reference::Relative { file } => convert_structured_type_name(package_name, file),
-        msg::Reference::Absolute { package, file } => convert_structured_type_name(package, file),
+        msg::Reference::Relative { file } => {
+            convert_structured_type_name(package_name, options, msg::INTERFACE_KIND, file)
+        }
+        msg::Reference::Absolute { package, file } => {
+            convert_structured_type_name(package, options, msg::INTERFACE_KIND, file)
+        }
     }
 }
 
+// The value gets the IEC 61131-3 type of its field, e.g. a uint16 mapped onto WORD a WORD value
 fn convert_initial_value(
     initial_value: &msg::InitialValue,
+    base_type: &dtp::BaseType,
     field: &msg::Field,
 ) -> Result<dtp::InitialValue> {
-    Ok(match initial_value {
-        msg::InitialValue::Bool(v) => dtp::InitialValue::BOOL(convert_bool_literal(v)),
-        msg::InitialValue::Float32(v) => dtp::InitialValue::REAL(*v),
-        msg::InitialValue::Float64(v) => dtp::InitialValue::LREAL(*v),
-        msg::InitialValue::Int8(v) => dtp::InitialValue::SINT(convert_int_literal(v)),
-        msg::InitialValue::Uint8(v) => dtp::InitialValue::USINT(convert_int_literal(v)),
-        msg::InitialValue::Int16(v) => dtp::InitialValue::INT(convert_int_literal(v)),
-        msg::InitialValue::Uint16(v) => dtp::InitialValue::UINT(convert_int_literal(v)),
-        msg::InitialValue::Int32(v) => dtp::InitialValue::DINT(convert_int_literal(v)),
-        msg::InitialValue::Uint32(v) => dtp::InitialValue::UDINT(convert_int_literal(v)),
-        msg::InitialValue::Int64(v) => dtp::InitialValue::LINT(convert_int_literal(v)),
-        msg::InitialValue::Uint64(v) => dtp::InitialValue::ULINT(convert_int_literal(v)),
-        msg::InitialValue::Byte(v) if is_word(field) => {
-            dtp::InitialValue::WORD(convert_int_literal(v))
-        }
-        msg::InitialValue::Byte(v) if is_dword(field) => {
-            dtp::InitialValue::DWORD(convert_int_literal(v))
-        }
-        msg::InitialValue::Byte(v) if is_lword(field) => {
-            dtp::InitialValue::LWORD(convert_int_literal(v))
+    let mismatch = || format!("The value of \"{}\" does not fit its type", field.name());
+    Ok(match (initial_value, base_type) {
+        (msg::InitialValue::Array(v), base_type) => dtp::InitialValue::Array(
+            v.iter()
+                .map(|value| convert_initial_value(value, base_type, field))
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        (msg::InitialValue::Bool(v), dtp::BaseType::BOOL) => {
+            dtp::InitialValue::BOOL(convert_bool_literal(v))
         }
-        msg::InitialValue::Byte(v) => dtp::InitialValue::BYTE(convert_int_literal(v)),
-        msg::InitialValue::Char(v) => dtp::InitialValue::CHAR(convert_to_char_literal(v)?),
-        msg::InitialValue::String(v) => {
-            dtp::InitialValue::STRING(v.replace("'", "$'").replace("\"", "&quot;"))
+        (msg::InitialValue::Float32(v), dtp::BaseType::REAL) => dtp::InitialValue::REAL(*v),
+        // Widened by its decimal representation, so that 0.1 stays 0.1
+        (msg::InitialValue::Float32(v), dtp::BaseType::LREAL) => {
+            dtp::InitialValue::LREAL(v.to_string().parse().unwrap_or(f64::from(*v)))
         }
-        msg::InitialValue::Wstring(v) => dtp::InitialValue::WSTRING(v.replace("\"", "$&quot;")),
-        msg::InitialValue::Array(v) => {
-            // Determine new capacity based on field constraint
-            let new_capacity = match field.constraint() {
-                Some(msg::Constraint::BoundedDynamicArray(capacity)) => *capacity,
-                Some(msg::Constraint::UnboundedDynamicArray) => 3,
-                _ => v.len(),
-            };
+        (msg::InitialValue::Float64(v), dtp::BaseType::REAL) => dtp::InitialValue::REAL(*v as f32),
+        (msg::InitialValue::Float64(v), dtp::BaseType::LREAL) => dtp::InitialValue::LREAL(*v),
+        (
+            msg::InitialValue::String(v) | msg::InitialValue::Wstring(v),
+            dtp::BaseType::STRING(_),
+        ) => dtp::InitialValue::STRING(v.clone()),
+        (
+            msg::InitialValue::String(v) | msg::InitialValue::Wstring(v),
+            dtp::BaseType::WSTRING(_),
+        ) => dtp::InitialValue::WSTRING(v.clone()),
+        (initial_value, dtp::BaseType::CHAR) => dtp::InitialValue::CHAR(convert_to_char_literal(
+            get_int_literal(initial_value).ok_or_else(mismatch)?,
+        )?),
+        (initial_value, base_type) => get_int_literal(initial_value)
+            .and_then(|v| create_int_initial_value(base_type, convert_int_literal(v)))
+            .ok_or_else(mismatch)?,
+    })
+}
 
-            // Convert initial values
-            let mut vec = v
-                .iter()
-                .map(|value| convert_initial_value(value, field))
-                .collect::<Result<Vec<_>>>()?;
-
-            // Add filler values as needed
-            if new_capacity > vec.len() {
-                let sample_initial_value = v.iter().next();
-                vec.extend(vec![
-                    create_filler_initial_value(field, sample_initial_value);
-                    new_capacity - vec.len()
-                ]);
-            }
+fn get_int_literal(initial_value: &msg::InitialValue) -> Option<&msg::IntLiteral> {
+    match initial_value {
+        msg::InitialValue::Byte(literal)
+        | msg::InitialValue::Char(literal)
+        | msg::InitialValue::Int8(literal)
+        | msg::InitialValue::Uint8(literal)
+        | msg::InitialValue::Int16(literal)
+        | msg::InitialValue::Uint16(literal)
+        | msg::InitialValue::Int32(literal)
+        | msg::InitialValue::Uint32(literal)
+        | msg::InitialValue::Int64(literal)
+        | msg::InitialValue::Uint64(literal) => Some(literal),
+        _ => None,
+    }
+}
 
-            dtp::InitialValue::Array(vec)
-        }
-    })
+fn create_int_initial_value(
+    base_type: &dtp::BaseType,
+    int_literal: dtp::IntLiteral,
+) -> Option<dtp::InitialValue> {
+    match base_type {
+        dtp::BaseType::SINT => Some(dtp::InitialValue::SINT(int_literal)),
+        dtp::BaseType::INT => Some(dtp::InitialValue::INT(int_literal)),
+        dtp::BaseType::DINT => Some(dtp::InitialValue::DINT(int_literal)),
+        dtp::BaseType::LINT => Some(dtp::InitialValue::LINT(int_literal)),
+        dtp::BaseType::USINT => Some(dtp::InitialValue::USINT(int_literal)),
+        dtp::BaseType::UINT => Some(dtp::InitialValue::UINT(int_literal)),
+        dtp::BaseType::UDINT => Some(dtp::InitialValue::UDINT(int_literal)),
+        dtp::BaseType::ULINT => Some(dtp::InitialValue::ULINT(int_literal)),
+        dtp::BaseType::BYTE => Some(dtp::InitialValue::BYTE(int_literal)),
+        dtp::BaseType::WORD => Some(dtp::InitialValue::WORD(int_literal)),
+        dtp::BaseType::DWORD => Some(dtp::InitialValue::DWORD(int_literal)),
+        dtp::BaseType::LWORD => Some(dtp::InitialValue::LWORD(int_literal)),
+        _ => None,
+    }
 }
 
+// Elements without a sample value get the default of their type
 fn create_filler_initial_value(
-    field: &msg::Field,
+    base_type: &dtp::BaseType,
     sample_initial_value: Option<&msg::InitialValue>,
 ) -> dtp::InitialValue {
-    sample_initial_value.map_or_else(
-        || create_default_initial_value(field),
-        |sample_initial_value| match sample_initial_value {
-            msg::InitialValue::Bool(literal) => {
-                dtp::InitialValue::BOOL(create_filler_bool_literal(Some(literal)))
-            }
-            msg::InitialValue::Byte(literal) => {
-                dtp::InitialValue::BYTE(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Uint16(literal) if is_word(field) => {
-                dtp::InitialValue::WORD(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Uint32(literal) if is_dword(field) => {
-                dtp::InitialValue::DWORD(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Uint64(literal) if is_lword(field) => {
-                dtp::InitialValue::LWORD(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Uint8(literal) => {
-                dtp::InitialValue::USINT(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Uint16(literal) => {
-                dtp::InitialValue::UINT(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Uint32(literal) => {
-                dtp::InitialValue::UDINT(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Uint64(literal) => {
-                dtp::InitialValue::ULINT(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Int8(literal) => {
-                dtp::InitialValue::SINT(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Int16(literal) => {
-                dtp::InitialValue::INT(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Int32(literal) => {
-                dtp::InitialValue::DINT(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Int64(literal) => {
-                dtp::InitialValue::LINT(create_filler_int_literal(Some(literal)))
-            }
-            msg::InitialValue::Float32(_) => dtp::InitialValue::REAL(0f32),
-            msg::InitialValue::Float64(_) => dtp::InitialValue::LREAL(0f64),
-            msg::InitialValue::Char(_) => dtp::InitialValue::CHAR(create_filler_char_literal()),
-            msg::InitialValue::String(_) => dtp::InitialValue::STRING(String::new()),
-            msg::InitialValue::Wstring(_) => dtp::InitialValue::WSTRING(String::new()),
-            msg::InitialValue::Array(_) => unimplemented!(),
-        },
-    )
-}
-
-fn create_default_initial_value(field: &msg::Field) -> dtp::InitialValue {
-    match field.base_type() {
-        msg::BaseType::Bool => dtp::InitialValue::BOOL(create_filler_bool_literal(None)),
-        msg::BaseType::Byte => dtp::InitialValue::BYTE(create_filler_int_literal(None)),
-        msg::BaseType::Uint16 if is_word(field) => {
-            dtp::InitialValue::WORD(create_filler_int_literal(None))
-        }
-        msg::BaseType::Uint32 if is_dword(field) => {
-            dtp::InitialValue::DWORD(create_filler_int_literal(None))
+    let sample_bool_literal = match sample_initial_value {
+        Some(msg::InitialValue::Bool(literal)) => Some(literal),
+        _ => None,
+    };
+    let sample_int_literal = sample_initial_value.and_then(get_int_literal);
+    match base_type {
+        dtp::BaseType::BOOL => {
+            dtp::InitialValue::BOOL(create_filler_bool_literal(sample_bool_literal))
         }
-        msg::BaseType::Uint64 if is_lword(field) => {
-            dtp::InitialValue::LWORD(create_filler_int_literal(None))
+        dtp::BaseType::REAL => dtp::InitialValue::REAL(0f32),
+        dtp::BaseType::LREAL => dtp::InitialValue::LREAL(0f64),
+        dtp::BaseType::CHAR => dtp::InitialValue::CHAR(create_filler_char_literal()),
+        dtp::BaseType::STRING(_) => dtp::InitialValue::STRING(String::new()),
+        dtp::BaseType::WSTRING(_) => dtp::InitialValue::WSTRING(String::new()),
+        // Without explicit values all members keep the defaults of the referenced type
+        dtp::BaseType::Custom(_) => dtp::InitialValue::Struct(Vec::new()),
+        base_type => {
+            create_int_initial_value(base_type, create_filler_int_literal(sample_int_literal))
+                .expect("the remaining types to be integers")
         }
-        msg::BaseType::Uint8 => dtp::InitialValue::USINT(create_filler_int_literal(None)),
-        msg::BaseType::Uint16 => dtp::InitialValue::UINT(create_filler_int_literal(None)),
-        msg::BaseType::Uint32 => dtp::InitialValue::UDINT(create_filler_int_literal(None)),
-        msg::BaseType::Uint64 => dtp::InitialValue::ULINT(create_filler_int_literal(None)),
-        msg::BaseType::Int8 => dtp::InitialValue::SINT(create_filler_int_literal(None)),
-        msg::BaseType::Int16 => dtp::InitialValue::INT(create_filler_int_literal(None)),
-        msg::BaseType::Int32 => dtp::InitialValue::DINT(create_filler_int_literal(None)),
-        msg::BaseType::Int64 => dtp::InitialValue::LINT(create_filler_int_literal(None)),
-        msg::BaseType::Float32 => dtp::InitialValue::REAL(0f32),
-        msg::BaseType::Float64 => dtp::InitialValue::LREAL(0f64),
-        msg::BaseType::Char => dtp::InitialValue::CHAR(create_filler_char_literal()),
-        msg::BaseType::String(_) => dtp::InitialValue::STRING(String::new()),
-        msg::BaseType::Wstring(_) => dtp::InitialValue::WSTRING(String::new()),
-        msg::BaseType::Custom(_) => unimplemented!(),
     }
 }
 
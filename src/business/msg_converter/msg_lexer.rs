@@ -0,0 +1,101 @@
+use crate::business::error::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenKind {
+    Whitespace,
+    Word,
+    Equals,
+    Comment,
+    LineEnding,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub line: usize,
+}
+
+// Splits the input into tokens without dropping a single character,
+// so that joining the token texts yields the input again.
+pub fn tokenize(input: &str) -> Result<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let (kind, length) = next_token(rest, line)?;
+        let (text, remaining) = rest.split_at(length);
+        tokens.push(Token { kind, text, line });
+        if kind == TokenKind::LineEnding {
+            line += 1;
+        }
+        rest = remaining;
+    }
+    Ok(tokens)
+}
+
+fn next_token(input: &str, line: usize) -> Result<(TokenKind, usize)> {
+    if input.starts_with("\r\n") {
+        return Ok((TokenKind::LineEnding, 2));
+    }
+    match input.chars().next() {
+        Some('\n') => Ok((TokenKind::LineEnding, 1)),
+        Some(' ') | Some('\t') => Ok((
+            TokenKind::Whitespace,
+            input
+                .find(|c| c != ' ' && c != '\t')
+                .unwrap_or(input.len()),
+        )),
+        Some('#') => Ok((TokenKind::Comment, line_length(input))),
+        Some('=') => Ok((TokenKind::Equals, 1)),
+        _ => Ok((TokenKind::Word, word_length(input, line)?)),
+    }
+}
+
+fn line_length(input: &str) -> usize {
+    match input.find('\n') {
+        Some(position) if input[..position].ends_with('\r') => position - 1,
+        Some(position) => position,
+        None => input.len(),
+    }
+}
+
+// A word ends at whitespace, a comment, a line ending or an assignment.
+// Quoted strings and bracketed arrays may contain any of those.
+fn word_length(input: &str, line: usize) -> Result<usize> {
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    let mut previous: Option<char> = None;
+    for (position, c) in input.char_indices() {
+        if c == '\n' || input[position..].starts_with("\r\n") {
+            if quote.is_some() {
+                break;
+            }
+            return Ok(position);
+        }
+        if let Some(delimiter) = quote {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == delimiter {
+                quote = None;
+            }
+        } else {
+            match c {
+                '"' | '\'' if position == 0 || depth > 0 => quote = Some(c),
+                '[' => depth += 1,
+                ']' => depth = depth.saturating_sub(1),
+                ' ' | '\t' | '#' if depth == 0 => return Ok(position),
+                '=' if depth == 0 && previous != Some('<') => return Ok(position),
+                _ => {}
+            }
+        }
+        previous = Some(c);
+    }
+    match quote {
+        Some(_) => Err(format!("Unterminated string literal in line {line}").into()),
+        None => Ok(input.len()),
+    }
+}
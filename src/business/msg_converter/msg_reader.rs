@@ -1,68 +1,200 @@
 use std::num::ParseIntError;
 use std::path::Path;
 
-use log::{debug, info};
+use log::info;
 use nom::branch::alt;
 use nom::bytes::complete::{is_a, tag, take_till1, take_while1};
-use nom::character::complete::{digit1, hex_digit1, line_ending, multispace1, oct_digit1};
-use nom::combinator::{eof, fail, map, map_res, opt, verify};
-use nom::multi::{many0, separated_list0};
+use nom::character::complete::{digit1, hex_digit1, oct_digit1, space0};
+use nom::combinator::{all_consuming, cut, fail, map, map_res, opt, verify};
+use nom::multi::separated_list0;
 use nom::number::complete::{double, float};
-use nom::sequence::{delimited, preceded, terminated, tuple};
-use nom::{Finish, IResult};
+use nom::sequence::{delimited, pair, preceded, tuple};
+use nom::IResult;
 
 use crate::business::error::Result;
+use crate::business::msg_converter::msg_lexer::{tokenize, Token, TokenKind};
+use crate::business::string_codec::decode_ros_string;
 use crate::core::msg::*;
+use crate::core::msg_cst::*;
+
+const SECTION_SEPARATOR: &str = "---";
 
 pub fn read(path_to_source_file: &str) -> Result<StructuredType> {
     info!("Start reading file {:?}", path_to_source_file);
     let source_file_path = Path::new(path_to_source_file);
     let file_name = parse_file_name(source_file_path)?;
     let file_content = std::fs::read_to_string(source_file_path)?;
-    let parsed_fields = parse_file(&file_content).finish()?.1;
-    let structured_type = StructuredType::new(&file_name, parsed_fields);
+    let structured_type = parse_structured_type(&file_name, &file_content)?;
     info!("Finished reading file {:?}", path_to_source_file);
     Ok(structured_type)
 }
 
-fn parse_file_name(path_to_file: &Path) -> Result<String> {
+pub(super) fn parse_file_name(path_to_file: &Path) -> Result<String> {
     let file_name = path_to_file
         .file_stem()
-        .map(|os_str| os_str.to_str())
-        .flatten()
+        .and_then(|os_str| os_str.to_str())
         .map(|str| str.to_string())
         .ok_or("Could not read file name from file path")?;
     Ok(file_name)
 }
 
-fn parse_file(input: &str) -> IResult<&str, Vec<Field>, nom::error::Error<String>> {
-    many0(terminated(parse_field, eol_or_eof))(input).map_err(|err| err.to_owned())
+// Services and actions consist of several message sections separated by "---".
+// Every section keeps its original text including line endings.
+pub(super) fn split_sections(file_content: &str, section_count: usize) -> Result<Vec<String>> {
+    let mut sections = vec![String::new()];
+    for line in file_content.split_inclusive('\n') {
+        if line.trim() == SECTION_SEPARATOR {
+            sections.push(String::new());
+        } else if let Some(section) = sections.last_mut() {
+            section.push_str(line);
+        }
+    }
+    if sections.len() != section_count {
+        return Err(format!(
+            "Expected {section_count} sections separated by \"{SECTION_SEPARATOR}\", found {}",
+            sections.len()
+        )
+        .into());
+    }
+    Ok(sections)
 }
 
-fn parse_field(input: &str) -> IResult<&str, Field> {
-    let (input, (base_type, optional_constraint, name)) = tuple((
-        parse_base_type,
-        opt(parse_constraint),
-        preceded(multispace1, parse_field_name),
-    ))(input)?;
-
-    let (input, field_type) = parse_field_type(&base_type, &optional_constraint)(input)?;
-    let (input, comment) = opt(preceded(multispace1, parse_line_comment))(input)?;
-
-    Ok((
-        input,
-        Field::new(
-            &base_type,
-            &optional_constraint,
-            &name,
-            &field_type,
-            &comment,
-        ),
+pub(super) fn parse_structured_type(name: &str, input: &str) -> Result<StructuredType> {
+    let syntax_tree = parse_syntax_tree(input)?;
+    let fields = syntax_tree
+        .lines()
+        .iter()
+        .filter_map(Line::field)
+        .cloned()
+        .collect();
+    Ok(StructuredType::new(
+        name,
+        &syntax_tree.file_level_comment(),
+        fields,
+        &Some(syntax_tree),
     ))
 }
 
-fn eol_or_eof(input: &str) -> IResult<&str, &str> {
-    alt((line_ending, eof))(input)
+pub fn parse_syntax_tree(input: &str) -> Result<SyntaxTree> {
+    let tokens = tokenize(input)?;
+    let lines = tokens
+        .split_inclusive(|token| token.kind == TokenKind::LineEnding)
+        .map(parse_line)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(SyntaxTree::new(lines))
+}
+
+fn parse_line(tokens: &[Token]) -> Result<Line> {
+    let line_number = tokens.first().map_or(0, |token| token.line);
+    let (line_ending, rest) = take_last(tokens, TokenKind::LineEnding);
+    let (comment, rest) = take_last(rest, TokenKind::Comment);
+    let (trailing_whitespace, rest) = take_last(rest, TokenKind::Whitespace);
+    let (leading_whitespace, rest) = take_first(rest, TokenKind::Whitespace);
+
+    let content = match rest {
+        [] => LineContent::Empty,
+        [type_token, separator, name, value_tokens @ ..]
+            if type_token.kind == TokenKind::Word
+                && separator.kind == TokenKind::Whitespace
+                && name.kind == TokenKind::Word =>
+        {
+            let node = FieldNode {
+                type_spelling: type_token.text.to_string(),
+                separator: separator.text.to_string(),
+                name: name.text.to_string(),
+                value: parse_value_node(value_tokens)
+                    .ok_or(format!("Invalid default value in line {line_number}"))?,
+            };
+            let comment_text = comment.map(|comment| comment.trim_start_matches('#').trim().to_string());
+            let field = lower_field(&node, &comment_text)
+                .ok_or(format!("Invalid field in line {line_number}: \"{}\"", node.text()))?;
+            LineContent::Field { node, field: Box::new(field) }
+        }
+        _ => return Err(format!("Invalid line {line_number}: expected a field or a comment").into()),
+    };
+
+    Ok(Line {
+        leading_whitespace: leading_whitespace.unwrap_or_default().to_string(),
+        content,
+        trailing_whitespace: trailing_whitespace.unwrap_or_default().to_string(),
+        comment: comment.map(str::to_string),
+        line_ending: line_ending.unwrap_or_default().to_string(),
+    })
+}
+
+// None, if the tokens do not form a valid assignment. Some(None), if there is no value.
+fn parse_value_node(tokens: &[Token]) -> Option<Option<ValueNode>> {
+    let (value, assignment) = match tokens.split_last() {
+        None => return Some(None),
+        Some((value, assignment)) if value.kind == TokenKind::Word => (value, assignment),
+        _ => return None,
+    };
+    let kinds: Vec<TokenKind> = assignment.iter().map(|token| token.kind).collect();
+    let is_valid = matches!(
+        kinds.as_slice(),
+        [TokenKind::Whitespace]
+            | [TokenKind::Equals]
+            | [TokenKind::Whitespace, TokenKind::Equals]
+            | [TokenKind::Equals, TokenKind::Whitespace]
+            | [TokenKind::Whitespace, TokenKind::Equals, TokenKind::Whitespace]
+    );
+    is_valid.then(|| {
+        Some(ValueNode {
+            assignment: assignment.iter().map(|token| token.text).collect(),
+            spelling: value.text.to_string(),
+        })
+    })
+}
+
+fn lower_field(node: &FieldNode, comment: &Option<String>) -> Option<Field> {
+    let (base_type, constraint) = parse_completely(
+        tuple((parse_base_type, opt(parse_constraint))),
+        &node.type_spelling,
+    )?;
+    let name = parse_completely(parse_field_name, &node.name)?;
+    let field_type = match &node.value {
+        Some(value) => {
+            let initial_value =
+                parse_completely(parse_initial_value(&base_type, &constraint), &value.spelling)?;
+            if value.assignment.contains('=') {
+                FieldType::Constant(initial_value)
+            } else {
+                FieldType::Variable(Some(initial_value))
+            }
+        }
+        None => FieldType::Variable(None),
+    };
+    Some(Field::new(&base_type, &constraint, name, &field_type, comment, &[]))
+}
+
+// Parses a value in MSG syntax, e.g. "[1, 2]" or "'text'", for the given field type
+pub(super) fn parse_field_value(
+    base_type: &BaseType,
+    constraint: &Option<Constraint>,
+    spelling: &str,
+) -> Option<InitialValue> {
+    parse_completely(parse_initial_value(base_type, constraint), spelling)
+}
+
+fn parse_completely<'a, O>(
+    parser: impl FnMut(&'a str) -> IResult<&'a str, O>,
+    input: &'a str,
+) -> Option<O> {
+    all_consuming(parser)(input).ok().map(|(_, output)| output)
+}
+
+fn take_first<'a>(tokens: &'a [Token<'a>], kind: TokenKind) -> (Option<&'a str>, &'a [Token<'a>]) {
+    match tokens.split_first() {
+        Some((first, rest)) if first.kind == kind => (Some(first.text), rest),
+        _ => (None, tokens),
+    }
+}
+
+fn take_last<'a>(tokens: &'a [Token<'a>], kind: TokenKind) -> (Option<&'a str>, &'a [Token<'a>]) {
+    match tokens.split_last() {
+        Some((last, rest)) if last.kind == kind => (Some(last.text), rest),
+        _ => (None, tokens),
+    }
 }
 
 fn parse_base_type(input: &str) -> IResult<&str, BaseType> {
@@ -81,16 +213,12 @@ fn parse_base_type(input: &str) -> IResult<&str, BaseType> {
         map(tag("uint64"), |_| BaseType::Uint64),
         map(tag("char"), |_| BaseType::Char),
         map(
-            tuple((tag("string"), opt(preceded(tag("<="), digit1)))),
-            |(_, optional_bound): (&str, Option<&str>)| {
-                BaseType::String(optional_bound.map(|digits| digits.parse().unwrap()))
-            },
+            preceded(tag("string"), opt(parse_string_bound)),
+            BaseType::String,
         ),
         map(
-            tuple((tag("wstring"), opt(preceded(tag("<="), digit1)))),
-            |(_, optional_bound): (&str, Option<&str>)| {
-                BaseType::Wstring(optional_bound.map(|digits| digits.parse().unwrap()))
-            },
+            preceded(tag("wstring"), opt(parse_string_bound)),
+            BaseType::Wstring,
         ),
         map_res(take_till1(|c| c == ' ' || c == '['), |custom_type: &str| {
             let parts: Vec<&str> = custom_type.split('/').collect();
@@ -110,17 +238,19 @@ fn parse_base_type(input: &str) -> IResult<&str, BaseType> {
     ))(input)
 }
 
+// A bound too large for usize must not leave the rest to be read as a custom type
+fn parse_string_bound(input: &str) -> IResult<&str, usize> {
+    preceded(tag("<="), cut(map_res(digit1, str::parse)))(input)
+}
+
 fn parse_constraint(input: &str) -> IResult<&str, Constraint> {
-    // Annahne: N ist in jedem Fall durch usize begrenzt
     alt((
         map(tag("[]"), |_| Constraint::UnboundedDynamicArray),
-        map(delimited(tag("[<="), digit1, tag("]")), |digits: &str| {
-            let casted = digits.parse().unwrap();
-            Constraint::BoundedDynamicArray(casted)
+        map_res(delimited(tag("[<="), digit1, tag("]")), |digits: &str| {
+            digits.parse().map(Constraint::BoundedDynamicArray)
         }),
-        map(delimited(tag("["), digit1, tag("]")), |digits: &str| {
-            let casted = digits.parse().unwrap();
-            Constraint::StaticArray(casted)
+        map_res(delimited(tag("["), digit1, tag("]")), |digits: &str| {
+            digits.parse().map(Constraint::StaticArray)
         }),
     ))(input)
 }
@@ -137,34 +267,6 @@ fn parse_field_name(input: &str) -> IResult<&str, &str> {
     })(input)
 }
 
-fn parse_field_type<'a>(
-    base_type: &BaseType,
-    constraint: &Option<Constraint>,
-) -> impl FnMut(&'a str) -> IResult<&'a str, FieldType> {
-    map(
-        opt(tuple((
-            alt((tag("="), tag(" "))),
-            parse_initial_value(base_type, constraint),
-        ))),
-        |option| {
-            match option {
-                Some((tag, initial_value)) => match tag {
-                    "=" => FieldType::Constant(initial_value),
-                    _ => FieldType::Variable(Some(initial_value)),
-                },
-                _ => FieldType::Variable(None),
-            }
-        },
-    )
-}
-
-fn parse_line_comment(input: &str) -> IResult<&str, String> {
-    map(
-        preceded(tag("#"), take_until_parser(eol_or_eof)),
-        |str: &str| str.trim().to_string(),
-    )(input)
-}
-
 fn parse_initial_value<'a>(
     datatype: &BaseType,
     optional_constraint: &Option<Constraint>,
@@ -172,9 +274,12 @@ fn parse_initial_value<'a>(
     if optional_constraint.is_some() {
         Box::new(map(
             delimited(
-                tag("["),
-                separated_list0(tag(","), parse_initial_value(datatype, &None)),
-                tag("]"),
+                pair(tag("["), space0),
+                separated_list0(
+                    delimited(space0, tag(","), space0),
+                    parse_initial_value(datatype, &None),
+                ),
+                pair(space0, tag("]")),
             ),
             InitialValue::Array,
         ))
@@ -230,10 +335,10 @@ fn dec_int_parser(input: &str) -> IResult<&str, IntLiteral> {
         |(sign, str): (Option<&str>, &str)| {
             Ok::<IntLiteral, ParseIntError>(if let Some(sign) = sign {
                 let to_parse = format!("{sign}{str}");
-                let i64 = i64::from_str_radix(&to_parse, 10)?;
+                let i64 = to_parse.parse::<i64>()?;
                 IntLiteral::SignedDecimalInt(i64)
             } else {
-                let u64 = u64::from_str_radix(&str, 10)?;
+                let u64 = str.parse::<u64>()?;
                 IntLiteral::UnsignedDecimalInt(u64)
             })
         },
@@ -274,23 +379,25 @@ fn bin_int_parser(input: &str) -> IResult<&str, IntLiteral> {
 }
 
 fn parse_quoted_string(input: &str) -> IResult<&str, String> {
-    alt((
-        delimited(tag("\""), parse_inner_string('"'), tag("\"")),
-        delimited(tag("'"), parse_inner_string('\''), tag("'")),
-    ))(input)
+    map_res(
+        alt((
+            delimited(tag("\""), parse_inner_string('"'), tag("\"")),
+            delimited(tag("'"), parse_inner_string('\''), tag("'")),
+        )),
+        decode_ros_string,
+    )(input)
 }
 
-fn parse_inner_string(quote: char) -> impl FnMut(&str) -> IResult<&str, String> {
+// The content is returned with its escape sequences
+fn parse_inner_string(quote: char) -> impl FnMut(&str) -> IResult<&str, &str> {
     move |input: &str| {
-        let mut ret = String::new();
         let mut skip_delimiter = false;
         for (i, ch) in input.char_indices() {
             if ch == '\\' && !skip_delimiter {
                 skip_delimiter = true;
             } else if ch == quote && !skip_delimiter {
-                return Ok((&input[i..], ret));
+                return Ok((&input[i..], &input[..i]));
             } else {
-                ret.push(ch);
                 skip_delimiter = false;
             }
         }
@@ -302,19 +409,20 @@ fn bin_digit(input: &str) -> IResult<&str, &str> {
     is_a("01")(input)
 }
 
-fn take_until_parser<P>(end_parser: P) -> impl Fn(&str) -> IResult<&str, &str>
-where
-    P: Fn(&str) -> IResult<&str, &str>,
-{
-    move |input: &str| {
-        let mut i = 0;
-        while i < input.len() {
-            let slice = &input[i..];
-            if end_parser(slice).is_ok() {
-                return Ok((&input[i..], &input[0..i]));
-            }
-            i += 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounds_exceeding_usize_are_rejected() {
+        for source in [
+            "string<=99999999999999999999 text\n",
+            "wstring<=99999999999999999999 text\n",
+            "int32[<=99999999999999999999] values\n",
+            "int32[99999999999999999999] values\n",
+        ] {
+            assert!(parse_syntax_tree(source).is_err(), "{source}");
         }
-        Ok(("", input))
+        assert!(parse_syntax_tree("string<=5 text\n").is_ok());
     }
 }
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use xmltree::Element;
+
+use crate::business::error::Result;
+use crate::core::package::*;
+
+// Marker files of colcon, e.g. in "build", "install" and "log" of a workspace
+const IGNORE_MARKER_FILES: [&str; 2] = ["COLCON_IGNORE", "AMENT_IGNORE"];
+
+pub fn read(path_to_file: &str) -> Result<Package> {
+    info!("Start reading file {:?}", path_to_file);
+    let file = std::fs::File::open(path_to_file)?;
+    let package = parse_package(file)?;
+    info!("Finished reading file {:?}", path_to_file);
+    Ok(package)
+}
+
+// The nearest package.xml from the given file or directory upwards belongs to its package
+pub fn find(path: &str) -> Option<PathBuf> {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    path.ancestors()
+        .map(|directory| directory.join(FILE_NAME))
+        .find(|path_to_file| path_to_file.is_file())
+}
+
+// A directory with a package.xml is a package, which contains no further packages
+pub fn find_packages(directory: &Path) -> Result<Vec<PathBuf>> {
+    if directory.join(FILE_NAME).is_file() {
+        return Ok(vec![directory.to_path_buf()]);
+    }
+    if IGNORE_MARKER_FILES
+        .iter()
+        .any(|marker_file| directory.join(marker_file).exists())
+    {
+        debug!("Skipping ignored directory {:?}", directory);
+        return Ok(Vec::new());
+    }
+    let mut subdirectories = std::fs::read_dir(directory)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    subdirectories.retain(|path| {
+        path.is_dir()
+            && !path
+                .file_name()
+                .is_some_and(|file_name| file_name.to_string_lossy().starts_with('.'))
+    });
+    subdirectories.sort();
+    let mut paths_to_packages = Vec::new();
+    for subdirectory in subdirectories {
+        paths_to_packages.append(&mut find_packages(&subdirectory)?);
+    }
+    Ok(paths_to_packages)
+}
+
+pub fn parse_package(file: std::fs::File) -> Result<Package> {
+    let date = format_date(file.metadata()?.modified()?);
+    let package_element = Element::parse(file)?;
+    let name = get_required_text(&package_element, XML_TAG_NAME)?;
+    let version = get_required_text(&package_element, XML_TAG_VERSION)?;
+    let description = get_texts(&package_element, XML_TAG_DESCRIPTION)
+        .into_iter()
+        .next()
+        .unwrap_or_default();
+    let maintainers = get_children(&package_element, XML_TAG_MAINTAINER)
+        .into_iter()
+        .map(|element| Person {
+            name: get_text(element),
+            email: element.attributes.get(XML_ATTRIBUTE_EMAIL).cloned(),
+        })
+        .collect::<Vec<_>>();
+    let licenses = get_texts(&package_element, XML_TAG_LICENSE);
+    let mut dependencies: Vec<String> = Vec::new();
+    for dependency in get_texts(&package_element, XML_TAG_DEPEND)
+        .into_iter()
+        .chain(get_texts(&package_element, XML_TAG_BUILD_DEPEND))
+    {
+        if !dependencies.contains(&dependency) {
+            dependencies.push(dependency);
+        }
+    }
+    Ok(Package::new(
+        &name,
+        &version,
+        &description,
+        &maintainers,
+        &licenses,
+        &dependencies,
+        &date,
+    ))
+}
+
+// yyyy-MM-dd in UTC, computed from the days since 1970-01-01 (proleptic Gregorian calendar)
+fn format_date(time: std::time::SystemTime) -> String {
+    let seconds = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+fn get_required_text(parent: &Element, name: &str) -> Result<String> {
+    get_texts(parent, name)
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("No \"{name}\" element found in \"{}\" element", parent.name).into())
+}
+
+fn get_texts(parent: &Element, name: &str) -> Vec<String> {
+    get_children(parent, name).into_iter().map(get_text).collect()
+}
+
+// Line breaks and indentation of the manifest are not part of the value
+fn get_text(element: &Element) -> String {
+    element
+        .get_text()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn get_children<'a>(parent: &'a Element, name: &str) -> Vec<&'a Element> {
+    parent
+        .children
+        .iter()
+        .filter_map(|child| child.as_element())
+        .filter(|child| child.name == name)
+        .collect()
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+use log::{debug, warn};
+
+use crate::business::error::Result;
+use crate::business::msg_converter::bundled_interfaces::BundledInterfaces;
+use crate::business::msg_converter::{
+    action_reader, idl_reader, msg_reader, package_reader, srv_reader,
+};
+use crate::core::idl::Interface;
+use crate::core::msg::{BaseType, Reference};
+use crate::core::{action, idl, msg, srv};
+
+const AMENT_PREFIX_PATH: &str = "AMENT_PREFIX_PATH";
+pub(super) const SHARE_DIRECTORY: &str = "share";
+pub(super) const AMENT_INDEX_DIRECTORY: &str = "share/ament_index/resource_index";
+pub(super) const ROSIDL_INTERFACES_RESOURCE_TYPE: &str = "rosidl_interfaces";
+
+// An interface file together with the package it belongs to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dependency {
+    pub package_name: String,
+    pub path_to_file: PathBuf,
+}
+
+// Where the packages of referenced messages are searched. Packages in the
+// source directories shadow installed ones, earlier paths shadow later ones.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchPaths {
+    // Searched for packages like a colcon workspace
+    pub source_directories: Vec<String>,
+    // Install trees like the ones in AMENT_PREFIX_PATH, whose packages are listed in the ament index
+    pub prefixes: Vec<String>,
+    // The definitions of common_interfaces embedded into the converter are searched last
+    pub bundled_interfaces: bool,
+}
+
+impl SearchPaths {
+    // The prefixes of the sourced ROS 2 environment come after the given ones
+    pub fn new(
+        source_directories: &[String],
+        prefixes: &[String],
+        bundled_interfaces: bool,
+    ) -> Self {
+        let mut prefixes = prefixes.to_vec();
+        if let Some(ament_prefix_path) = env::var_os(AMENT_PREFIX_PATH) {
+            prefixes.extend(
+                env::split_paths(&ament_prefix_path)
+                    .filter(|prefix| !prefix.as_os_str().is_empty())
+                    .map(|prefix| prefix.to_string_lossy().to_string()),
+            );
+        }
+        Self {
+            source_directories: source_directories.to_vec(),
+            prefixes,
+            bundled_interfaces,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KnownPackage {
+    path_to_package: PathBuf,
+    // Files relative to the package directory like "msg/Header.msg", only known for installed packages
+    interface_files: Option<Vec<String>>,
+}
+
+// Resolves references like "geometry_msgs/Pose" or a sibling "Header"
+// to the message files of the packages found in the search paths
+#[derive(Debug)]
+pub struct ReferenceResolver {
+    packages: HashMap<String, KnownPackage>,
+    // Keeps the extracted files as long as the resolved paths are used
+    _bundled_interfaces: Option<BundledInterfaces>,
+}
+
+impl ReferenceResolver {
+    pub fn new(search_paths: &SearchPaths) -> Result<Self> {
+        let mut packages: HashMap<String, KnownPackage> = HashMap::new();
+        for source_directory in &search_paths.source_directories {
+            for path_to_package in package_reader::find_packages(Path::new(source_directory))? {
+                let path_to_package_file = path_to_package.join(crate::core::package::FILE_NAME);
+                let package = package_reader::read(&path_to_package_file.to_string_lossy())?;
+                if let Some(known_package) = packages.get(package.name()) {
+                    warn!(
+                        "Package {:?} in {:?} is shadowed by {:?}",
+                        package.name(),
+                        path_to_package,
+                        known_package.path_to_package
+                    );
+                    continue;
+                }
+                packages.insert(
+                    package.name().to_string(),
+                    KnownPackage {
+                        path_to_package,
+                        interface_files: None,
+                    },
+                );
+            }
+        }
+        for prefix in &search_paths.prefixes {
+            add_installed_packages(&mut packages, Path::new(prefix))?;
+        }
+        let bundled_interfaces = if search_paths.bundled_interfaces {
+            let bundled_interfaces = BundledInterfaces::extract()?;
+            add_installed_packages(&mut packages, bundled_interfaces.prefix())?;
+            Some(bundled_interfaces)
+        } else {
+            None
+        };
+        debug!("packages: {:?}", packages);
+        Ok(Self {
+            packages,
+            _bundled_interfaces: bundled_interfaces,
+        })
+    }
+
+    // Relative references point to a message of the package of the referencing file
+    pub fn resolve(&self, reference: &Reference, source: &Dependency) -> Option<Dependency> {
+        let (package_name, file) = match reference {
+            Reference::Relative { file } => (source.package_name.as_str(), file),
+            Reference::Absolute { package, file } => (package.as_str(), file),
+        };
+        let file_name = format!("{file}.{}", msg::INTERFACE_KIND);
+        let mut candidates: Vec<PathBuf> = self
+            .packages
+            .get(package_name)
+            .map(|known_package| known_package.candidates(file))
+            .unwrap_or_default();
+        if package_name == source.package_name {
+            // Next to the file or in the "msg" folder next to its "srv" or "action" folder
+            if let Some(directory) = source.path_to_file.parent() {
+                candidates.push(directory.join(&file_name));
+                if let Some(path_to_package) = directory.parent() {
+                    candidates.push(path_to_package.join(msg::INTERFACE_KIND).join(&file_name));
+                }
+            }
+        }
+        candidates
+            .into_iter()
+            .find(|path_to_file| path_to_file.is_file())
+            .map(|path_to_file| Dependency {
+                package_name: package_name.to_string(),
+                path_to_file,
+            })
+    }
+
+    // Returns every message the sources depend on directly or indirectly without the sources
+    // themselves. Each message comes after the messages it depends on.
+    pub fn resolve_closure(&self, sources: &[Dependency]) -> Result<Vec<Dependency>> {
+        let mut closure: Vec<Dependency> = Vec::new();
+        let mut unresolved: Vec<String> = Vec::new();
+        for source in sources {
+            self.visit(source, &mut Vec::new(), &mut closure, &mut unresolved)?;
+        }
+        if !unresolved.is_empty() {
+            return Err(format!("Unresolved references: {}", unresolved.join(", ")).into());
+        }
+        closure.retain(|dependency| {
+            !sources
+                .iter()
+                .any(|source| is_same_file(&source.path_to_file, &dependency.path_to_file))
+        });
+        Ok(closure)
+    }
+
+    // Depth-first search, which adds a message after all of its dependencies
+    fn visit(
+        &self,
+        dependency: &Dependency,
+        path: &mut Vec<PathBuf>,
+        closure: &mut Vec<Dependency>,
+        unresolved: &mut Vec<String>,
+    ) -> Result<()> {
+        if closure
+            .iter()
+            .any(|known| is_same_file(&known.path_to_file, &dependency.path_to_file))
+        {
+            return Ok(());
+        }
+        if path
+            .iter()
+            .any(|visited| is_same_file(visited, &dependency.path_to_file))
+        {
+            return Err(format!(
+                "Cyclic reference found in {:?}",
+                dependency.path_to_file
+            )
+            .into());
+        }
+        path.push(dependency.path_to_file.clone());
+        for reference in read_references(&dependency.path_to_file)? {
+            match self.resolve(&reference, dependency) {
+                Some(referenced) => self.visit(&referenced, path, closure, unresolved)?,
+                None => unresolved.push(format!(
+                    "{} in {:?}",
+                    reference_as_string(&reference, &dependency.package_name),
+                    dependency.path_to_file
+                )),
+            }
+        }
+        path.pop();
+        closure.push(dependency.clone());
+        Ok(())
+    }
+}
+
+impl KnownPackage {
+    // An installed message may only be listed as IDL file, which rosidl generates for every message
+    fn candidates(&self, file: &str) -> Vec<PathBuf> {
+        let Some(interface_files) = &self.interface_files else {
+            let file_name = format!("{file}.{}", msg::INTERFACE_KIND);
+            return vec![self
+                .path_to_package
+                .join(msg::INTERFACE_KIND)
+                .join(file_name)];
+        };
+        [msg::INTERFACE_KIND, idl::FILE_EXTENSION]
+            .iter()
+            .map(|extension| format!("{}/{file}.{extension}", msg::INTERFACE_KIND))
+            .filter(|interface_file| interface_files.contains(interface_file))
+            .map(|interface_file| self.path_to_package.join(interface_file))
+            .collect()
+    }
+}
+
+fn add_installed_packages(
+    packages: &mut HashMap<String, KnownPackage>,
+    prefix: &Path,
+) -> Result<()> {
+    for (package_name, interface_files) in read_ament_index(prefix)? {
+        if let Some(known_package) = packages.get(&package_name) {
+            // Common for overlays, so this is no warning
+            debug!(
+                "Package {:?} in {:?} is shadowed by {:?}",
+                package_name, prefix, known_package.path_to_package
+            );
+            continue;
+        }
+        let path_to_package = prefix.join(SHARE_DIRECTORY).join(&package_name);
+        packages.insert(
+            package_name,
+            KnownPackage {
+                path_to_package,
+                interface_files: Some(interface_files),
+            },
+        );
+    }
+    Ok(())
+}
+
+// Every package of the prefix, which provides interfaces, has a file named like the package
+// in "share/ament_index/resource_index/rosidl_interfaces" listing its interface files
+fn read_ament_index(prefix: &Path) -> Result<Vec<(String, Vec<String>)>> {
+    let path_to_resource_directory = prefix
+        .join(AMENT_INDEX_DIRECTORY)
+        .join(ROSIDL_INTERFACES_RESOURCE_TYPE);
+    if !path_to_resource_directory.is_dir() {
+        warn!(
+            "No ament index with interfaces found in prefix {:?}",
+            prefix
+        );
+        return Ok(Vec::new());
+    }
+    let mut resources: Vec<(String, Vec<String>)> = Vec::new();
+    for entry in fs::read_dir(&path_to_resource_directory)? {
+        let path_to_resource = entry?.path();
+        let package_name = match path_to_resource.file_name().and_then(|name| name.to_str()) {
+            // Hidden files are no resources
+            Some(name) if path_to_resource.is_file() && !name.starts_with('.') => name.to_string(),
+            _ => continue,
+        };
+        let interface_files = fs::read_to_string(&path_to_resource)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+        resources.push((package_name, interface_files));
+    }
+    resources.sort();
+    Ok(resources)
+}
+
+fn read_references(path_to_file: &Path) -> Result<Vec<Reference>> {
+    let path_to_file = path_to_file.to_string_lossy();
+    let interface = match Path::new(path_to_file.as_ref())
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some(srv::INTERFACE_KIND) => Interface::Service(srv_reader::read(&path_to_file)?),
+        Some(action::INTERFACE_KIND) => Interface::Action(action_reader::read(&path_to_file)?),
+        Some(idl::FILE_EXTENSION) => idl_reader::read(&path_to_file)?,
+        _ => Interface::Message(msg_reader::read(&path_to_file)?),
+    };
+    let mut references: Vec<Reference> = Vec::new();
+    for structured_type in interface.structured_types() {
+        for field in structured_type.fields() {
+            if let BaseType::Custom(reference) = field.base_type() {
+                if !references.contains(reference) {
+                    references.push(reference.clone());
+                }
+            }
+        }
+    }
+    Ok(references)
+}
+
+fn reference_as_string(reference: &Reference, package_name: &str) -> String {
+    match reference {
+        Reference::Relative { file } => format!("{package_name}/{file}"),
+        Reference::Absolute { package, file } => format!("{package}/{file}"),
+    }
+}
+
+fn is_same_file(first: &Path, second: &Path) -> bool {
+    match (std::fs::canonicalize(first), std::fs::canonicalize(second)) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => first == second,
+    }
+}
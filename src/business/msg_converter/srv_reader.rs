@@ -0,0 +1,23 @@
+use std::path::Path;
+
+use log::info;
+
+use crate::business::error::Result;
+use crate::business::msg_converter::msg_reader::{
+    parse_file_name, parse_structured_type, split_sections,
+};
+use crate::core::srv::*;
+
+pub fn read(path_to_source_file: &str) -> Result<Service> {
+    info!("Start reading file {:?}", path_to_source_file);
+    let source_file_path = Path::new(path_to_source_file);
+    let file_name = parse_file_name(source_file_path)?;
+    let file_content = std::fs::read_to_string(source_file_path)?;
+    let sections = split_sections(&file_content, 2)?;
+    let request =
+        parse_structured_type(&format!("{file_name}{REQUEST_SUFFIX}"), &sections[0])?;
+    let response =
+        parse_structured_type(&format!("{file_name}{RESPONSE_SUFFIX}"), &sections[1])?;
+    info!("Finished reading file {:?}", path_to_source_file);
+    Ok(Service::new(&file_name, &request, &response))
+}
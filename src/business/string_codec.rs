@@ -0,0 +1,232 @@
+use crate::business::error::Result;
+
+// Both data models store strings with their actual content. The escape
+// sequences of IEC 61131-3 and of ROS 2 (Python-style) only exist in the
+// spelling of a literal and are translated here.
+
+// $hh stands for a byte in a STRING and $hhhh for an UTF-16 code unit in a WSTRING
+pub fn decode_iec_string(value: &str, wide: bool) -> Result<String> {
+    let mut units: Vec<u32> = Vec::new();
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '$' {
+            push_char(&mut units, char, wide);
+            continue;
+        }
+        let escaped = chars
+            .next()
+            .ok_or_else(|| format!("Incomplete escape sequence at the end of \"{value}\""))?;
+        match escaped {
+            '$' | '\'' | '"' => push_char(&mut units, escaped, wide),
+            'L' | 'l' | 'N' | 'n' => push_char(&mut units, '\n', wide),
+            'P' | 'p' => push_char(&mut units, '\u{c}', wide),
+            'R' | 'r' => push_char(&mut units, '\r', wide),
+            'T' | 't' => push_char(&mut units, '\t', wide),
+            first if first.is_ascii_hexdigit() => {
+                let digits = if wide { 4 } else { 2 };
+                let hex: String = std::iter::once(first)
+                    .chain(chars.by_ref().take(digits - 1))
+                    .collect();
+                let unit = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == digits)
+                    .ok_or_else(|| format!("Invalid escape sequence \"${hex}\" in \"{value}\""))?;
+                units.push(unit);
+            }
+            other => {
+                return Err(format!("Invalid escape sequence \"${other}\" in \"{value}\"").into())
+            }
+        }
+    }
+    if wide {
+        let units: Vec<u16> = units.into_iter().map(|unit| unit as u16).collect();
+        String::from_utf16(&units).map_err(|_| format!("Invalid UTF-16 in \"{value}\"").into())
+    } else {
+        let bytes: Vec<u8> = units.into_iter().map(|unit| unit as u8).collect();
+        // Bytes, which are no valid UTF-8, are read as Latin-1
+        Ok(String::from_utf8(bytes)
+            .unwrap_or_else(|error| error.into_bytes().into_iter().map(char::from).collect()))
+    }
+}
+
+fn push_char(units: &mut Vec<u32>, char: char, wide: bool) {
+    if wide {
+        units.extend(char.encode_utf16(&mut [0; 2]).iter().map(|unit| *unit as u32));
+    } else {
+        units.extend(char.encode_utf8(&mut [0; 4]).bytes().map(|byte| byte as u32));
+    }
+}
+
+// "$", line breaks, tabs, form feeds and the quote of the literal ("$'" in a STRING, '$"' in a WSTRING)
+// get their escape sequence, other control characters are written as "$hh" or "$hhhh"
+pub fn encode_iec_string(value: &str, wide: bool) -> String {
+    let mut result = String::new();
+    for char in value.chars() {
+        match char {
+            '$' => result.push_str("$$"),
+            '\n' => result.push_str("$L"),
+            '\r' => result.push_str("$R"),
+            '\t' => result.push_str("$T"),
+            '\u{c}' => result.push_str("$P"),
+            '\'' if !wide => result.push_str("$'"),
+            '"' if wide => result.push_str("$\""),
+            _ if char.is_control() => push_hex_escape(&mut result, char, wide),
+            _ => result.push(char),
+        }
+    }
+    result
+}
+
+fn push_hex_escape(result: &mut String, char: char, wide: bool) {
+    if wide {
+        for unit in char.encode_utf16(&mut [0; 2]) {
+            result.push_str(&format!("${unit:04X}"));
+        }
+    } else {
+        for byte in char.encode_utf8(&mut [0; 4]).bytes() {
+            result.push_str(&format!("${byte:02X}"));
+        }
+    }
+}
+
+// Unknown escape sequences keep their backslash like in Python
+pub fn decode_ros_string(value: &str) -> Result<String> {
+    let mut result = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            result.push(char);
+            continue;
+        }
+        let escaped = chars
+            .next()
+            .ok_or_else(|| format!("Incomplete escape sequence at the end of \"{value}\""))?;
+        match escaped {
+            '\\' | '\'' | '"' => result.push(escaped),
+            'n' => result.push('\n'),
+            'r' => result.push('\r'),
+            't' => result.push('\t'),
+            'a' => result.push('\u{7}'),
+            'b' => result.push('\u{8}'),
+            'f' => result.push('\u{c}'),
+            'v' => result.push('\u{b}'),
+            'x' | 'u' | 'U' => {
+                let digits = match escaped {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let hex: String = chars.by_ref().take(digits).collect();
+                let char = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == digits)
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        format!("Invalid escape sequence \"\\{escaped}{hex}\" in \"{value}\"")
+                    })?;
+                result.push(char);
+            }
+            '0'..='7' => {
+                let mut octal = escaped.to_string();
+                while octal.len() < 3 && chars.peek().is_some_and(|c| ('0'..='7').contains(c)) {
+                    octal.extend(chars.next());
+                }
+                let code = u32::from_str_radix(&octal, 8).unwrap_or_default();
+                result.extend(char::from_u32(code));
+            }
+            other => {
+                result.push('\\');
+                result.push(other);
+            }
+        }
+    }
+    Ok(result)
+}
+
+pub fn encode_ros_string(value: &str, quote: char) -> String {
+    let mut result = String::new();
+    for char in value.chars() {
+        match char {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ if char == quote => {
+                result.push('\\');
+                result.push(char);
+            }
+            _ if char.is_control() && (char as u32) <= 0xFF => {
+                result.push_str(&format!("\\x{:02x}", char as u32))
+            }
+            _ if char.is_control() => result.push_str(&format!("\\u{:04x}", char as u32)),
+            _ => result.push(char),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_escapes_of_iec_strings() {
+        assert_eq!(decode_iec_string("A$41$0A", false).unwrap(), "AA\n");
+        assert_eq!(decode_iec_string("$C3$A4", false).unwrap(), "ä");
+        assert_eq!(decode_iec_string("$03A9mega", true).unwrap(), "Ωmega");
+        assert_eq!(decode_iec_string("$D83D$DE00", true).unwrap(), "😀");
+        assert_eq!(encode_iec_string("\u{1}", false), "$01");
+        assert_eq!(encode_iec_string("\u{1}", true), "$0001");
+    }
+
+    #[test]
+    fn quotes_and_line_breaks_of_iec_strings() {
+        assert_eq!(decode_iec_string("it$'s$L$$5", false).unwrap(), "it's\n$5");
+        assert_eq!(encode_iec_string("it's \"x\"", false), "it$'s \"x\"");
+        assert_eq!(encode_iec_string("it's \"x\"", true), "it's $\"x$\"");
+    }
+
+    #[test]
+    fn invalid_iec_escapes_are_rejected() {
+        assert!(decode_iec_string("$Q", false).is_err());
+        assert!(decode_iec_string("end$", false).is_err());
+        assert!(decode_iec_string("$4", false).is_err());
+        assert!(decode_iec_string("$04G1", true).is_err());
+        assert!(decode_iec_string("$D83D", true).is_err());
+    }
+
+    #[test]
+    fn invalid_utf8_falls_back_to_latin1() {
+        assert_eq!(decode_iec_string("$E4$FC", false).unwrap(), "äü");
+        assert_eq!(decode_iec_string("Gr$F6$DFe", false).unwrap(), "Größe");
+    }
+
+    #[test]
+    fn python_escapes_of_ros_strings() {
+        assert_eq!(decode_ros_string(r"\101\0\7").unwrap(), "A\0\u{7}");
+        assert_eq!(decode_ros_string(r"\1234").unwrap(), "S4");
+        assert_eq!(decode_ros_string(r"\x41Ω\U0001F600").unwrap(), "AΩ😀");
+        assert_eq!(decode_ros_string(r"a\tb\nc").unwrap(), "a\tb\nc");
+        assert_eq!(decode_ros_string(r"\d").unwrap(), r"\d");
+    }
+
+    #[test]
+    fn invalid_ros_escapes_are_rejected() {
+        assert!(decode_ros_string(r"\x4").is_err());
+        assert!(decode_ros_string(r"\u03G9").is_err());
+        assert!(decode_ros_string(r"\UFFFFFFFF").is_err());
+        assert!(decode_ros_string("end\\").is_err());
+    }
+
+    #[test]
+    fn only_the_own_quote_of_ros_strings_is_escaped() {
+        assert_eq!(encode_ros_string(r#"it's "x""#, '"'), r#"it's \"x\""#);
+        assert_eq!(encode_ros_string(r#"it's "x""#, '\''), r#"it\'s "x""#);
+        assert_eq!(encode_ros_string("a\\b\n\u{1}", '"'), r"a\\b\n\x01");
+        let value = "say \"hi\"\\";
+        assert_eq!(
+            decode_ros_string(&encode_ros_string(value, '"')).unwrap(),
+            value
+        );
+    }
+}
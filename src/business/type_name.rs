@@ -0,0 +1,469 @@
+use crate::business::error::Result;
+use crate::core::msg::{self, Reference};
+use crate::core::{action, dtp, srv};
+
+// Types generated from ROS 2 interfaces are named by a template, by default "ROS2_<package>_<kind>_<name>".
+// As the package may be stripped of underscores, spaces and dashes or missing at all, a name alone
+// does not reveal its package. That is why the ROS 2 names are kept in attributes, which take
+// precedence over the type name.
+
+pub const DEFAULT_TEMPLATE: &str = "ROS2_{pkg}_{kind}_{Type}";
+// 4diac IDE 3 places types into packages like "ros2::std_msgs", the file is named like the last segment
+const PACKAGE_SEPARATOR: &str = "::";
+const INTERFACE_KINDS: [&str; 3] = [
+    msg::INTERFACE_KIND,
+    srv::INTERFACE_KIND,
+    action::INTERFACE_KIND,
+];
+// Keeps the IEC 61499 name of a type, which was renamed for ROS 2, e.g. "@IEC61499_TypeName(Lager_Platz)"
+const TYPE_NAME_ANNOTATION: &str = "@IEC61499_TypeName";
+// The sections of services and actions including the ones rosidl derives implicitly
+const SERVICE_SECTION_SUFFIXES: [&str; 2] = [srv::REQUEST_SUFFIX, srv::RESPONSE_SUFFIX];
+const ACTION_SECTION_SUFFIXES: [&str; 8] = [
+    "_SendGoal_Request",
+    "_SendGoal_Response",
+    "_GetResult_Request",
+    "_GetResult_Response",
+    "_FeedbackMessage",
+    action::GOAL_SUFFIX,
+    action::RESULT_SUFFIX,
+    action::FEEDBACK_SUFFIX,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Placeholder {
+    // "foerderband_msgs"
+    Package,
+    // "foerderbandmsgs"
+    EncodedPackage,
+    // "FoerderbandMsgs"
+    PascalCasePackage,
+    // "msg", "srv" or "action"
+    InterfaceKind,
+    // "Transport_Goal"
+    Type,
+}
+
+const PLACEHOLDERS: [(&str, Placeholder); 5] = [
+    ("package", Placeholder::Package),
+    ("pkg", Placeholder::EncodedPackage),
+    ("Pkg", Placeholder::PascalCasePackage),
+    ("kind", Placeholder::InterfaceKind),
+    ("Type", Placeholder::Type),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+// The name of a generated type, e.g. "ROS2_{pkg}_{kind}_{Type}", "{Pkg}{Type}_T" or "ros2::{package}::{Type}"
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeNameTemplate {
+    segments: Vec<Segment>,
+}
+
+// The parts of a type name matching a template, the package as it appears in the name
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodedTypeName<'a> {
+    package: Option<(Placeholder, &'a str)>,
+    pub interface_kind: Option<&'a str>,
+    pub name: &'a str,
+}
+
+impl TypeNameTemplate {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            if start > 0 {
+                segments.push(Segment::Text(rest[..start].to_string()));
+            }
+            let end = rest[start..].find('}').ok_or_else(|| {
+                format!("Unclosed placeholder in type name template \"{template}\"")
+            })? + start;
+            let placeholder_name = &rest[start + 1..end];
+            let placeholder = PLACEHOLDERS
+                .iter()
+                .find(|(name, _)| *name == placeholder_name)
+                .map(|(_, placeholder)| *placeholder)
+                .ok_or_else(|| {
+                    format!(
+                        "Unknown placeholder \"{{{placeholder_name}}}\" in type name template \"{template}\", expected one of {}",
+                        PLACEHOLDERS
+                            .iter()
+                            .map(|(name, _)| format!("{{{name}}}"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+            segments.push(Segment::Placeholder(placeholder));
+            rest = &rest[end + 1..];
+        }
+        if !rest.is_empty() {
+            segments.push(Segment::Text(rest.to_string()));
+        }
+        let type_count = segments
+            .iter()
+            .filter(|segment| **segment == Segment::Placeholder(Placeholder::Type))
+            .count();
+        if type_count != 1 {
+            return Err(format!(
+                "The type name template \"{template}\" has to contain {{Type}} exactly once"
+            )
+            .into());
+        }
+        // The package of a qualified name must not depend on the type
+        if template
+            .rfind(PACKAGE_SEPARATOR)
+            .is_some_and(|separator| template.find("{Type}") < Some(separator))
+        {
+            return Err(format!(
+                "{{Type}} has to follow the last \"{PACKAGE_SEPARATOR}\" of the type name template \"{template}\""
+            )
+            .into());
+        }
+        Ok(Self { segments })
+    }
+
+    pub fn render(&self, package_name: &str, interface_kind: &str, name: &str) -> String {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text.clone(),
+                Segment::Placeholder(placeholder) => {
+                    placeholder.render(package_name, interface_kind, name)
+                }
+            })
+            .collect()
+    }
+
+    // A name may match in several ways, e.g. "{Pkg}{Type}" does not tell where the package
+    // ends. The match belonging to the given package is preferred.
+    pub fn decode<'a>(
+        &self,
+        type_name: &'a str,
+        package_name: &str,
+    ) -> Option<DecodedTypeName<'a>> {
+        let mut matches: Vec<DecodedTypeName> = Vec::new();
+        match_segments(&self.segments, type_name, &mut Vec::new(), &mut matches);
+        if let Some(position) = matches
+            .iter()
+            .position(|decoded| decoded.belongs_to(package_name))
+        {
+            return Some(matches.swap_remove(position));
+        }
+        matches.into_iter().next()
+    }
+}
+
+impl Default for TypeNameTemplate {
+    fn default() -> Self {
+        Self::parse(DEFAULT_TEMPLATE).expect("the default type name template to be valid")
+    }
+}
+
+impl Placeholder {
+    fn render(self, package_name: &str, interface_kind: &str, name: &str) -> String {
+        match self {
+            Placeholder::Package => package_name.to_string(),
+            Placeholder::EncodedPackage => encode_package_name(package_name),
+            Placeholder::PascalCasePackage => to_pascal_case(package_name),
+            Placeholder::InterfaceKind => interface_kind.to_string(),
+            Placeholder::Type => name.to_string(),
+        }
+    }
+
+    fn accepts(self, value: &str) -> bool {
+        let has_separators = value.contains(['_', ' ', '-']);
+        !value.is_empty()
+            && match self {
+                Placeholder::Package => !value.contains(PACKAGE_SEPARATOR),
+                Placeholder::EncodedPackage => !has_separators,
+                Placeholder::PascalCasePackage => {
+                    !has_separators && value.starts_with(|c: char| c.is_uppercase())
+                }
+                Placeholder::InterfaceKind => INTERFACE_KINDS.contains(&value),
+                Placeholder::Type => !value.contains(PACKAGE_SEPARATOR),
+            }
+    }
+}
+
+impl<'a> DecodedTypeName<'a> {
+    // A template without a package matches types of every package
+    pub fn belongs_to(&self, package_name: &str) -> bool {
+        match self.package {
+            Some((placeholder, value)) => placeholder.render(package_name, "", "") == value,
+            None => true,
+        }
+    }
+
+    // Only "{package}" keeps the package name as it is
+    pub fn package_name(&self) -> Option<&'a str> {
+        self.package.map(|(_, value)| value)
+    }
+
+    pub fn is_package_name_complete(&self) -> bool {
+        matches!(self.package, Some((Placeholder::Package, _)))
+    }
+}
+
+// Collects every way the segments match the whole name, placeholders take as few characters as possible first
+fn match_segments<'a>(
+    segments: &[Segment],
+    type_name: &'a str,
+    bindings: &mut Vec<(Placeholder, &'a str)>,
+    matches: &mut Vec<DecodedTypeName<'a>>,
+) {
+    let Some((segment, remaining_segments)) = segments.split_first() else {
+        if type_name.is_empty() {
+            matches.extend(create_decoded_type_name(bindings));
+        }
+        return;
+    };
+    match segment {
+        Segment::Text(text) => {
+            if let Some(rest) = type_name.strip_prefix(text.as_str()) {
+                match_segments(remaining_segments, rest, bindings, matches);
+            }
+        }
+        Segment::Placeholder(placeholder) => {
+            for end in (1..=type_name.len()).filter(|end| type_name.is_char_boundary(*end)) {
+                let value = &type_name[..end];
+                let is_consistent = bindings
+                    .iter()
+                    .all(|(bound, bound_value)| bound != placeholder || *bound_value == value);
+                if !placeholder.accepts(value) || !is_consistent {
+                    continue;
+                }
+                bindings.push((*placeholder, value));
+                match_segments(remaining_segments, &type_name[end..], bindings, matches);
+                bindings.pop();
+            }
+        }
+    }
+}
+
+fn create_decoded_type_name<'a>(
+    bindings: &[(Placeholder, &'a str)],
+) -> Option<DecodedTypeName<'a>> {
+    let find = |wanted: &[Placeholder]| {
+        bindings
+            .iter()
+            .find(|(placeholder, _)| wanted.contains(placeholder))
+            .copied()
+    };
+    Some(DecodedTypeName {
+        package: find(&[
+            Placeholder::Package,
+            Placeholder::EncodedPackage,
+            Placeholder::PascalCasePackage,
+        ]),
+        interface_kind: find(&[Placeholder::InterfaceKind]).map(|(_, value)| value),
+        name: find(&[Placeholder::Type])?.1,
+    })
+}
+
+pub fn encode_package_name(package_name: &str) -> String {
+    package_name
+        .replace("_", "")
+        .replace(" ", "")
+        .replace("-", "")
+}
+
+// ROS 2 type names have to match "^[A-Z][A-Za-z0-9]*$", while IEC 61499 names may contain underscores
+// and start lowercase, e.g. "Lager_Platz" becomes "LagerPlatz". Services and actions keep the suffix of
+// their sections, e.g. "Lager_Platz_Request" becomes "LagerPlatz_Request".
+pub fn to_ros_type_name(name: &str, interface_kind: &str) -> String {
+    let suffixes: &[&str] = match interface_kind {
+        srv::INTERFACE_KIND => &SERVICE_SECTION_SUFFIXES,
+        action::INTERFACE_KIND => &ACTION_SECTION_SUFFIXES,
+        _ => &[],
+    };
+    let (name, suffix) = suffixes
+        .iter()
+        .find_map(|suffix| Some((name.strip_suffix(suffix)?, *suffix)))
+        .unwrap_or((name, ""));
+    let ros_name: String = to_pascal_case(name)
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect();
+    // Names starting with a digit or without any valid character get a prefix
+    match ros_name.starts_with(|char: char| char.is_ascii_uppercase()) {
+        true => format!("{ros_name}{suffix}"),
+        false => format!("T{ros_name}{suffix}"),
+    }
+}
+
+// The name of an interface without the suffix of a section, "^[A-Z][A-Za-z0-9]*$"
+pub fn is_valid_ros_type_name(name: &str) -> bool {
+    name.starts_with(|char: char| char.is_ascii_uppercase())
+        && name.chars().all(|char| char.is_ascii_alphanumeric())
+}
+
+pub fn encode_type_name_annotation(iec_name: &str) -> String {
+    format!("{TYPE_NAME_ANNOTATION}({iec_name})")
+}
+
+// Returns the IEC 61499 name of "@IEC61499_TypeName(<name>)" anywhere in the comment
+pub fn decode_type_name_annotation(comment: &str) -> Option<&str> {
+    let start = comment.find(&format!("{TYPE_NAME_ANNOTATION}("))? + TYPE_NAME_ANNOTATION.len() + 1;
+    let end = comment[start..].find(')')? + start;
+    let iec_name = &comment[start..end];
+    let is_valid = !iec_name.is_empty()
+        && iec_name
+            .chars()
+            .all(|char| char.is_alphanumeric() || char == '_' || char == ':');
+    is_valid.then_some(iec_name)
+}
+
+// "foerderband_msgs" becomes "FoerderbandMsgs"
+fn to_pascal_case(package_name: &str) -> String {
+    package_name
+        .split(['_', ' ', '-'])
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .into_iter()
+                .flat_map(char::to_uppercase)
+                .chain(chars)
+        })
+        .collect()
+}
+
+// Splits "ros2::std_msgs::Header" into the package "ros2::std_msgs" and the name "Header"
+pub fn split_qualified_name(type_name: &str) -> (Option<&str>, &str) {
+    match type_name.rsplit_once(PACKAGE_SEPARATOR) {
+        Some((package_name, name)) => (Some(package_name), name),
+        None => (None, type_name),
+    }
+}
+
+// The name other data types use to reference the data type
+pub fn qualified_name(data_type: &dtp::DataType) -> String {
+    match data_type
+        .compiler_info()
+        .as_ref()
+        .and_then(|compiler_info| compiler_info.package_name.as_deref())
+    {
+        Some(package_name) => format!("{package_name}{PACKAGE_SEPARATOR}{}", data_type.name()),
+        None => data_type.name().to_string(),
+    }
+}
+
+// The name rosidl gives the type, e.g. "std_msgs/msg/Header"
+pub fn encode_full_name(package_name: &str, interface_kind: &str, name: &str) -> String {
+    format!("{package_name}/{interface_kind}/{name}")
+}
+
+pub fn decode_full_name(full_name: &str) -> Result<(&str, &str, &str)> {
+    match full_name.split('/').collect::<Vec<_>>()[..] {
+        [package_name, interface_kind, name]
+            if !package_name.is_empty()
+                && INTERFACE_KINDS.contains(&interface_kind)
+                && !name.is_empty() =>
+        {
+            Ok((package_name, interface_kind, name))
+        }
+        _ => Err(format!("Invalid type name \"{full_name}\"").into()),
+    }
+}
+
+// The reference as written in a MSG file, e.g. "geometry_msgs/Pose" or "Header"
+pub fn encode_reference(reference: &Reference) -> String {
+    match reference {
+        Reference::Relative { file } => file.clone(),
+        Reference::Absolute { package, file } => format!("{package}/{file}"),
+    }
+}
+
+pub fn decode_reference(value: &str) -> Result<Reference> {
+    match value.split('/').collect::<Vec<_>>()[..] {
+        [file] if !file.is_empty() => Ok(Reference::Relative {
+            file: file.to_string(),
+        }),
+        [package, file] | [package, msg::INTERFACE_KIND, file]
+            if !package.is_empty() && !file.is_empty() =>
+        {
+            Ok(Reference::Absolute {
+                package: package.to_string(),
+                file: file.to_string(),
+            })
+        }
+        _ => Err(format!("Invalid reference \"{value}\"").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn underscored_iec_name_round_trips() {
+        let ros_name = to_ros_type_name("Lager_Platz", msg::INTERFACE_KIND);
+        assert_eq!(ros_name, "LagerPlatz");
+        let comment = format!(
+            "{}. Ein Platz im Lager",
+            encode_type_name_annotation("Lager_Platz")
+        );
+        assert_eq!(decode_type_name_annotation(&comment), Some("Lager_Platz"));
+    }
+
+    #[test]
+    fn valid_ros_type_names_are_kept() {
+        assert_eq!(
+            to_ros_type_name("Iec61499Motor", msg::INTERFACE_KIND),
+            "Iec61499Motor"
+        );
+        assert_eq!(
+            to_ros_type_name("antrieb_zustand", msg::INTERFACE_KIND),
+            "AntriebZustand"
+        );
+    }
+
+    #[test]
+    fn sanitized_names_are_valid() {
+        for name in ["Lager_Platz", "motor_Result", "3D_Punkt", "_", "Ä_Typ"] {
+            let ros_name = to_ros_type_name(name, msg::INTERFACE_KIND);
+            assert!(is_valid_ros_type_name(&ros_name), "{name} -> {ros_name}");
+        }
+        assert!(!is_valid_ros_type_name("Lager_Platz"));
+        assert!(!is_valid_ros_type_name("lagerPlatz"));
+    }
+
+    #[test]
+    fn section_suffixes_are_kept() {
+        assert_eq!(
+            to_ros_type_name("Motor_Result", msg::INTERFACE_KIND),
+            "MotorResult"
+        );
+        assert_eq!(
+            to_ros_type_name("Motor_Result", action::INTERFACE_KIND),
+            "Motor_Result"
+        );
+        assert_eq!(
+            to_ros_type_name("Lager_Platz_SendGoal_Request", action::INTERFACE_KIND),
+            "LagerPlatz_SendGoal_Request"
+        );
+        assert_eq!(
+            to_ros_type_name("einlagern_Request", srv::INTERFACE_KIND),
+            "Einlagern_Request"
+        );
+    }
+
+    #[test]
+    fn qualified_type_name_annotation_is_decoded() {
+        assert_eq!(
+            decode_type_name_annotation(
+                "@IEC61499_StartIndex(1), @IEC61499_TypeName(ros2::Lager_Platz)."
+            ),
+            Some("ros2::Lager_Platz")
+        );
+        assert_eq!(
+            decode_type_name_annotation("@IEC61499_TypeName(). Leer"),
+            None
+        );
+    }
+}
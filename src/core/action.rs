@@ -0,0 +1,42 @@
+use crate::core::msg::StructuredType;
+
+pub const INTERFACE_KIND: &str = "action";
+pub const GOAL_SUFFIX: &str = "_Goal";
+pub const RESULT_SUFFIX: &str = "_Result";
+pub const FEEDBACK_SUFFIX: &str = "_Feedback";
+
+#[derive(Debug, Clone)]
+pub struct Action {
+    name: String,
+    goal: StructuredType,
+    result: StructuredType,
+    feedback: StructuredType,
+}
+
+impl Action {
+    pub fn new(
+        name: &str,
+        goal: &StructuredType,
+        result: &StructuredType,
+        feedback: &StructuredType,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            goal: goal.clone(),
+            result: result.clone(),
+            feedback: feedback.clone(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn goal(&self) -> &StructuredType {
+        &self.goal
+    }
+    pub fn result(&self) -> &StructuredType {
+        &self.result
+    }
+    pub fn feedback(&self) -> &StructuredType {
+        &self.feedback
+    }
+}
@@ -4,19 +4,36 @@
 pub struct DataType {
     name: String,
     comment: Option<String>,
-    // identification: Option<Identification>,
-    // version_info: Vec<VersionInfo>,
-    // compiler_info: Option<CompilerInfo>,
+    identification: Option<Identification>,
+    version_info: Vec<VersionInfo>,
+    compiler_info: Option<CompilerInfo>,
     // asn1_tag: Option<ASN1Tag>,
     data_type_kind: DataTypeKind,
+    attributes: Vec<Attribute>,
+    foreign_xml: ForeignXml,
 }
 
 impl DataType {
-    pub fn new(name: &str, comment: &Option<String>, data_type_kind: &DataTypeKind) -> Self {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: &str,
+        comment: &Option<String>,
+        identification: &Option<Identification>,
+        version_info: &[VersionInfo],
+        compiler_info: &Option<CompilerInfo>,
+        data_type_kind: &DataTypeKind,
+        attributes: &[Attribute],
+        foreign_xml: &ForeignXml,
+    ) -> Self {
         Self {
             name: name.to_string(),
             comment: comment.clone(),
+            identification: identification.clone(),
+            version_info: version_info.to_vec(),
+            compiler_info: compiler_info.clone(),
             data_type_kind: data_type_kind.clone(),
+            attributes: attributes.to_vec(),
+            foreign_xml: foreign_xml.clone(),
         }
     }
     pub fn comment(&self) -> &Option<String> {
@@ -25,45 +42,265 @@ impl DataType {
     pub fn name(&self) -> &str {
         &self.name
     }
+    pub fn identification(&self) -> &Option<Identification> {
+        &self.identification
+    }
+    pub fn version_info(&self) -> &Vec<VersionInfo> {
+        &self.version_info
+    }
+    pub fn compiler_info(&self) -> &Option<CompilerInfo> {
+        &self.compiler_info
+    }
     pub fn data_type_kind(&self) -> &DataTypeKind {
         &self.data_type_kind
     }
+    pub fn attributes(&self) -> &Vec<Attribute> {
+        &self.attributes
+    }
+    pub fn foreign_xml(&self) -> &ForeignXml {
+        &self.foreign_xml
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Identification {
+    pub standard: Option<String>,
+    pub classification: Option<String>,
+    pub application_domain: Option<String>,
+    pub function: Option<String>,
+    pub identification_type: Option<String>,
+    pub description: Option<String>,
 }
 
+#[derive(Clone, Debug)]
+pub struct VersionInfo {
+    pub organization: Option<String>,
+    pub version: String,
+    pub author: String,
+    pub date: String,
+    pub remarks: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CompilerInfo {
+    // The package of 4diac IDE 3 like "ros2::std_msgs"
+    pub package_name: Option<String>,
+    pub header: Option<String>,
+    pub classdef: Option<String>,
+    pub compilers: Vec<Compiler>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Compiler {
+    pub language: String,
+    pub vendor: String,
+    pub product: String,
+    pub version: String,
+}
+
+// Named after the elements of the DTP file
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug)]
 pub enum DataTypeKind {
-    // DirectlyDerivedType(DirectlyDerivedType),
-    // EnumeratedType(EnumeratedType),
-    // SubrangeType(SubrangeType),
-    // ArrayType(ArrayType),
+    DirectlyDerivedType(DirectlyDerivedType),
+    EnumeratedType(EnumeratedType),
+    SubrangeType(SubrangeType),
+    ArrayType(ArrayType),
     StructuredType(StructuredType),
 }
 
 impl DataTypeKind {
     pub fn matches_any<T: AsRef<str>>(str: T) -> bool {
-        match str.as_ref() {
+        matches!(
+            str.as_ref(),
             "StructuredType"
-            // | "DirectlyDerivedType"
-            // | "EnumeratedType"
-            // | "SubrangeType"
-            // | "ArrayType"
-            => true,
-            _ => false,
+                | "DirectlyDerivedType"
+                | "EnumeratedType"
+                | "SubrangeType"
+                | "ArrayType"
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DirectlyDerivedType {
+    base_type: BaseType,
+    initial_value: Option<InitialValue>,
+    comment: Option<String>,
+}
+
+impl DirectlyDerivedType {
+    pub fn new(
+        base_type: &BaseType,
+        initial_value: &Option<InitialValue>,
+        comment: &Option<String>,
+    ) -> Self {
+        Self {
+            base_type: base_type.clone(),
+            initial_value: initial_value.clone(),
+            comment: comment.clone(),
+        }
+    }
+    pub fn base_type(&self) -> &BaseType {
+        &self.base_type
+    }
+    pub fn initial_value(&self) -> &Option<InitialValue> {
+        &self.initial_value
+    }
+    pub fn comment(&self) -> &Option<String> {
+        &self.comment
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnumeratedType {
+    comment: Option<String>,
+    // Name of one of the values
+    initial_value: Option<String>,
+    values: Vec<EnumeratedValue>,
+}
+
+impl EnumeratedType {
+    pub fn new(
+        comment: &Option<String>,
+        initial_value: &Option<String>,
+        values: &[EnumeratedValue],
+    ) -> Self {
+        Self {
+            comment: comment.clone(),
+            initial_value: initial_value.clone(),
+            values: values.to_vec(),
+        }
+    }
+    pub fn comment(&self) -> &Option<String> {
+        &self.comment
+    }
+    pub fn initial_value(&self) -> &Option<String> {
+        &self.initial_value
+    }
+    pub fn values(&self) -> &Vec<EnumeratedValue> {
+        &self.values
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnumeratedValue {
+    name: String,
+    comment: Option<String>,
+}
+
+impl EnumeratedValue {
+    pub fn new(name: &str, comment: &Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            comment: comment.clone(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn comment(&self) -> &Option<String> {
+        &self.comment
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SubrangeType {
+    base_type: BaseType,
+    initial_value: Option<InitialValue>,
+    comment: Option<String>,
+    subrange: Subrange,
+}
+
+impl SubrangeType {
+    pub fn new(
+        base_type: &BaseType,
+        initial_value: &Option<InitialValue>,
+        comment: &Option<String>,
+        subrange: &Subrange,
+    ) -> Self {
+        Self {
+            base_type: base_type.clone(),
+            initial_value: initial_value.clone(),
+            comment: comment.clone(),
+            subrange: *subrange,
+        }
+    }
+    pub fn base_type(&self) -> &BaseType {
+        &self.base_type
+    }
+    pub fn initial_value(&self) -> &Option<InitialValue> {
+        &self.initial_value
+    }
+    pub fn comment(&self) -> &Option<String> {
+        &self.comment
+    }
+    pub fn subrange(&self) -> &Subrange {
+        &self.subrange
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArrayType {
+    base_type: BaseType,
+    // One subrange per dimension
+    subranges: Vec<Subrange>,
+    initial_values: Option<InitialValue>,
+    comment: Option<String>,
+}
+
+impl ArrayType {
+    pub fn new(
+        base_type: &BaseType,
+        subranges: &[Subrange],
+        initial_values: &Option<InitialValue>,
+        comment: &Option<String>,
+    ) -> Self {
+        Self {
+            base_type: base_type.clone(),
+            subranges: subranges.to_vec(),
+            initial_values: initial_values.clone(),
+            comment: comment.clone(),
         }
     }
+    pub fn base_type(&self) -> &BaseType {
+        &self.base_type
+    }
+    pub fn subranges(&self) -> &Vec<Subrange> {
+        &self.subranges
+    }
+    pub fn initial_values(&self) -> &Option<InitialValue> {
+        &self.initial_values
+    }
+    pub fn comment(&self) -> &Option<String> {
+        &self.comment
+    }
+}
+
+// Both limits are inclusive
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Subrange {
+    pub lower_limit: i64,
+    pub upper_limit: i64,
 }
 
 #[derive(Clone, Debug)]
 pub struct StructuredType {
     comment: Option<String>,
     children: Vec<StructuredTypeChild>,
+    foreign_xml: ForeignXml,
 }
 
 impl StructuredType {
-    pub fn new(comment: &Option<String>, children: &[StructuredTypeChild]) -> Self {
+    pub fn new(
+        comment: &Option<String>,
+        children: &[StructuredTypeChild],
+        foreign_xml: &ForeignXml,
+    ) -> Self {
         Self {
             comment: comment.clone(),
             children: children.to_vec(),
+            foreign_xml: foreign_xml.clone(),
         }
     }
     pub fn comment(&self) -> &Option<String> {
@@ -72,17 +309,20 @@ impl StructuredType {
     pub fn children(&self) -> &Vec<StructuredTypeChild> {
         &self.children
     }
+    pub fn foreign_xml(&self) -> &ForeignXml {
+        &self.foreign_xml
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum StructuredTypeChild {
     VarDeclaration(VarDeclaration),
-    // SubrangeVarDeclaration(SubrangeVarDeclaration),
+    SubrangeVarDeclaration(SubrangeVarDeclaration),
 }
 
 impl StructuredTypeChild {
     pub fn matches_any<T: AsRef<str>>(str: T) -> bool {
-        matches!(str.as_ref(), "VarDeclaration")
+        matches!(str.as_ref(), "VarDeclaration" | "SubrangeVarDeclaration")
     }
 }
 
@@ -94,7 +334,8 @@ pub struct VarDeclaration {
     array_size: Option<ArraySize>,
     initial_value: Option<InitialValue>,
     comment: Option<String>,
-    attributes: Vec<Attribute>
+    attributes: Vec<Attribute>,
+    foreign_xml: ForeignXml,
 }
 
 impl VarDeclaration {
@@ -104,7 +345,8 @@ impl VarDeclaration {
         array_size: &Option<ArraySize>,
         initial_value: &Option<InitialValue>,
         comment: &Option<String>,
-        attributes: &Vec<Attribute>
+        attributes: &[Attribute],
+        foreign_xml: &ForeignXml,
     ) -> Self {
         Self {
             name: name.to_string(),
@@ -112,7 +354,8 @@ impl VarDeclaration {
             array_size: array_size.clone(),
             initial_value: initial_value.clone(),
             comment: comment.clone(),
-            attributes: attributes.clone(),
+            attributes: attributes.to_vec(),
+            foreign_xml: foreign_xml.clone(),
         }
     }
     pub fn name(&self) -> &str {
@@ -133,6 +376,79 @@ impl VarDeclaration {
     pub fn attributes(&self) -> &Vec<Attribute> {
         &self.attributes
     }
+    pub fn foreign_xml(&self) -> &ForeignXml {
+        &self.foreign_xml
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SubrangeVarDeclaration {
+    name: String,
+    base_type: BaseType,
+    // Without a subrange the one of the base type applies
+    subrange: Option<Subrange>,
+    initial_value: Option<InitialValue>,
+    comment: Option<String>,
+    attributes: Vec<Attribute>
+}
+
+impl SubrangeVarDeclaration {
+    pub fn new(
+        name: &str,
+        base_type: &BaseType,
+        subrange: &Option<Subrange>,
+        initial_value: &Option<InitialValue>,
+        comment: &Option<String>,
+        attributes: &[Attribute]
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            base_type: base_type.clone(),
+            subrange: *subrange,
+            initial_value: initial_value.clone(),
+            comment: comment.clone(),
+            attributes: attributes.to_vec(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn base_type(&self) -> &BaseType {
+        &self.base_type
+    }
+    pub fn subrange(&self) -> &Option<Subrange> {
+        &self.subrange
+    }
+    pub fn initial_value(&self) -> &Option<InitialValue> {
+        &self.initial_value
+    }
+    pub fn comment(&self) -> &Option<String> {
+        &self.comment
+    }
+    pub fn attributes(&self) -> &Vec<Attribute> {
+        &self.attributes
+    }
+}
+
+// XML attributes and elements, which are not modeled, are kept with their
+// position among the attributes or child elements to write them back unchanged
+#[derive(Clone, Debug, Default)]
+pub struct ForeignXml {
+    pub attributes: Vec<(usize, String, String)>,
+    pub elements: Vec<(usize, ForeignElement)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ForeignElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<ForeignNode>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ForeignNode {
+    Element(ForeignElement),
+    Text(String),
 }
 
 #[derive(Clone, Debug)]
@@ -143,6 +459,8 @@ pub struct Attribute {
     pub comment: Option<String>
 }
 
+// IEC 61131-3 type names
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum BaseType {
     BOOL,
@@ -167,18 +485,19 @@ pub enum BaseType {
     Custom(String),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ArraySize {
     Dynamic,
     Static(Capacity),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Capacity {
     InPlace(usize),
     Shifted(i64, i64),
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum InitialValue {
     BOOL(BoolLiteral),
@@ -200,19 +519,62 @@ pub enum InitialValue {
     STRING(String),
     WSTRING(String),
     Array(Vec<InitialValue>),
+    // Initialisierung einer Struktur, z.B. "(x:=1.0, y:=2.0)"
+    Struct(Vec<StructElement>),
+    // Wert einer Aufzählung, z.B. "RED" oder "Color#RED"
+    Enumerated(String),
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructElement {
+    pub name: String,
+    pub value: InitialValue,
+}
+
+pub const FILE_EXTENSION: &str = "dtp";
+
 pub const XML_TAG_DATA_TYPE: &str = "DataType";
+pub const XML_TAG_IDENTIFICATION: &str = "Identification";
+pub const XML_TAG_VERSION_INFO: &str = "VersionInfo";
+pub const XML_TAG_COMPILER_INFO: &str = "CompilerInfo";
+pub const XML_TAG_COMPILER: &str = "Compiler";
 pub const XML_TAG_STRUCTURED_TYPE: &str = "StructuredType";
+pub const XML_TAG_DIRECTLY_DERIVED_TYPE: &str = "DirectlyDerivedType";
+pub const XML_TAG_ARRAY_TYPE: &str = "ArrayType";
+pub const XML_TAG_ENUMERATED_TYPE: &str = "EnumeratedType";
+pub const XML_TAG_ENUMERATED_VALUE: &str = "EnumeratedValue";
+pub const XML_TAG_SUBRANGE_TYPE: &str = "SubrangeType";
+pub const XML_TAG_SUBRANGE: &str = "Subrange";
 pub const XML_TAG_VAR_DECLARATION: &str = "VarDeclaration";
+pub const XML_TAG_SUBRANGE_VAR_DECLARATION: &str = "SubrangeVarDeclaration";
 pub const XML_TAG_ATTRIBUTE: &str = "Attribute";
 
 pub const XML_ATTRIBUTE_NAME: &str = "Name";
 pub const XML_ATTRIBUTE_TYPE: &str = "Type";
+pub const XML_ATTRIBUTE_BASE_TYPE: &str = "BaseType";
+pub const XML_ATTRIBUTE_LOWER_LIMIT: &str = "LowerLimit";
+pub const XML_ATTRIBUTE_UPPER_LIMIT: &str = "UpperLimit";
 pub const XML_ATTRIBUTE_ARRAY_SIZE: &str = "ArraySize";
 pub const XML_ATTRIBUTE_INITIAL_VALUE: &str = "InitialValue";
+pub const XML_ATTRIBUTE_INITIAL_VALUES: &str = "InitialValues";
 pub const XML_ATTRIBUTE_VALUE: &str = "Value";
 pub const XML_ATTRIBUTE_COMMENT: &str = "Comment";
+pub const XML_ATTRIBUTE_STANDARD: &str = "Standard";
+pub const XML_ATTRIBUTE_CLASSIFICATION: &str = "Classification";
+pub const XML_ATTRIBUTE_APPLICATION_DOMAIN: &str = "ApplicationDomain";
+pub const XML_ATTRIBUTE_FUNCTION: &str = "Function";
+pub const XML_ATTRIBUTE_DESCRIPTION: &str = "Description";
+pub const XML_ATTRIBUTE_ORGANIZATION: &str = "Organization";
+pub const XML_ATTRIBUTE_VERSION: &str = "Version";
+pub const XML_ATTRIBUTE_AUTHOR: &str = "Author";
+pub const XML_ATTRIBUTE_DATE: &str = "Date";
+pub const XML_ATTRIBUTE_REMARKS: &str = "Remarks";
+pub const XML_ATTRIBUTE_PACKAGE_NAME: &str = "packageName";
+pub const XML_ATTRIBUTE_HEADER: &str = "header";
+pub const XML_ATTRIBUTE_CLASSDEF: &str = "classdef";
+pub const XML_ATTRIBUTE_LANGUAGE: &str = "Language";
+pub const XML_ATTRIBUTE_VENDOR: &str = "Vendor";
+pub const XML_ATTRIBUTE_PRODUCT: &str = "Product";
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum BoolLiteral {
@@ -220,6 +582,7 @@ pub enum BoolLiteral {
     Int(bool),
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum IntLiteral {
     SignedDecimalInt(i64),
@@ -0,0 +1,36 @@
+use crate::core::action::Action;
+use crate::core::msg::StructuredType;
+use crate::core::srv::Service;
+
+pub const FILE_EXTENSION: &str = "idl";
+// Constants of a structure "X" are declared in the sibling module "X_Constants"
+pub const CONSTANTS_MODULE_SUFFIX: &str = "_Constants";
+// rosidl adds this member to structures without any fields, since IDL forbids empty ones
+pub const EMPTY_STRUCTURE_MEMBER_NAME: &str = "structure_needs_at_least_one_member";
+
+// Like the files rosidl generates, an IDL file contains exactly one interface,
+// see https://design.ros2.org/articles/idl_interface_definition.html
+#[derive(Debug, Clone)]
+pub enum Interface {
+    Message(StructuredType),
+    Service(Service),
+    Action(Action),
+}
+
+impl Interface {
+    pub fn name(&self) -> &str {
+        match self {
+            Interface::Message(structured_type) => structured_type.name(),
+            Interface::Service(service) => service.name(),
+            Interface::Action(action) => action.name(),
+        }
+    }
+    // The sections of a service or an action, a message consists of one
+    pub fn structured_types(&self) -> Vec<&StructuredType> {
+        match self {
+            Interface::Message(structured_type) => vec![structured_type],
+            Interface::Service(service) => vec![service.request(), service.response()],
+            Interface::Action(action) => vec![action.goal(), action.result(), action.feedback()],
+        }
+    }
+}
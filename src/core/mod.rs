@@ -1,2 +1,7 @@
 pub mod msg;
+pub mod msg_cst;
 pub mod dtp;
+pub mod srv;
+pub mod action;
+pub mod idl;
+pub mod package;
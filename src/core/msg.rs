@@ -1,31 +1,51 @@
+use crate::core::msg_cst::SyntaxTree;
+
+pub const INTERFACE_KIND: &str = "msg";
+
 #[derive(Debug, Clone)]
 pub struct StructuredType {
     name: String,
+    comment: Option<String>,
     fields: Vec<Field>,
+    syntax_tree: Option<SyntaxTree>,
 }
 
 impl StructuredType {
-    pub fn new(name: &str, fields: Vec<Field>) -> Self {
+    pub fn new(
+        name: &str,
+        comment: &Option<String>,
+        fields: Vec<Field>,
+        syntax_tree: &Option<SyntaxTree>,
+    ) -> Self {
         Self {
             name: name.to_string().clone(),
+            comment: comment.clone(),
             fields,
+            syntax_tree: syntax_tree.clone(),
         }
     }
     pub fn name(&self) -> &str {
         &self.name
     }
+    pub fn comment(&self) -> Option<&String> {
+        self.comment.as_ref()
+    }
     pub fn fields(&self) -> &Vec<Field> {
         &self.fields
     }
+    pub fn syntax_tree(&self) -> Option<&SyntaxTree> {
+        self.syntax_tree.as_ref()
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Field {
     base_type: BaseType,
     constraint: Option<Constraint>,
     name: String,
     field_type: FieldType,
     comment: Option<String>,
+    annotations: Vec<Annotation>,
 }
 
 impl Field {
@@ -35,13 +55,15 @@ impl Field {
         name: &str,
         field_type: &FieldType,
         comment: &Option<String>,
+        annotations: &[Annotation],
     ) -> Self {
         Self {
             base_type: base_type.clone(),
             constraint: constraint.clone(),
             name: name.to_string(),
             field_type: field_type.clone(),
-            comment: comment.clone()
+            comment: comment.clone(),
+            annotations: annotations.to_vec(),
         }
     }
     pub fn base_type(&self) -> &BaseType {
@@ -59,9 +81,21 @@ impl Field {
     pub fn comment(&self) -> Option<&String> {
         self.comment.as_ref()
     }
+    pub fn annotations(&self) -> &Vec<Annotation> {
+        &self.annotations
+    }
 }
 
-#[derive(Debug, Clone)]
+// IDL annotations without a MSG counterpart, e.g. "@key" or "@unit (value="m")".
+// "@default" and "@verbatim" are mapped onto the field type and comment instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub name: String,
+    // The raw text between the parentheses
+    pub parameters: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum BaseType {
     Bool,
     Byte,
@@ -87,7 +121,8 @@ pub enum Reference {
     Absolute { package: String, file: String },
 }
 
-#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Constraint {
     StaticArray(usize),
     UnboundedDynamicArray,
@@ -135,6 +170,7 @@ pub enum BoolLiteral {
     Int(bool),
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum IntLiteral {
     SignedDecimalInt(i64),
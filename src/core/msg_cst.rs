@@ -0,0 +1,117 @@
+use crate::core::msg::Field;
+
+// Concrete syntax tree of a message (section). In contrast to the
+// StructuredType it keeps comments, whitespace and the original spelling
+// of every token, so that `text` reproduces the source byte by byte.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxTree {
+    lines: Vec<Line>,
+}
+
+impl SyntaxTree {
+    pub fn new(lines: Vec<Line>) -> Self {
+        Self { lines }
+    }
+    pub fn lines(&self) -> &Vec<Line> {
+        &self.lines
+    }
+    pub fn text(&self) -> String {
+        self.lines.iter().map(Line::text).collect()
+    }
+    // Like rosidl, every comment line before the first other line
+    // documents the whole message.
+    pub fn file_level_comment_lines(&self) -> &[Line] {
+        let count = self
+            .lines
+            .iter()
+            .take_while(|line| line.is_file_level_comment())
+            .count();
+        &self.lines[..count]
+    }
+    pub fn file_level_comment(&self) -> Option<String> {
+        let lines = self.file_level_comment_lines();
+        match lines.is_empty() {
+            false => Some(
+                lines
+                    .iter()
+                    .filter_map(Line::comment_text)
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            true => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub leading_whitespace: String,
+    pub content: LineContent,
+    pub trailing_whitespace: String,
+    // Including the leading '#'
+    pub comment: Option<String>,
+    // Either "", "\n" or "\r\n"
+    pub line_ending: String,
+}
+
+impl Line {
+    pub fn text(&self) -> String {
+        let mut result = self.leading_whitespace.clone();
+        if let LineContent::Field { node, .. } = &self.content {
+            result.push_str(&node.text());
+        }
+        result.push_str(&self.trailing_whitespace);
+        result.push_str(self.comment.as_deref().unwrap_or_default());
+        result.push_str(&self.line_ending);
+        result
+    }
+    pub fn field(&self) -> Option<&Field> {
+        match &self.content {
+            LineContent::Field { field, .. } => Some(field),
+            LineContent::Empty => None,
+        }
+    }
+    pub fn comment_text(&self) -> Option<String> {
+        self.comment
+            .as_ref()
+            .map(|comment| comment.trim_start_matches('#').trim().to_string())
+    }
+    fn is_file_level_comment(&self) -> bool {
+        self.leading_whitespace.is_empty()
+            && self.content == LineContent::Empty
+            && self.comment.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LineContent {
+    Empty,
+    Field { node: FieldNode, field: Box<Field> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldNode {
+    // Including array constraints, e.g. "string<=5[<=3]"
+    pub type_spelling: String,
+    pub separator: String,
+    pub name: String,
+    pub value: Option<ValueNode>,
+}
+
+impl FieldNode {
+    pub fn text(&self) -> String {
+        let mut result = format!("{}{}{}", self.type_spelling, self.separator, self.name);
+        if let Some(value) = &self.value {
+            result.push_str(&value.assignment);
+            result.push_str(&value.spelling);
+        }
+        result
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueNode {
+    // Everything between field name and value, e.g. " " or " = "
+    pub assignment: String,
+    pub spelling: String,
+}
@@ -0,0 +1,74 @@
+pub const FILE_NAME: &str = "package.xml";
+
+pub const XML_TAG_NAME: &str = "name";
+pub const XML_TAG_VERSION: &str = "version";
+pub const XML_TAG_DESCRIPTION: &str = "description";
+pub const XML_TAG_MAINTAINER: &str = "maintainer";
+pub const XML_TAG_LICENSE: &str = "license";
+pub const XML_TAG_DEPEND: &str = "depend";
+pub const XML_TAG_BUILD_DEPEND: &str = "build_depend";
+
+pub const XML_ATTRIBUTE_EMAIL: &str = "email";
+
+// The parts of a ROS 2 package manifest (REP 149), which are needed for the conversion
+#[derive(Debug, Clone)]
+pub struct Package {
+    name: String,
+    version: String,
+    description: String,
+    maintainers: Vec<Person>,
+    licenses: Vec<String>,
+    dependencies: Vec<String>,
+    date: String,
+}
+
+impl Package {
+    pub fn new(
+        name: &str,
+        version: &str,
+        description: &str,
+        maintainers: &[Person],
+        licenses: &[String],
+        dependencies: &[String],
+        date: &str,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            version: version.to_string(),
+            description: description.to_string(),
+            maintainers: maintainers.to_vec(),
+            licenses: licenses.to_vec(),
+            dependencies: dependencies.to_vec(),
+            date: date.to_string(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+    pub fn maintainers(&self) -> &Vec<Person> {
+        &self.maintainers
+    }
+    pub fn licenses(&self) -> &Vec<String> {
+        &self.licenses
+    }
+    // Packages needed to build the interfaces of this package
+    pub fn dependencies(&self) -> &Vec<String> {
+        &self.dependencies
+    }
+    // Last modification of the manifest as yyyy-MM-dd, so converting again gives the same output
+    pub fn date(&self) -> &str {
+        &self.date
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Person {
+    pub name: String,
+    pub email: Option<String>,
+}
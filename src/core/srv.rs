@@ -0,0 +1,31 @@
+use crate::core::msg::StructuredType;
+
+pub const INTERFACE_KIND: &str = "srv";
+pub const REQUEST_SUFFIX: &str = "_Request";
+pub const RESPONSE_SUFFIX: &str = "_Response";
+
+#[derive(Debug, Clone)]
+pub struct Service {
+    name: String,
+    request: StructuredType,
+    response: StructuredType,
+}
+
+impl Service {
+    pub fn new(name: &str, request: &StructuredType, response: &StructuredType) -> Self {
+        Self {
+            name: name.to_string(),
+            request: request.clone(),
+            response: response.clone(),
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn request(&self) -> &StructuredType {
+        &self.request
+    }
+    pub fn response(&self) -> &StructuredType {
+        &self.response
+    }
+}